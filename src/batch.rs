@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use bourso_api::client::transfer::batch::BatchTransfer;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Completed,
+    Failed,
+}
+
+/// Outcome of a transfer of a batch, as a line of its results file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchResult {
+    pub timestamp: DateTime<Local>,
+    /// Run of the batch the transfer belongs to, see [`BatchResults::open`]
+    #[serde(default)]
+    pub run: String,
+    /// Identifies the transfer between runs, see [`batch_keys`]
+    pub key: String,
+    /// Line of the transfer in the CSV file
+    pub line: usize,
+    #[serde(flatten)]
+    pub transfer: BatchTransfer,
+    pub status: BatchStatus,
+    /// Error message of a failed transfer
+    pub message: Option<String>,
+    /// Reference of a completed transfer
+    #[serde(default)]
+    pub reference: Option<String>,
+}
+
+impl BatchResult {
+    pub fn new(
+        key: &str,
+        line: usize,
+        transfer: &BatchTransfer,
+        status: BatchStatus,
+        message: Option<String>,
+    ) -> BatchResult {
+        BatchResult {
+            timestamp: Local::now(),
+            run: String::new(),
+            key: key.to_string(),
+            line,
+            transfer: transfer.clone(),
+            status,
+            message,
+            reference: None,
+        }
+    }
+}
+
+/// Keys identifying the transfers of a batch between runs, whatever their line
+///
+/// The key of a transfer is its id column if set, otherwise its content followed by its
+/// occurrence among the identical transfers of the batch (e.g. `a583...:b419...:150.00:Livret A#1`).
+/// An edited transfer gets a new key, so that it is executed again.
+pub fn batch_keys(transfers: &[BatchTransfer]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for transfer in transfers {
+        let key = match &transfer.id {
+            Some(id) => id.clone(),
+            None => {
+                let content = format!(
+                    "{}:{}:{:.2}:{}",
+                    transfer.from,
+                    transfer.to,
+                    transfer.amount,
+                    transfer.reason.as_deref().unwrap_or_default()
+                );
+                let occurrence = keys
+                    .iter()
+                    .filter(|k| k.rsplit_once('#').map(|(c, _)| c) == Some(content.as_str()))
+                    .count()
+                    + 1;
+                format!("{}#{}", content, occurrence)
+            }
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// Run of a batch when none is given: the current month, e.g. `2026-10`, so that a batch of
+/// monthly transfers is executed again each month
+pub fn default_run() -> String {
+    Local::now().format("%Y-%m").to_string()
+}
+
+/// Append-only results of a run of a batch of transfers, stored as JSON lines next to the
+/// batch file. A transfer completed earlier in the same run is not executed again.
+pub struct BatchResults {
+    path: PathBuf,
+    run: String,
+    results: Vec<BatchResult>,
+}
+
+impl BatchResults {
+    /// Path of the results file of a run of a batch file, e.g. `transfers.csv.2026-10.results.jsonl`
+    pub fn default_path(batch_path: &str, run: &str) -> String {
+        format!("{}.{}.results.jsonl", batch_path, run)
+    }
+
+    /// Open the results of a run at the given path, it will be created on the first record
+    ///
+    /// The results of the other runs stored in the same file are ignored.
+    pub fn open(path: impl AsRef<Path>, run: &str) -> Result<BatchResults> {
+        let path = path.as_ref().to_path_buf();
+        let mut results = Vec::new();
+
+        if path.exists() {
+            let file = fs::File::open(&path)
+                .with_context(|| format!("Failed to open batch results {}", path.display()))?;
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                results.push(serde_json::from_str(&line).with_context(|| {
+                    format!(
+                        "Failed to parse line {} of batch results {}",
+                        i + 1,
+                        path.display()
+                    )
+                })?);
+            }
+        }
+
+        Ok(BatchResults {
+            path,
+            run: run.to_string(),
+            results,
+        })
+    }
+
+    /// Whether the transfer with the given key, see [`batch_keys`], has already been completed
+    /// in this run
+    pub fn is_completed(&self, key: &str) -> bool {
+        self.results
+            .iter()
+            .any(|r| r.run == self.run && r.key == key && r.status == BatchStatus::Completed)
+    }
+
+    /// Append a result of this run to the results file
+    pub fn record(&mut self, result: BatchResult) -> Result<()> {
+        let result = BatchResult {
+            run: self.run.clone(),
+            ..result
+        };
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open batch results {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&result)?)
+            .context("Failed to write batch result")?;
+        self.results.push(result);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_keys() {
+        let transfer = BatchTransfer {
+            from: "a583f3c5842c34fb00b408486ef493e0".to_string(),
+            to: "b419f2d28010bafb331badef11bba51e".to_string(),
+            amount: 100.0,
+            ..Default::default()
+        };
+        let rent = BatchTransfer {
+            id: Some("rent".to_string()),
+            ..transfer.clone()
+        };
+        let keys = batch_keys(&[transfer.clone(), rent.clone(), transfer.clone()]);
+        assert_eq!(
+            keys,
+            vec![
+                "a583f3c5842c34fb00b408486ef493e0:b419f2d28010bafb331badef11bba51e:100.00:#1",
+                "rent",
+                "a583f3c5842c34fb00b408486ef493e0:b419f2d28010bafb331badef11bba51e:100.00:#2",
+            ]
+        );
+
+        // Inserting or removing another row keeps the keys of the transfers
+        let edited = BatchTransfer {
+            amount: 200.0,
+            ..transfer.clone()
+        };
+        let inserted = batch_keys(&[edited, transfer.clone(), rent, transfer]);
+        assert!(inserted[0].contains(":200.00:"));
+        assert_eq!(inserted[1..], keys[..]);
+    }
+
+    #[test]
+    fn test_batch_results() {
+        let path = std::env::temp_dir().join(format!("bourso-batch-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let transfer = BatchTransfer {
+            from: "a583f3c5842c34fb00b408486ef493e0".to_string(),
+            to: "b419f2d28010bafb331badef11bba51e".to_string(),
+            amount: 100.0,
+            ..Default::default()
+        };
+        let key = &batch_keys(std::slice::from_ref(&transfer))[0];
+        let mut results = BatchResults::open(&path, "2026-10").unwrap();
+        results
+            .record(BatchResult::new(
+                key,
+                2,
+                &transfer,
+                BatchStatus::Failed,
+                Some("Setting transfer amount failed".to_string()),
+            ))
+            .unwrap();
+        assert!(!results.is_completed(key));
+        results
+            .record(BatchResult::new(
+                key,
+                2,
+                &transfer,
+                BatchStatus::Completed,
+                None,
+            ))
+            .unwrap();
+
+        let results = BatchResults::open(&path, "2026-10").unwrap();
+        assert!(results.is_completed(key));
+        assert!(!results.is_completed("rent"));
+
+        // The transfers of another run are executed again
+        let results = BatchResults::open(&path, "2026-11").unwrap();
+        assert!(!results.is_completed(key));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
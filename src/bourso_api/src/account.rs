@@ -11,7 +11,7 @@ pub enum AccountKind {
 }
 
 /// A bank account
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Account {
     /// Account id as an hexadecimal string (32 characters)
     pub id: String,
@@ -19,7 +19,7 @@ use tracing::{debug, error, info};
 
 use self::config::{extract_brs_config, Config};
 
-use super::constants::BASE_URL;
+use super::constants::{BASE_URL, DEFAULT_CONCURRENCY};
 
 lazy_static::lazy_static! {
     /// Regex to extract OTP parameters from the authentication payload.
@@ -64,6 +64,8 @@ pub struct BoursoWebClient {
     cookie_store: Arc<CookieStoreMutex>,
     /// Bourso Web current configuration
     pub config: Config,
+    /// Maximum number of requests sent at the same time by the batch methods (e.g. `instrument_quotes`)
+    concurrency: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -121,9 +123,23 @@ impl BoursoWebClient {
             token: String::new(),
             password: String::new(),
             config: Config::default(),
+            concurrency: DEFAULT_CONCURRENCY,
         }
     }
 
+    /// Set the maximum number of requests sent at the same time by the batch methods
+    ///
+    /// # Arguments
+    ///
+    /// * `concurrency` - The number of requests, at least 1
+    pub fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
     /// Get the headers needed to make requests to the Bourso website.
     ///
     /// # Returns
@@ -0,0 +1,385 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use super::feed::InstrumentQuoteResponse;
+
+/// Number of days searched for the next session, long enough to skip any holiday period
+const MAX_CLOSED_DAYS: i64 = 30;
+
+/// Holidays and half-days rules of an exchange
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rules {
+    Euronext,
+    Nyse,
+    /// Only weekends are closed
+    WeekendsOnly,
+}
+
+/// Trading calendar of an exchange: timezone, trading hours, holidays and half-days
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeCalendar {
+    /// Exchange code (e.g: XPAR for Euronext Paris)
+    pub code: String,
+    pub timezone: Tz,
+    /// Opening time, in the exchange timezone
+    pub open: NaiveTime,
+    /// Closing time, in the exchange timezone
+    pub close: NaiveTime,
+    /// Closing time of the half-days (e.g: Christmas Eve), in the exchange timezone
+    pub half_day_close: NaiveTime,
+    rules: Rules,
+}
+
+impl ExchangeCalendar {
+    /// Get the calendar of an exchange by its code
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Exchange code, either its MIC (e.g: XPAR) or the `exchange_code` of an instrument quote
+    ///
+    /// # Returns
+    ///
+    /// The calendar, or None if the exchange is not known
+    pub fn from_exchange_code(code: &str) -> Option<ExchangeCalendar> {
+        let code = code.to_uppercase();
+        let (timezone, open, close, half_day_close, rules) = match code.as_str() {
+            // Euronext Paris, Amsterdam, Brussels and Lisbon
+            "XPAR" | "PAR" | "XAMS" | "AMS" | "XBRU" | "BRU" | "XLIS" | "LIS" => (
+                chrono_tz::Europe::Paris,
+                time(9, 0),
+                time(17, 30),
+                time(14, 5),
+                Rules::Euronext,
+            ),
+            "XMIL" | "MIL" => (
+                chrono_tz::Europe::Rome,
+                time(9, 0),
+                time(17, 30),
+                time(14, 5),
+                Rules::Euronext,
+            ),
+            "XDUB" | "DUB" => (
+                chrono_tz::Europe::Dublin,
+                time(8, 0),
+                time(16, 30),
+                time(12, 28),
+                Rules::Euronext,
+            ),
+            // NYSE and Nasdaq
+            "XNYS" | "NYS" | "NYSE" | "XNAS" | "NAS" | "NASDAQ" => (
+                chrono_tz::America::New_York,
+                time(9, 30),
+                time(16, 0),
+                time(13, 0),
+                Rules::Nyse,
+            ),
+            _ => return None,
+        };
+
+        Some(ExchangeCalendar {
+            code,
+            timezone,
+            open,
+            close,
+            half_day_close,
+            rules,
+        })
+    }
+
+    /// Get the calendar of the exchange of an instrument
+    ///
+    /// Falls back to the trading hours of the quote, in Paris time, when the exchange is not known.
+    /// Only weekends are considered closed in that case.
+    pub fn from_quote(quote: &InstrumentQuoteResponse) -> ExchangeCalendar {
+        if let Some(calendar) = ExchangeCalendar::from_exchange_code(&quote.exchange_code) {
+            return calendar;
+        }
+
+        let open = NaiveTime::parse_from_str(&quote.opening_time, "%H:%M:%S").unwrap_or(time(9, 0));
+        let close =
+            NaiveTime::parse_from_str(&quote.closing_time, "%H:%M:%S").unwrap_or(time(17, 30));
+
+        ExchangeCalendar {
+            code: quote.exchange_code.clone(),
+            timezone: chrono_tz::Europe::Paris,
+            open,
+            close,
+            half_day_close: close,
+            rules: Rules::WeekendsOnly,
+        }
+    }
+
+    /// Whether the exchange is closed all day on the given date
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        match self.rules {
+            Rules::Euronext => euronext_holidays(date.year()).contains(&date),
+            Rules::Nyse => nyse_holidays(date.year()).contains(&date),
+            Rules::WeekendsOnly => false,
+        }
+    }
+
+    /// Whether the exchange closes early on the given date
+    pub fn is_half_day(&self, date: NaiveDate) -> bool {
+        if !self.is_trading_day(date) {
+            return false;
+        }
+        match self.rules {
+            Rules::Euronext => {
+                (date.month(), date.day()) == (12, 24) || (date.month(), date.day()) == (12, 31)
+            }
+            Rules::Nyse => {
+                let thanksgiving = nth_weekday(date.year(), 11, Weekday::Thu, 4);
+                date == thanksgiving + Duration::days(1)
+                    || (date.month(), date.day()) == (12, 24)
+                    || (date.month(), date.day()) == (7, 3)
+            }
+            Rules::WeekendsOnly => false,
+        }
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.is_holiday(date)
+    }
+
+    /// Opening and closing instants of the session on the given date, None if the exchange is closed
+    pub fn session(&self, date: NaiveDate) -> Option<(DateTime<Tz>, DateTime<Tz>)> {
+        if !self.is_trading_day(date) {
+            return None;
+        }
+        let close = if self.is_half_day(date) {
+            self.half_day_close
+        } else {
+            self.close
+        };
+        Some((self.instant(date, self.open), self.instant(date, close)))
+    }
+
+    /// Whether the exchange is open at the given instant
+    pub fn is_open<T: TimeZone>(&self, at: &DateTime<T>) -> bool {
+        let at = at.with_timezone(&self.timezone);
+        match self.session(at.date_naive()) {
+            Some((open, close)) => at >= open && at < close,
+            None => false,
+        }
+    }
+
+    /// Next opening instant after the given instant
+    pub fn next_open<T: TimeZone>(&self, after: &DateTime<T>) -> Option<DateTime<Tz>> {
+        self.sessions_from(after)
+            .map(|(open, _)| open)
+            .find(|open| open > after)
+    }
+
+    /// Next closing instant after the given instant
+    pub fn next_close<T: TimeZone>(&self, after: &DateTime<T>) -> Option<DateTime<Tz>> {
+        self.sessions_from(after)
+            .map(|(_, close)| close)
+            .find(|close| close > after)
+    }
+
+    fn sessions_from<T: TimeZone>(
+        &self,
+        from: &DateTime<T>,
+    ) -> impl Iterator<Item = (DateTime<Tz>, DateTime<Tz>)> + '_ {
+        let start = from.with_timezone(&self.timezone).date_naive();
+        (0..MAX_CLOSED_DAYS).filter_map(move |i| self.session(start + Duration::days(i)))
+    }
+
+    fn instant(&self, date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+        // Trading hours never fall in a DST gap, take the earliest instant if ambiguous
+        self.timezone
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .unwrap_or_else(|| {
+                Utc.from_utc_datetime(&date.and_time(time))
+                    .with_timezone(&self.timezone)
+            })
+    }
+}
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+/// Easter Sunday of the given year (anonymous Gregorian algorithm)
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+/// The n-th (starting at 1) given weekday of a month
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8).unwrap()
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+/// A holiday falling on a Saturday is observed on the Friday, on a Sunday on the Monday
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+fn euronext_holidays(year: i32) -> Vec<NaiveDate> {
+    let easter = easter(year);
+    vec![
+        date(year, 1, 1),
+        // Good Friday and Easter Monday
+        easter - Duration::days(2),
+        easter + Duration::days(1),
+        // Labour Day
+        date(year, 5, 1),
+        date(year, 12, 25),
+        date(year, 12, 26),
+    ]
+}
+
+fn nyse_holidays(year: i32) -> Vec<NaiveDate> {
+    let mut holidays = vec![
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        // Good Friday
+        easter(year) - Duration::days(2),
+        // Memorial Day
+        last_weekday(year, 5, Weekday::Mon),
+        observed(date(year, 7, 4)),
+        // Labor Day
+        nth_weekday(year, 9, Weekday::Mon, 1),
+        // Thanksgiving
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        observed(date(year, 12, 25)),
+    ];
+    // The market stays open on the Friday when New Year's Day is a Saturday
+    if date(year, 1, 1).weekday() != Weekday::Sat {
+        holidays.push(observed(date(year, 1, 1)));
+    }
+    if year >= 2022 {
+        holidays.push(observed(date(year, 6, 19)));
+    }
+    holidays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paris(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Tz> {
+        chrono_tz::Europe::Paris
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_easter() {
+        assert_eq!(easter(2024), date(2024, 3, 31));
+        assert_eq!(easter(2025), date(2025, 4, 20));
+        assert_eq!(easter(2026), date(2026, 4, 5));
+    }
+
+    #[test]
+    fn test_euronext_calendar() {
+        let calendar = ExchangeCalendar::from_exchange_code("xpar").unwrap();
+
+        // Good Friday, Easter Monday, Labour Day and weekends
+        assert!(!calendar.is_trading_day(date(2026, 4, 3)));
+        assert!(!calendar.is_trading_day(date(2026, 4, 6)));
+        assert!(!calendar.is_trading_day(date(2026, 5, 1)));
+        assert!(!calendar.is_trading_day(date(2026, 10, 18)));
+        assert!(calendar.is_trading_day(date(2026, 10, 19)));
+
+        assert!(calendar.is_open(&paris(2026, 10, 19, 9, 0)));
+        assert!(!calendar.is_open(&paris(2026, 10, 19, 17, 30)));
+        // Same instant from another timezone
+        assert!(calendar.is_open(
+            &chrono_tz::America::New_York
+                .with_ymd_and_hms(2026, 10, 19, 8, 0, 0)
+                .unwrap()
+        ));
+
+        // Half-day
+        assert!(calendar.is_half_day(date(2026, 12, 24)));
+        assert!(calendar.is_open(&paris(2026, 12, 24, 14, 0)));
+        assert!(!calendar.is_open(&paris(2026, 12, 24, 15, 0)));
+    }
+
+    #[test]
+    fn test_next_open_and_close() {
+        let calendar = ExchangeCalendar::from_exchange_code("XPAR").unwrap();
+
+        // From a Sunday to Monday's session
+        let sunday = paris(2026, 10, 18, 12, 0);
+        assert_eq!(calendar.next_open(&sunday), Some(paris(2026, 10, 19, 9, 0)));
+        assert_eq!(
+            calendar.next_close(&sunday),
+            Some(paris(2026, 10, 19, 17, 30))
+        );
+
+        // During a session, the next open is the next day
+        let monday = paris(2026, 10, 19, 10, 0);
+        assert_eq!(calendar.next_open(&monday), Some(paris(2026, 10, 20, 9, 0)));
+        assert_eq!(
+            calendar.next_close(&monday),
+            Some(paris(2026, 10, 19, 17, 30))
+        );
+
+        // Over Easter
+        let thursday = paris(2026, 4, 2, 18, 0);
+        assert_eq!(calendar.next_open(&thursday), Some(paris(2026, 4, 7, 9, 0)));
+    }
+
+    #[test]
+    fn test_nyse_calendar() {
+        let calendar = ExchangeCalendar::from_exchange_code("XNYS").unwrap();
+
+        // Thanksgiving and the following half-day
+        assert!(calendar.is_holiday(date(2026, 11, 26)));
+        assert!(calendar.is_half_day(date(2026, 11, 27)));
+        // Independence Day on a Saturday is observed on Friday
+        assert!(calendar.is_holiday(date(2026, 7, 3)));
+        assert!(!calendar.is_half_day(date(2026, 7, 3)));
+        // New Year's Day 2022 on a Saturday, the market was open on Friday
+        assert!(calendar.is_trading_day(date(2021, 12, 31)));
+
+        let new_york = chrono_tz::America::New_York;
+        assert!(calendar.is_open(&new_york.with_ymd_and_hms(2026, 10, 19, 9, 30, 0).unwrap()));
+        assert!(!calendar.is_open(&paris(2026, 10, 19, 15, 0)));
+    }
+
+    #[test]
+    fn test_calendar_from_unknown_quote() {
+        let quote = InstrumentQuoteResponse {
+            exchange_code: "UNKNOWN".to_string(),
+            opening_time: "08:00:00".to_string(),
+            closing_time: "20:00:00".to_string(),
+            ..Default::default()
+        };
+        let calendar = ExchangeCalendar::from_quote(&quote);
+        assert_eq!(calendar.open, time(8, 0));
+        assert!(calendar.is_trading_day(date(2026, 5, 1)));
+        assert!(!calendar.is_trading_day(date(2026, 10, 18)));
+        assert!(calendar.is_open(&paris(2026, 10, 19, 19, 0)));
+    }
+}
@@ -1,5 +1,33 @@
+use std::fmt;
+
 use serde::{Serialize, Deserialize};
 
+#[derive(Debug)]
+pub enum OrderError {
+    /// The amount does not allow to buy a single share at the given price
+    AmountTooLow { amount: f64, price: f64 },
+    InvalidPrice,
+    /// The order was submitted but its confirmation failed, it may have been placed anyway
+    ConfirmationFailed,
+}
+
+impl fmt::Display for OrderError {
+    #[cfg(not(tarpaulin_include))]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderError::AmountTooLow { amount, price } => write!(
+                f,
+                "Amount {:.2} is too low to buy a single share at {:.2} (fees included)",
+                amount, price
+            ),
+            OrderError::InvalidPrice => write!(f, "Could not determine a valid price for the order"),
+            OrderError::ConfirmationFailed => write!(f, "Failed to confirm the order, it may have been placed anyway"),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ErrorResponse {
@@ -1,26 +1,57 @@
-use crate::client::{config::Config, BoursoWebClient};
+use crate::{
+    client::{config::Config, BoursoWebClient},
+    constants::PUBLIC_API_URL,
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use super::calendar::ExchangeCalendar;
+
 impl BoursoWebClient {
+    /// Check if the market of the given symbol is open, according to the calendar of its exchange
     #[cfg(not(tarpaulin_include))]
     pub async fn is_market_open(&self, symbol: &str) -> Result<bool> {
-        let quote = match self.instrument_quote(symbol).await {
-            Ok(quote) => quote,
+        let calendar = match self.exchange_calendar(symbol).await {
+            Ok(calendar) => calendar,
             Err(e) => {
                 return Err(anyhow::anyhow!("Failed to check if market is open: {}", e));
             }
         };
 
-        let opening_time = quote.opening_time;
-        let closing_time = quote.closing_time;
+        Ok(calendar.is_open(&chrono::Utc::now()))
+    }
+
+    /// Get the trading calendar of the exchange on which the given symbol is traded
+    #[cfg(not(tarpaulin_include))]
+    pub async fn exchange_calendar(&self, symbol: &str) -> Result<ExchangeCalendar> {
+        let quote = self.instrument_quote(symbol).await?;
 
-        let current_time = chrono::Local::now().time();
+        Ok(ExchangeCalendar::from_quote(&quote))
+    }
 
-        let opening_time = chrono::NaiveTime::parse_from_str(&opening_time, "%H:%M:%S")?;
-        let closing_time = chrono::NaiveTime::parse_from_str(&closing_time, "%H:%M:%S")?;
+    /// Get the quotes of several instruments, sending at most `concurrency` requests at the same time
+    ///
+    /// # Arguments
+    ///
+    /// * `symbols` - The symbol ids (e.g: '1rTCW8')
+    ///
+    /// # Returns
+    ///
+    /// The quote of each symbol, in the order of the given symbols. A failure for a symbol does
+    /// not prevent the others from being fetched.
+    #[cfg(not(tarpaulin_include))]
+    pub async fn instrument_quotes(
+        &self,
+        symbols: &[&str],
+    ) -> Vec<(String, Result<InstrumentQuoteResponse>)> {
+        use futures_util::StreamExt;
 
-        Ok(current_time >= opening_time && current_time < closing_time)
+        futures_util::stream::iter(symbols.iter().map(|symbol| async move {
+            (symbol.to_string(), self.instrument_quote(symbol).await)
+        }))
+        .buffered(self.concurrency())
+        .collect()
+        .await
     }
 
     #[cfg(not(tarpaulin_include))]
@@ -52,7 +83,12 @@ impl BoursoWebClient {
 }
 
 fn get_feed_base_url(config: &Config) -> Result<String> {
-    Ok(format!("{}/_public_/feed", config.api_url))
+    // The feed is public, it can be used without the configuration fetched at login
+    let api_url = match config.api_url.as_str() {
+        "" => PUBLIC_API_URL,
+        api_url => api_url,
+    };
+    Ok(format!("{}/_public_/feed", api_url))
 }
 
 fn get_instrument_quote_url(config: &Config, symbol: &str) -> Result<String> {
@@ -82,3 +118,25 @@ pub struct InstrumentQuoteResponse {
     pub opening_time: String,
     pub closing_time: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instrument_quote_url() {
+        assert_eq!(
+            get_instrument_quote_url(&Config::default(), "1rTCW8").unwrap(),
+            "https://api.boursobank.com/services/api/v1.7/_public_/feed/instrument/quote/1rTCW8?_host=tradingboard.boursobank.com"
+        );
+
+        let config = Config {
+            api_url: "https://api.example.com".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_feed_base_url(&config).unwrap(),
+            "https://api.example.com/_public_/feed"
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! Technical indicators computed over quotes series
+//!
+//! Moving indicators return one value per input value, aligned with the input. The first values
+//! are `None` until there are enough values to fill the window.
+
+use serde::{Deserialize, Serialize};
+
+use super::tick::{QuoteTab, TickPeriod};
+
+/// Trading days in a year, used to annualize the volatility of daily quotes
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+/// Trading minutes in a day on Euronext (09:00 to 17:30)
+const TRADING_MINUTES_PER_DAY: f64 = 510.0;
+
+/// MACD (Moving Average Convergence Divergence) values
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Macd {
+    /// Fast EMA minus slow EMA
+    pub macd: f64,
+    /// EMA of the MACD
+    pub signal: f64,
+    /// MACD minus signal
+    pub histogram: f64,
+}
+
+/// Bollinger bands values
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bollinger {
+    pub lower: f64,
+    /// SMA of the window
+    pub middle: f64,
+    pub upper: f64,
+}
+
+/// Closing prices of the quotes
+pub fn closes(quotes: &[QuoteTab]) -> Vec<f64> {
+    quotes.iter().map(|q| q.close).collect()
+}
+
+/// Simple moving average
+pub fn sma(values: &[f64], window: usize) -> Vec<Option<f64>> {
+    if window == 0 {
+        return vec![None; values.len()];
+    }
+    (0..values.len())
+        .map(|i| {
+            if i + 1 < window {
+                return None;
+            }
+            Some(values[i + 1 - window..=i].iter().sum::<f64>() / window as f64)
+        })
+        .collect()
+}
+
+/// Exponential moving average, seeded with the SMA of the first window
+pub fn ema(values: &[f64], window: usize) -> Vec<Option<f64>> {
+    let alpha = 2.0 / (window as f64 + 1.0);
+    smoothed(values, window, alpha)
+}
+
+/// Relative Strength Index, between 0 and 100, using Wilder's smoothing
+pub fn rsi(values: &[f64], window: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if window == 0 || values.len() <= window {
+        return result;
+    }
+
+    let changes = values.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+    let mut gain = changes[..window].iter().map(|c| c.max(0.0)).sum::<f64>() / window as f64;
+    let mut loss = changes[..window].iter().map(|c| (-c).max(0.0)).sum::<f64>() / window as f64;
+    result[window] = Some(rsi_value(gain, loss));
+
+    for (i, change) in changes.iter().enumerate().skip(window) {
+        gain = (gain * (window - 1) as f64 + change.max(0.0)) / window as f64;
+        loss = (loss * (window - 1) as f64 + (-change).max(0.0)) / window as f64;
+        result[i + 1] = Some(rsi_value(gain, loss));
+    }
+
+    result
+}
+
+/// Moving Average Convergence Divergence (usually 12, 26, 9)
+pub fn macd(values: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<Option<Macd>> {
+    let fast = ema(values, fast);
+    let slow = ema(values, slow);
+    let lines = fast
+        .iter()
+        .zip(slow.iter())
+        .map(|(f, s)| Some((*f)? - (*s)?))
+        .collect::<Vec<_>>();
+
+    // The signal line is computed over the defined MACD values only
+    let start = lines
+        .iter()
+        .position(|l| l.is_some())
+        .unwrap_or(lines.len());
+    let defined = lines[start..]
+        .iter()
+        .map(|l| l.unwrap())
+        .collect::<Vec<_>>();
+    let signals = ema(&defined, signal);
+
+    let mut result = vec![None; values.len()];
+    for (i, (line, signal)) in defined.iter().zip(signals).enumerate() {
+        if let Some(signal) = signal {
+            result[start + i] = Some(Macd {
+                macd: *line,
+                signal,
+                histogram: line - signal,
+            });
+        }
+    }
+    result
+}
+
+/// Bollinger bands: SMA of the window plus and minus `k` standard deviations (usually 20, 2)
+pub fn bollinger(values: &[f64], window: usize, k: f64) -> Vec<Option<Bollinger>> {
+    sma(values, window)
+        .into_iter()
+        .enumerate()
+        .map(|(i, middle)| {
+            let middle = middle?;
+            let variance = values[i + 1 - window..=i]
+                .iter()
+                .map(|v| (v - middle).powi(2))
+                .sum::<f64>()
+                / window as f64;
+            let deviation = variance.sqrt();
+            Some(Bollinger {
+                lower: middle - k * deviation,
+                middle,
+                upper: middle + k * deviation,
+            })
+        })
+        .collect()
+}
+
+/// Average True Range, using Wilder's smoothing
+pub fn atr(quotes: &[QuoteTab], window: usize) -> Vec<Option<f64>> {
+    let true_ranges = quotes
+        .iter()
+        .enumerate()
+        .map(|(i, q)| match i {
+            0 => q.high - q.low,
+            _ => {
+                let previous = quotes[i - 1].close;
+                (q.high - q.low)
+                    .max((q.high - previous).abs())
+                    .max((q.low - previous).abs())
+            }
+        })
+        .collect::<Vec<_>>();
+
+    smoothed(&true_ranges, window, 1.0 / window as f64)
+}
+
+/// Largest decline from a peak, between 0 and 1 (e.g: 0.25 for -25%)
+pub fn max_drawdown(values: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut drawdown: f64 = 0.0;
+    for value in values {
+        peak = peak.max(*value);
+        if peak > 0.0 {
+            drawdown = drawdown.max((peak - value) / peak);
+        }
+    }
+    drawdown
+}
+
+/// Annualized volatility: standard deviation of the log returns, scaled to a year
+///
+/// # Arguments
+///
+/// * `values` - Prices
+/// * `periods_per_year` - Number of prices in a year, see `periods_per_year`
+pub fn volatility(values: &[f64], periods_per_year: f64) -> Option<f64> {
+    let returns = values
+        .windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| (w[1] / w[0]).ln())
+        .collect::<Vec<_>>();
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Some(variance.sqrt() * periods_per_year.sqrt())
+}
+
+/// Return over the last `window` periods (e.g: 0.05 for +5%)
+pub fn returns(values: &[f64], window: usize) -> Option<f64> {
+    if window == 0 || values.len() <= window {
+        return None;
+    }
+    let last = values[values.len() - 1];
+    let first = values[values.len() - 1 - window];
+    if first == 0.0 {
+        return None;
+    }
+    Some(last / first - 1.0)
+}
+
+/// Number of quotes of the given period in a trading year
+pub fn periods_per_year(period: TickPeriod) -> f64 {
+    match period {
+        TickPeriod::Minutes(minutes) => {
+            TRADING_DAYS_PER_YEAR * TRADING_MINUTES_PER_DAY / minutes.max(1) as f64
+        }
+        TickPeriod::Hourly => TRADING_DAYS_PER_YEAR * TRADING_MINUTES_PER_DAY / 60.0,
+        TickPeriod::Daily => TRADING_DAYS_PER_YEAR,
+        TickPeriod::Weekly => 52.0,
+        TickPeriod::Monthly => 12.0,
+    }
+}
+
+/// Last defined value of a moving indicator
+pub fn last<T: Copy>(values: &[Option<T>]) -> Option<T> {
+    values.last().copied().flatten()
+}
+
+/// Exponential smoothing seeded with the average of the first window
+fn smoothed(values: &[f64], window: usize, alpha: f64) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if window == 0 || values.len() < window {
+        return result;
+    }
+
+    let mut current = values[..window].iter().sum::<f64>() / window as f64;
+    result[window - 1] = Some(current);
+    for i in window..values.len() {
+        current = alpha * values[i] + (1.0 - alpha) * current;
+        result[i] = Some(current);
+    }
+    result
+}
+
+fn rsi_value(gain: f64, loss: f64) -> f64 {
+    if loss == 0.0 {
+        return 100.0;
+    }
+    100.0 - 100.0 / (1.0 + gain / loss)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: Option<f64>, expected: f64) {
+        let value = value.unwrap();
+        assert!(
+            (value - expected).abs() < 1e-6,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_sma_and_ema() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        let sma = sma(&values, 3);
+        assert_eq!(sma[..2], [None, None]);
+        assert_close(sma[2], 2.0);
+        assert_close(sma[4], 4.0);
+
+        let ema = ema(&values, 3);
+        assert_eq!(ema[1], None);
+        assert_close(ema[2], 2.0);
+        // alpha = 0.5
+        assert_close(ema[3], 3.0);
+        assert_close(ema[4], 4.0);
+
+        assert_eq!(super::sma(&values, 6), vec![None; 5]);
+    }
+
+    #[test]
+    fn test_rsi() {
+        let values = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00,
+        ];
+        let rsi = rsi(&values, 14);
+        assert_eq!(rsi[13], None);
+        assert_close(rsi[14], 70.464135);
+        assert_close(rsi[15], 66.249619);
+
+        // Only gains
+        assert_close(last(&super::rsi(&[1.0, 2.0, 3.0], 2)), 100.0);
+    }
+
+    #[test]
+    fn test_macd() {
+        let values = (1..=40).map(|v| v as f64).collect::<Vec<_>>();
+        let macd = macd(&values, 12, 26, 9);
+        // The slow EMA is defined from the 26th value, the signal 8 values later
+        assert_eq!(macd[32], None);
+        let last = last(&macd).unwrap();
+        // On a linear series both EMAs lag by (window - 1) / 2
+        assert!((last.macd - 7.0).abs() < 1e-6);
+        assert!(last.histogram.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bollinger() {
+        let bands = bollinger(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0], 8, 2.0);
+        let bands = last(&bands).unwrap();
+        assert_close(Some(bands.middle), 5.0);
+        assert_close(Some(bands.lower), 1.0);
+        assert_close(Some(bands.upper), 9.0);
+    }
+
+    #[test]
+    fn test_atr() {
+        let quote = |high: f64, low: f64, close: f64| QuoteTab {
+            high,
+            low,
+            close,
+            ..Default::default()
+        };
+        let quotes = [
+            quote(10.0, 8.0, 9.0),
+            quote(11.0, 9.0, 10.0),
+            // Gap: the true range is from the previous close
+            quote(14.0, 13.0, 13.5),
+        ];
+        let atr = atr(&quotes, 2);
+        assert_eq!(atr[0], None);
+        assert_close(atr[1], 2.0);
+        assert_close(atr[2], 3.0);
+    }
+
+    #[test]
+    fn test_drawdown_volatility_and_returns() {
+        assert_close(
+            Some(max_drawdown(&[100.0, 120.0, 90.0, 110.0, 80.0])),
+            1.0 / 3.0,
+        );
+        assert_eq!(max_drawdown(&[1.0, 2.0, 3.0]), 0.0);
+
+        assert_close(volatility(&[100.0, 100.0, 100.0], 252.0), 0.0);
+        let up_down = volatility(&[100.0, 110.0, 100.0, 110.0], 252.0).unwrap();
+        assert!(up_down > 1.0);
+        assert_eq!(volatility(&[100.0], 252.0), None);
+
+        assert_close(returns(&[100.0, 105.0, 110.0], 2), 0.1);
+        assert_close(returns(&[100.0, 105.0, 110.0], 1), 110.0 / 105.0 - 1.0);
+        assert_eq!(returns(&[100.0], 1), None);
+
+        assert_eq!(periods_per_year(TickPeriod::Daily), 252.0);
+    }
+}
@@ -1,8 +1,14 @@
+pub mod calendar;
 pub mod error;
 pub mod feed;
+pub mod indicators;
 pub mod order;
+pub mod portfolio;
+pub mod search;
 pub mod tick;
 
+use core::fmt;
+
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
@@ -100,6 +106,17 @@ pub struct SummaryValue {
     pub currency: Option<String>,
 }
 
+impl fmt::Display for SummaryValue {
+    /// Format the value with the number of decimals given by Bourso, followed by its currency if any
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.*}", self.decimals as usize, self.value)?;
+        if let Some(currency) = &self.currency {
+            write!(f, " {}", currency)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AccountSummary {
@@ -8,7 +8,7 @@ use crate::{
     client::config::Config,
 };
 
-use super::{get_trading_base_url, BoursoWebClient};
+use super::{error::OrderError, get_trading_base_url, BoursoWebClient};
 
 impl BoursoWebClient {
     /// Place an order
@@ -31,6 +31,48 @@ impl BoursoWebClient {
         symbol: &str,
         quantity: usize,
         order_data: Option<OrderData>,
+    ) -> Result<(String, Option<f64>)> {
+        self.place_order(side, account, symbol, quantity, order_data, None)
+            .await
+    }
+
+    /// Place a limit order
+    ///
+    /// The order is filled with the data fetched from Bourso API, and the price limit is set on top of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - Order side (buy or sell)
+    /// * `account` - Account to use. Must be a trading account
+    /// * `symbol` - Symbol to trade
+    /// * `quantity` - Quantity to trade
+    /// * `price_limit` - Price limit of the order
+    ///
+    /// # Returns
+    /// Order ID and order price limit
+    #[cfg(not(tarpaulin_include))]
+    pub async fn limit_order(
+        &self,
+        side: OrderSide,
+        account: &Account,
+        symbol: &str,
+        quantity: usize,
+        price_limit: f64,
+    ) -> Result<(String, Option<f64>)> {
+        self.place_order(side, account, symbol, quantity, None, Some(price_limit))
+            .await
+    }
+
+    /// Place an order, see [`BoursoWebClient::order`] and [`BoursoWebClient::limit_order`]
+    #[cfg(not(tarpaulin_include))]
+    async fn place_order(
+        &self,
+        side: OrderSide,
+        account: &Account,
+        symbol: &str,
+        quantity: usize,
+        order_data: Option<OrderData>,
+        price_limit: Option<f64>,
     ) -> Result<(String, Option<f64>)> {
         if account.kind != AccountKind::Trading {
             return Err(anyhow::anyhow!("Account is not a trading account"));
@@ -40,45 +82,7 @@ impl BoursoWebClient {
 
         debug!("Prepare data {:#?}", response);
 
-        // Either the order data set by the user
-        // or a prefilled data object fetched from Bourso API
-        let mut order_data = match order_data {
-            Some(data) => data,
-            None => response.prefill_order_data.clone(),
-        };
-
-        let last_price = response.symbol.last_price;
-
-        // As either the data received by Bourso API or the data given by the user can contain
-        // and order quantity set to none, we forcefully define it here
-        if order_data.order_quantity.is_none() {
-            order_data.order_quantity = Some(quantity);
-        }
-
-        if order_data.order_price_limit.is_none() && order_data.order_type == OrderKind::Limit {
-            if order_data.order_amount.is_some() {
-                // Use quoted market price or given user price
-                order_data.order_price_limit = order_data.order_amount;
-            } else {
-                // Use the last price fetched
-                order_data.order_price_limit = Some(last_price);
-            }
-        } // else TODO: other types of orders data definition
-
-        if order_data.order_side.is_none() {
-            order_data.order_side = Some(side);
-        }
-
-        if order_data.order_expiration_date.is_none() {
-            // Set expiration date to date given by the API
-            order_data.order_expiration_date = response.prefill_order_data.order_validity;
-        } else {
-            // Set order_data.order_expiration_date to today
-            order_data.order_expiration_date =
-                Some(chrono::Utc::now().format("%Y-%m-%d").to_string());
-        }
-
-        order_data.resource_id = Some(response.resource_id);
+        let order_data = build_order_data(side, &response, quantity, order_data, price_limit);
 
         debug!("Order data: {:#?}", order_data);
 
@@ -86,7 +90,8 @@ impl BoursoWebClient {
 
         let response = self
             .confirm(&order_data.resource_id.as_ref().unwrap())
-            .await?;
+            .await
+            .context(OrderError::ConfirmationFailed)?;
 
         info!(
             quantity,
@@ -103,6 +108,158 @@ impl BoursoWebClient {
         Ok((response.order_id, order_data.order_price_limit))
     }
 
+    /// Place an order for a cash amount instead of a quantity
+    ///
+    /// The quantity is the largest whole number of shares whose cost, including the fees
+    /// estimated by Bourso at the check step, fits in the given amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - Order side (buy or sell)
+    /// * `account` - Account to use. Must be a trading account
+    /// * `symbol` - Symbol to trade
+    /// * `amount` - Amount to invest in the account currency (e.g: 300.0 for 300 EUR)
+    /// * `price_limit` - Price limit of a limit order. If set, it is used to compute the quantity
+    ///   instead of the last price of the symbol
+    ///
+    /// # Returns
+    /// The passed order, with its quantity and estimated cost
+    #[cfg(not(tarpaulin_include))]
+    pub async fn order_by_amount(
+        &self,
+        side: OrderSide,
+        account: &Account,
+        symbol: &str,
+        amount: f64,
+        price_limit: Option<f64>,
+    ) -> Result<AmountOrder> {
+        if account.kind != AccountKind::Trading {
+            return Err(anyhow::anyhow!("Account is not a trading account"));
+        }
+
+        let response = self.prepare(account, symbol).await?;
+
+        debug!("Prepare data {:#?}", response);
+
+        let price = price_limit.unwrap_or(response.symbol.last_price);
+
+        // Start without fees, then lower the quantity until the estimated fees fit in the amount
+        let mut quantity = quantity_for_amount(side, amount, price, 0.0)?;
+        let (order_data, cost) = loop {
+            let mut data = build_order_data(side, &response, quantity, None, price_limit);
+            data.order_quantity = Some(quantity);
+            let check = self.check(&data).await?;
+            let fees = check.check_order_data.total_estimated_fees();
+            let cost = order_cost(side, quantity, price, fees);
+
+            debug!(
+                "Checked order for {} {}: price {}, fees {}, cost {}",
+                quantity, symbol, price, fees, cost
+            );
+
+            if cost <= amount {
+                break (data, cost);
+            }
+
+            quantity = quantity_for_amount(side, amount, price, fees)?.min(quantity - 1);
+            if quantity == 0 {
+                return Err(OrderError::AmountTooLow { amount, price }.into());
+            }
+        };
+
+        let confirm = self
+            .confirm(order_data.resource_id.as_ref().unwrap())
+            .await
+            .context(OrderError::ConfirmationFailed)?;
+
+        info!(
+            quantity,
+            symbol,
+            amount,
+            cost,
+            order_id = confirm.order_id,
+            order_price_limit = order_data.order_price_limit,
+            "Order for {} {} ({:.2} out of {:.2}) successfully passed with ID {} at price {:?} ✅",
+            quantity,
+            symbol,
+            cost,
+            amount,
+            confirm.order_id,
+            order_data.order_price_limit
+        );
+
+        Ok(AmountOrder {
+            order_id: confirm.order_id,
+            order_price_limit: order_data.order_price_limit,
+            quantity,
+            cost,
+        })
+    }
+
+    /// Estimate the price and fees of an order without placing it
+    ///
+    /// The order is prepared and checked by Bourso, but never confirmed.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - Order side (buy or sell)
+    /// * `account` - Account to use. Must be a trading account
+    /// * `symbol` - Symbol to trade
+    /// * `quantity` - Quantity to trade
+    ///
+    /// # Returns
+    /// The estimated price, fees and cost of the order
+    #[cfg(not(tarpaulin_include))]
+    pub async fn estimate_order(
+        &self,
+        side: OrderSide,
+        account: &Account,
+        symbol: &str,
+        quantity: usize,
+    ) -> Result<OrderEstimate> {
+        if account.kind != AccountKind::Trading {
+            return Err(anyhow::anyhow!("Account is not a trading account"));
+        }
+
+        let response = self.prepare(account, symbol).await?;
+        let mut order_data = build_order_data(side, &response, quantity, None, None);
+        // The prefilled data may already contain a quantity
+        order_data.order_quantity = Some(quantity);
+        let check = self.check(&order_data).await?;
+
+        let price = order_data
+            .order_price_limit
+            .unwrap_or(response.symbol.last_price);
+        let fees = check.check_order_data.total_estimated_fees();
+
+        Ok(OrderEstimate {
+            price,
+            fees,
+            cost: order_cost(side, quantity, price, fees),
+        })
+    }
+
+    /// Fetch the data Bourso returns before placing an order, without placing it
+    ///
+    /// Useful to read the account type (PEA, CTO...) and its fiscality (latent and realized gains)
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - Account to use. Must be a trading account
+    /// * `symbol` - Any symbol tradable on the account
+    ///
+    /// # Returns
+    ///
+    /// An order prepare response
+    #[cfg(not(tarpaulin_include))]
+    pub async fn prepare_order(
+        &self,
+        account: &Account,
+        symbol: &str,
+    ) -> Result<OrderPrepareResponse> {
+        self.prepare(account, symbol).await
+    }
+
     /// Prepare an order
     ///
     /// This will fetch trading data for the given symbol
@@ -256,6 +413,108 @@ impl BoursoWebClient {
     }
 }
 
+/// Fill the order data with the given parameters and the data fetched at the prepare step
+///
+/// # Arguments
+///
+/// * `side` - Order side (buy or sell)
+/// * `response` - Response of the `/order/prepare` endpoint
+/// * `quantity` - Quantity to trade
+/// * `order_data` - Order data. If not set, the prefilled data of the prepare response is used
+/// * `price_limit` - Price limit of a limit order, set on top of the order data
+fn build_order_data(
+    side: OrderSide,
+    response: &OrderPrepareResponse,
+    quantity: usize,
+    order_data: Option<OrderData>,
+    price_limit: Option<f64>,
+) -> OrderData {
+    // Either the order data set by the user
+    // or a prefilled data object fetched from Bourso API
+    let mut order_data = match order_data {
+        Some(data) => data,
+        None => response.prefill_order_data.clone(),
+    };
+
+    if let Some(price_limit) = price_limit {
+        order_data.order_type = OrderKind::Limit;
+        order_data.order_price_limit = Some(price_limit);
+    }
+
+    let last_price = response.symbol.last_price;
+
+    // As either the data received by Bourso API or the data given by the user can contain
+    // and order quantity set to none, we forcefully define it here
+    if order_data.order_quantity.is_none() {
+        order_data.order_quantity = Some(quantity);
+    }
+
+    if order_data.order_price_limit.is_none() && order_data.order_type == OrderKind::Limit {
+        if order_data.order_amount.is_some() {
+            // Use quoted market price or given user price
+            order_data.order_price_limit = order_data.order_amount;
+        } else {
+            // Use the last price fetched
+            order_data.order_price_limit = Some(last_price);
+        }
+    } // else TODO: other types of orders data definition
+
+    if order_data.order_side.is_none() {
+        order_data.order_side = Some(side);
+    }
+
+    if order_data.order_expiration_date.is_none() {
+        // Set expiration date to date given by the API
+        order_data.order_expiration_date = response.prefill_order_data.order_validity.clone();
+    } else {
+        // Set order_data.order_expiration_date to today
+        order_data.order_expiration_date = Some(chrono::Utc::now().format("%Y-%m-%d").to_string());
+    }
+
+    order_data.resource_id = Some(response.resource_id.clone());
+
+    order_data
+}
+
+/// Cost of an order. Fees are added to the cost of a buy order, the proceeds of a sell order
+/// are the gross value of the shares
+fn order_cost(side: OrderSide, quantity: usize, price: f64, fees: f64) -> f64 {
+    match side {
+        OrderSide::Buy => quantity as f64 * price + fees,
+        OrderSide::Sell => quantity as f64 * price,
+    }
+}
+
+/// Compute the largest whole quantity whose cost fits in the given amount
+///
+/// # Arguments
+///
+/// * `side` - Order side (buy or sell)
+/// * `amount` - Amount available
+/// * `price` - Price of a share
+/// * `fees` - Fees estimated for the order
+///
+/// # Returns
+/// The quantity, or an `OrderError` if not a single share can be traded
+pub fn quantity_for_amount(side: OrderSide, amount: f64, price: f64, fees: f64) -> Result<usize> {
+    if price.is_nan() || price <= 0.0 {
+        return Err(OrderError::InvalidPrice.into());
+    }
+
+    let available = match side {
+        OrderSide::Buy => amount - fees,
+        OrderSide::Sell => amount,
+    };
+    // Round before flooring to avoid losing a share to floating point errors (e.g: 300.0 / 30.0)
+    let quantity = ((available / price * 1e6).round() / 1e6).floor();
+
+    if quantity < 1.0 {
+        return Err(OrderError::AmountTooLow { amount, price }.into());
+    }
+
+    Ok(quantity as usize)
+}
+
 fn get_order_url(config: &Config) -> Result<String> {
     let trading_url = get_trading_base_url(config)?;
 
@@ -535,6 +794,30 @@ pub enum OrderSide {
     Sell,
 }
 
+/// An order placed for a cash amount, see `BoursoWebClient::order_by_amount`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmountOrder {
+    pub order_id: String,
+    pub order_price_limit: Option<f64>,
+    /// Quantity computed from the amount
+    pub quantity: usize,
+    /// Estimated cost of the order, fees included for a buy order
+    pub cost: f64,
+}
+
+/// Estimation of an order, see `BoursoWebClient::estimate_order`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderEstimate {
+    /// Price limit of the order, or last price of the symbol
+    pub price: f64,
+    /// Fees estimated at the `/ordersimple/check` endpoint
+    pub fees: f64,
+    /// Estimated cost of the order, fees included for a buy order
+    pub cost: f64,
+}
+
 /// Order data submitted to the `/ordersimple/check` endpoint
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct OrderData {
@@ -585,6 +868,16 @@ pub struct OrderData {
     pub estimated_balance: Option<f64>,
 }
 
+impl OrderData {
+    /// Sum of the fees estimated at the `/ordersimple/check` endpoint
+    pub fn total_estimated_fees(&self) -> f64 {
+        self.estimated_fees
+            .as_ref()
+            .map(|fees| fees.iter().map(|fee| fee.amount).sum())
+            .unwrap_or(0.0)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderCheckResponse {
@@ -679,3 +972,109 @@ pub struct ActionApiParams {
     pub account_type: String,
     pub account_key: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare_response(last_price: f64) -> OrderPrepareResponse {
+        OrderPrepareResponse {
+            resource_id: "resource".to_string(),
+            symbol: Symbol {
+                symbol: "1rTCW8".to_string(),
+                last_price,
+                ..Default::default()
+            },
+            prefill_order_data: OrderData {
+                order_risk_mode: "risk-mode".to_string(),
+                order_validity: Some("2024-03-05".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quantity_for_amount() {
+        assert_eq!(
+            quantity_for_amount(OrderSide::Buy, 300.0, 30.0, 0.0).unwrap(),
+            10
+        );
+        assert_eq!(
+            quantity_for_amount(OrderSide::Buy, 300.0, 30.0, 1.99).unwrap(),
+            9
+        );
+        assert_eq!(
+            quantity_for_amount(OrderSide::Buy, 300.0, 29.36, 0.0).unwrap(),
+            10
+        );
+        assert_eq!(
+            quantity_for_amount(OrderSide::Sell, 300.0, 30.0, 1.99).unwrap(),
+            10
+        );
+        assert_eq!(
+            quantity_for_amount(OrderSide::Buy, 0.3, 0.1, 0.0).unwrap(),
+            3
+        );
+
+        let err = quantity_for_amount(OrderSide::Buy, 300.0, 450.0, 0.0).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<OrderError>(),
+            Some(OrderError::AmountTooLow { .. })
+        ));
+        let err = quantity_for_amount(OrderSide::Buy, 300.0, 0.0, 0.0).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<OrderError>(),
+            Some(OrderError::InvalidPrice)
+        ));
+    }
+
+    #[test]
+    fn test_order_cost() {
+        assert_eq!(order_cost(OrderSide::Buy, 9, 30.0, 1.99), 271.99);
+        assert_eq!(order_cost(OrderSide::Sell, 9, 30.0, 1.99), 270.0);
+    }
+
+    #[test]
+    fn test_total_estimated_fees() {
+        let mut data = OrderData::default();
+        assert_eq!(data.total_estimated_fees(), 0.0);
+        data.estimated_fees = Some(vec![
+            EstimatedFee {
+                amount: 1.99,
+                ..Default::default()
+            },
+            EstimatedFee {
+                amount: 0.5,
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(data.total_estimated_fees(), 2.49);
+    }
+
+    #[test]
+    fn test_build_order_data() {
+        let response = prepare_response(29.36);
+        let data = build_order_data(OrderSide::Buy, &response, 4, None, None);
+        assert_eq!(data.order_quantity, Some(4));
+        assert_eq!(data.order_side, Some(OrderSide::Buy));
+        assert_eq!(data.order_price_limit, Some(29.36));
+        assert_eq!(data.order_expiration_date, Some("2024-03-05".to_string()));
+        assert_eq!(data.resource_id, Some("resource".to_string()));
+
+        let user_data = OrderData {
+            order_amount: Some(28.0),
+            ..Default::default()
+        };
+        let data = build_order_data(OrderSide::Sell, &response, 2, Some(user_data), None);
+        assert_eq!(data.order_side, Some(OrderSide::Sell));
+        assert_eq!(data.order_price_limit, Some(28.0));
+
+        // The price limit keeps the rest of the prefilled data
+        let data = build_order_data(OrderSide::Buy, &response, 4, None, Some(27.5));
+        assert_eq!(data.order_type, OrderKind::Limit);
+        assert_eq!(data.order_price_limit, Some(27.5));
+        assert_eq!(data.order_risk_mode, "risk-mode");
+        assert_eq!(data.order_expiration_date, Some("2024-03-05".to_string()));
+    }
+}
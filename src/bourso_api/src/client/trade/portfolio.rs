@@ -0,0 +1,282 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Account, AccountKind},
+    client::BoursoWebClient,
+};
+
+use super::{AccountSummary, PositionSummary, TradingSummaryItem};
+
+impl BoursoWebClient {
+    /// Get the portfolio of the given trading accounts
+    ///
+    /// Non trading accounts are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `accounts` - Accounts to fetch the trading summary for (e.g: your PEA and CTO)
+    ///
+    /// # Returns
+    ///
+    /// The positions and account summaries of every trading account
+    #[cfg(not(tarpaulin_include))]
+    pub async fn get_portfolio(&self, accounts: &[Account]) -> Result<Portfolio> {
+        let mut portfolio = Portfolio::default();
+
+        for account in accounts.iter().filter(|a| a.kind == AccountKind::Trading) {
+            let summary = self.get_trading_summary(account.clone()).await?;
+            portfolio
+                .accounts
+                .push(PortfolioAccount::from_summary(account.clone(), summary));
+        }
+
+        Ok(portfolio)
+    }
+}
+
+/// Positions held across one or several trading accounts
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub accounts: Vec<PortfolioAccount>,
+}
+
+/// A trading account with its summary and positions
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioAccount {
+    pub account: Account,
+    /// Will be None if Bourso did not return an "account" item
+    pub summary: Option<AccountSummary>,
+    pub positions: Vec<PositionSummary>,
+}
+
+/// Totals computed over a set of positions
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioTotals {
+    /// Sum of the accounts cash
+    pub cash: f64,
+    /// Market value of the positions
+    pub valuation: f64,
+    /// Cost of the positions (quantity * buying price)
+    pub cost: f64,
+    pub gain_loss: f64,
+    pub gain_loss_percent: f64,
+    /// Cash deposited on the accounts
+    pub contribution: f64,
+}
+
+impl PortfolioAccount {
+    /// Build a portfolio account from the items returned by `get_trading_summary`
+    pub fn from_summary(account: Account, items: Vec<TradingSummaryItem>) -> Self {
+        let mut portfolio_account = PortfolioAccount {
+            account,
+            summary: None,
+            positions: Vec::new(),
+        };
+
+        for item in items {
+            if let Some(summary) = item.account {
+                portfolio_account.summary = Some(summary);
+            }
+            if let Some(positions) = item.positions {
+                portfolio_account.positions.extend(positions);
+            }
+        }
+
+        portfolio_account
+    }
+
+    /// Market value of the account positions
+    pub fn valuation(&self) -> f64 {
+        self.positions.iter().map(|p| p.amount.value).sum()
+    }
+
+    /// Weight of a position within this account, between 0 and 1
+    pub fn weight(&self, position: &PositionSummary) -> f64 {
+        weight(position, self.valuation())
+    }
+
+    pub fn totals(&self) -> PortfolioTotals {
+        totals(std::slice::from_ref(self))
+    }
+}
+
+impl Portfolio {
+    /// Iterate over every position along with the account holding it
+    pub fn positions(&self) -> impl Iterator<Item = (&PortfolioAccount, &PositionSummary)> {
+        self.accounts
+            .iter()
+            .flat_map(|a| a.positions.iter().map(move |p| (a, p)))
+    }
+
+    /// Market value of all the positions
+    pub fn valuation(&self) -> f64 {
+        self.accounts.iter().map(|a| a.valuation()).sum()
+    }
+
+    /// Weight of a position across all the accounts, between 0 and 1
+    pub fn weight(&self, position: &PositionSummary) -> f64 {
+        weight(position, self.valuation())
+    }
+
+    /// Totals across all the accounts (e.g: PEA + CTO)
+    pub fn totals(&self) -> PortfolioTotals {
+        totals(&self.accounts)
+    }
+}
+
+fn weight(position: &PositionSummary, valuation: f64) -> f64 {
+    if valuation == 0.0 {
+        return 0.0;
+    }
+    position.amount.value / valuation
+}
+
+fn totals(accounts: &[PortfolioAccount]) -> PortfolioTotals {
+    let mut totals = PortfolioTotals::default();
+
+    for account in accounts {
+        if let Some(summary) = &account.summary {
+            totals.cash += summary.cash.value;
+            totals.contribution += summary.contribution;
+        }
+        for position in account.positions.iter() {
+            totals.valuation += position.amount.value;
+            totals.cost += position.quantity.value * position.buying_price.value;
+            totals.gain_loss += position.gain_loss.value;
+        }
+    }
+
+    if totals.cost != 0.0 {
+        totals.gain_loss_percent = totals.gain_loss / totals.cost * 100.0;
+    }
+
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::trade::SummaryValue;
+
+    fn value(value: f64, decimals: u64) -> SummaryValue {
+        SummaryValue {
+            value,
+            decimals,
+            currency: Some("EUR".to_string()),
+        }
+    }
+
+    fn position(symbol: &str, quantity: f64, buying_price: f64, last: f64) -> PositionSummary {
+        PositionSummary {
+            symbol: symbol.to_string(),
+            label: symbol.to_string(),
+            quantity: value(quantity, 0),
+            buying_price: value(buying_price, 4),
+            amount: value(quantity * last, 2),
+            last: value(last, 3),
+            gain_loss: value(quantity * (last - buying_price), 2),
+            ..Default::default()
+        }
+    }
+
+    fn account(
+        id: &str,
+        cash: f64,
+        contribution: f64,
+        positions: Vec<PositionSummary>,
+    ) -> PortfolioAccount {
+        PortfolioAccount::from_summary(
+            Account {
+                id: id.to_string(),
+                kind: AccountKind::Trading,
+                ..Default::default()
+            },
+            vec![
+                TradingSummaryItem {
+                    id: "account".to_string(),
+                    account: Some(AccountSummary {
+                        cash: value(cash, 2),
+                        contribution,
+                        ..Default::default()
+                    }),
+                    positions: None,
+                },
+                TradingSummaryItem {
+                    id: "positions".to_string(),
+                    account: None,
+                    positions: Some(positions),
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_from_summary() {
+        let pea = account(
+            "pea",
+            100.0,
+            1000.0,
+            vec![position("1rTCW8", 2.0, 400.0, 450.0)],
+        );
+        assert_eq!(pea.summary.as_ref().unwrap().cash.value, 100.0);
+        assert_eq!(pea.positions.len(), 1);
+        assert_eq!(pea.valuation(), 900.0);
+    }
+
+    #[test]
+    fn test_portfolio_totals_and_weights() {
+        let portfolio = Portfolio {
+            accounts: vec![
+                account(
+                    "pea",
+                    100.0,
+                    1000.0,
+                    vec![
+                        position("1rTCW8", 2.0, 400.0, 450.0),
+                        position("1rTPE500", 10.0, 30.0, 30.0),
+                    ],
+                ),
+                account(
+                    "cto",
+                    50.0,
+                    500.0,
+                    vec![position("AAPL", 1.0, 200.0, 180.0)],
+                ),
+            ],
+        };
+
+        let totals = portfolio.totals();
+        assert_eq!(totals.cash, 150.0);
+        assert_eq!(totals.contribution, 1500.0);
+        assert_eq!(totals.valuation, 1380.0);
+        assert_eq!(totals.cost, 1300.0);
+        assert_eq!(totals.gain_loss, 80.0);
+        assert!((totals.gain_loss_percent - 6.153846).abs() < 1e-6);
+
+        assert_eq!(portfolio.positions().count(), 3);
+        let (pea, world) = portfolio.positions().next().unwrap();
+        assert!((portfolio.weight(world) - 900.0 / 1380.0).abs() < 1e-9);
+        assert!((pea.weight(world) - 0.75).abs() < 1e-9);
+        assert_eq!(pea.totals().valuation, 1200.0);
+    }
+
+    #[test]
+    fn test_empty_portfolio() {
+        let portfolio = Portfolio::default();
+        assert_eq!(portfolio.valuation(), 0.0);
+        assert_eq!(portfolio.totals(), PortfolioTotals::default());
+        assert_eq!(portfolio.weight(&position("X", 1.0, 1.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn test_summary_value_display() {
+        assert_eq!(value(29.36312, 3).to_string(), "29.363 EUR");
+        let quantity = SummaryValue {
+            value: 4.0,
+            decimals: 0,
+            currency: None,
+        };
+        assert_eq!(quantity.to_string(), "4");
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::client::BoursoWebClient;
+
+use super::order::Details2;
+
+lazy_static! {
+    /// Regex to extract each result of the search page, the link holds the kind and symbol id
+    static ref SEARCH_RESULT_REGEX: Regex = Regex::new(
+        r#"(?ms)<a[^>]*?href="(?:https://www\.boursorama\.com)?/bourse/(?P<kind>[a-z-]+)/cours/(?P<symbol>[^/"]+)/?"[^>]*>(?P<content>.*?)</a>"#
+    )
+    .unwrap();
+    static ref TITLE_REGEX: Regex =
+        Regex::new(r#"(?ms)search__item-title[^>]*>\s*(?P<title>.*?)\s*<"#).unwrap();
+    static ref CONTENT_REGEX: Regex =
+        Regex::new(r#"(?ms)search__item-content[^>]*>\s*(?P<content>.*?)\s*</"#).unwrap();
+    static ref ISIN_REGEX: Regex = Regex::new(r"\b[A-Z]{2}[A-Z0-9]{9}[0-9]\b").unwrap();
+    static ref TAG_REGEX: Regex = Regex::new(r"(?ms)<[^>]*>").unwrap();
+}
+
+impl BoursoWebClient {
+    /// Search instruments by ISIN, ticker or name on Boursorama
+    ///
+    /// This does not require authentication.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - An ISIN (e.g: 'FR0010756098'), a ticker (e.g: 'CW8') or a name (e.g: 'msci world')
+    ///
+    /// # Returns
+    ///
+    /// The instruments matching the query, best matches first
+    #[cfg(not(tarpaulin_include))]
+    pub async fn search_instruments(&self, query: &str) -> Result<Vec<Instrument>> {
+        let response = self
+            .client
+            .get("https://www.boursorama.com/recherche/ajax")
+            .query(&[("query", query), ("searchId", "")])
+            .header("X-Requested-With", "XMLHttpRequest")
+            .send()
+            .await?;
+
+        let status_code = response.status();
+
+        let response = response.text().await?;
+
+        if status_code != 200 {
+            return Err(anyhow::anyhow!(
+                "Failed to search instruments ({}): {}",
+                status_code,
+                response
+            ));
+        }
+
+        Ok(parse_search_results(&response))
+    }
+
+    /// Resolve an ISIN to the symbol id used by Bourso
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - A symbol id (e.g: '1rTCW8') or an ISIN (e.g: 'FR0010756098')
+    ///
+    /// # Returns
+    ///
+    /// The symbol id, unchanged if the given symbol is not an ISIN. Fails if no instrument has
+    /// exactly this ISIN, or if it is listed on several exchanges
+    #[cfg(not(tarpaulin_include))]
+    pub async fn resolve_symbol(&self, symbol: &str) -> Result<String> {
+        if !is_isin(symbol) {
+            return Ok(symbol.to_string());
+        }
+
+        let instruments = self.search_instruments(symbol).await?;
+
+        select_listing(&instruments, symbol)
+    }
+}
+
+/// Kind of a financial instrument
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum InstrumentKind {
+    Stock,
+    /// ETF
+    Tracker,
+    /// Mutual fund (OPCVM)
+    Fund,
+    Warrant,
+    Turbo,
+    Bond,
+    Index,
+    #[default]
+    Other,
+}
+
+/// An instrument found by `BoursoWebClient::search_instruments`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instrument {
+    /// The symbol id (e.g: '1rTCW8')
+    pub symbol: String,
+    pub isin: Option<String>,
+    pub label: String,
+    /// Exchange label (e.g: 'Euronext Paris')
+    pub exchange: Option<String>,
+    pub kind: InstrumentKind,
+}
+
+impl InstrumentKind {
+    /// Kind of instrument from the path of its page on Boursorama (e.g: 'trackers' in '/bourse/trackers/cours/1rTCW8/')
+    pub fn from_path(path: &str) -> InstrumentKind {
+        match path {
+            "action" | "actions" => InstrumentKind::Stock,
+            "trackers" => InstrumentKind::Tracker,
+            "opcvm" => InstrumentKind::Fund,
+            "warrants" => InstrumentKind::Warrant,
+            "turbos" => InstrumentKind::Turbo,
+            "obligations" => InstrumentKind::Bond,
+            "indices" => InstrumentKind::Index,
+            _ => InstrumentKind::Other,
+        }
+    }
+}
+
+impl From<&Details2> for InstrumentKind {
+    /// Kind of instrument from the details returned at the `/order/prepare` endpoint
+    fn from(details: &Details2) -> Self {
+        if details.tracker {
+            InstrumentKind::Tracker
+        } else if details.opcvm {
+            InstrumentKind::Fund
+        } else if details.turbo {
+            InstrumentKind::Turbo
+        } else if details.warrant {
+            InstrumentKind::Warrant
+        } else {
+            InstrumentKind::Stock
+        }
+    }
+}
+
+impl std::fmt::Display for InstrumentKind {
+    #[cfg(not(tarpaulin_include))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            InstrumentKind::Stock => "stock",
+            InstrumentKind::Tracker => "ETF",
+            InstrumentKind::Fund => "fund",
+            InstrumentKind::Warrant => "warrant",
+            InstrumentKind::Turbo => "turbo",
+            InstrumentKind::Bond => "bond",
+            InstrumentKind::Index => "index",
+            InstrumentKind::Other => "other",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// Whether the given string is a valid ISIN (International Securities Identification Number)
+///
+/// The check digit is verified with the Luhn algorithm, letters being converted to numbers (A = 10, ..., Z = 35).
+pub fn is_isin(value: &str) -> bool {
+    if value.len() != 12
+        || !value.is_ascii()
+        || !value[..2].chars().all(|c| c.is_ascii_uppercase())
+        || !value[2..11]
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        || !value[11..].chars().all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+
+    let digits = value
+        .chars()
+        .flat_map(|c| {
+            let n = c.to_digit(36).unwrap();
+            if n >= 10 {
+                vec![n / 10, n % 10]
+            } else {
+                vec![n]
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let d = d * 2;
+                d / 10 + d % 10
+            } else {
+                d
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// Pick the only listing of the given ISIN among search results
+fn select_listing(instruments: &[Instrument], isin: &str) -> Result<String> {
+    let listings = instruments
+        .iter()
+        .filter(|i| i.isin.as_deref() == Some(isin))
+        .collect::<Vec<_>>();
+
+    match listings.as_slice() {
+        [] => Err(anyhow::anyhow!("No instrument found for ISIN {}", isin)),
+        [listing] => Ok(listing.symbol.clone()),
+        listings => Err(anyhow::anyhow!(
+            "ISIN {} is listed on several exchanges, use one of these symbols instead: {}",
+            isin,
+            listings
+                .iter()
+                .map(|i| format!(
+                    "{} ({})",
+                    i.symbol,
+                    i.exchange.as_deref().unwrap_or("unknown exchange")
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Parse the HTML returned by the Boursorama search endpoint
+fn parse_search_results(html: &str) -> Vec<Instrument> {
+    let mut instruments: Vec<Instrument> = Vec::new();
+
+    for captures in SEARCH_RESULT_REGEX.captures_iter(html) {
+        let symbol = captures["symbol"].to_string();
+        if instruments.iter().any(|i| i.symbol == symbol) {
+            continue;
+        }
+        let content = &captures["content"];
+
+        let label = TITLE_REGEX
+            .captures(content)
+            .map(|c| clean_text(&c["title"]))
+            .unwrap_or_else(|| clean_text(content));
+
+        // Details are separated by bullets, the exchange comes after the ticker,
+        // e.g: "CW8 • Euronext Paris • FR0010756098"
+        let details = CONTENT_REGEX
+            .captures(content)
+            .map(|c| clean_text(&c["content"]))
+            .unwrap_or_default();
+        let isin = ISIN_REGEX
+            .find(&details)
+            .or_else(|| ISIN_REGEX.find(content))
+            .map(|m| m.as_str().to_string());
+        let exchange = details
+            .split('•')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty() && Some(*s) != isin.as_deref())
+            .nth(1)
+            .map(|s| s.to_string());
+
+        instruments.push(Instrument {
+            symbol,
+            isin,
+            label,
+            exchange,
+            kind: InstrumentKind::from_path(&captures["kind"]),
+        });
+    }
+
+    instruments
+}
+
+/// Remove the HTML tags and collapse the whitespaces of a text
+fn clean_text(html: &str) -> String {
+    TAG_REGEX
+        .replace_all(html, " ")
+        .replace("&bull;", "•")
+        .replace("&amp;", "&")
+        .replace("&#039;", "'")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEARCH_HTML: &str = r#"
+        <ul class="search__list">
+            <li class="search__list-item">
+                <a href="/bourse/trackers/cours/1rTCW8/" class="search__list-link">
+                    <div class="search__item">
+                        <div class="search__item-title">AMUNDI MSCI WORLD UCITS ETF - EUR</div>
+                        <div class="search__item-content">CW8 &bull; Euronext Paris • FR0010756098</div>
+                    </div>
+                </a>
+            </li>
+            <li class="search__list-item">
+                <a href="/bourse/action/graph/ws/" class="search__list-link">Chart</a>
+                <a href="/bourse/action/cours/1rPAI/" class="search__list-link">
+                    <div class="search__item">
+                        <div class="search__item-title">AIR LIQUIDE</div>
+                        <div class="search__item-content">AI • Euronext Paris • FR0000120073</div>
+                    </div>
+                </a>
+                <a href="/bourse/opcvm/cours/MP-802250/" class="search__list-link">
+                    <div class="search__item-title">Comgest Monde C</div>
+                </a>
+            </li>
+        </ul>
+    "#;
+
+    #[test]
+    fn test_parse_search_results() {
+        let instruments = parse_search_results(SEARCH_HTML);
+        assert_eq!(instruments.len(), 3);
+
+        assert_eq!(instruments[0].symbol, "1rTCW8");
+        assert_eq!(instruments[0].label, "AMUNDI MSCI WORLD UCITS ETF - EUR");
+        assert_eq!(instruments[0].isin.as_deref(), Some("FR0010756098"));
+        assert_eq!(instruments[0].exchange.as_deref(), Some("Euronext Paris"));
+        assert_eq!(instruments[0].kind, InstrumentKind::Tracker);
+
+        assert_eq!(instruments[1].symbol, "1rPAI");
+        assert_eq!(instruments[1].kind, InstrumentKind::Stock);
+
+        assert_eq!(instruments[2].symbol, "MP-802250");
+        assert_eq!(instruments[2].label, "Comgest Monde C");
+        assert_eq!(instruments[2].isin, None);
+        assert_eq!(instruments[2].kind, InstrumentKind::Fund);
+    }
+
+    #[test]
+    fn test_select_listing() {
+        let mut instruments = parse_search_results(SEARCH_HTML);
+        assert_eq!(
+            select_listing(&instruments, "FR0010756098").unwrap(),
+            "1rTCW8"
+        );
+        // A result with another ISIN is never picked
+        assert!(select_listing(&instruments, "IE00B4L5Y983").is_err());
+
+        instruments.push(Instrument {
+            symbol: "1zCW8".to_string(),
+            isin: Some("FR0010756098".to_string()),
+            exchange: Some("Xetra".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            select_listing(&instruments, "FR0010756098")
+                .unwrap_err()
+                .to_string(),
+            "ISIN FR0010756098 is listed on several exchanges, use one of these symbols instead: 1rTCW8 (Euronext Paris), 1zCW8 (Xetra)"
+        );
+    }
+
+    #[test]
+    fn test_is_isin() {
+        assert!(is_isin("FR0010756098"));
+        assert!(is_isin("US0378331005"));
+        assert!(is_isin("IE00B4L5Y983"));
+        assert!(!is_isin("FR0010756099"));
+        assert!(!is_isin("1rTCW8"));
+        assert!(!is_isin("fr0010756098"));
+    }
+
+    #[test]
+    fn test_instrument_kind_from_details() {
+        let details = Details2 {
+            tracker: true,
+            euronext: true,
+            ..Default::default()
+        };
+        assert_eq!(InstrumentKind::from(&details), InstrumentKind::Tracker);
+        assert_eq!(
+            InstrumentKind::from(&Details2::default()),
+            InstrumentKind::Stock
+        );
+    }
+}
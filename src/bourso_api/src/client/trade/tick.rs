@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 
 use crate::client::BoursoWebClient;
 
@@ -12,19 +13,19 @@ impl BoursoWebClient {
     /// # Arguments
     /// 
     /// * `symbol` - The symbol id of the stock (e.g: '1rTCW8')
-    /// * `length` - The length period of the stock (e.g: '30' for 30 days)
-    /// * `period` - The interval of the stock (e.g: '0' for default interval)
+    /// * `length` - The length period of the stock (e.g: `TickLength::Month` for 30 days)
+    /// * `period` - The interval of the stock (e.g: `TickPeriod::Daily` for one quote per day)
     /// 
     /// # Returns
     /// 
     /// A struct containing the quotes for the given symbol, period and length
     #[cfg(not(tarpaulin_include))]
-    pub async fn get_ticks(&self, symbol: &str, length: i64, period: i64) -> Result<GetTicksEOD> {
+    pub async fn get_ticks(&self, symbol: &str, length: TickLength, period: TickPeriod) -> Result<GetTicksEOD> {
         let url = format!(
             "https://www.boursorama.com/bourse/action/graph/ws/GetTicksEOD?symbol={}&length={}&period={}&guid=",
             symbol,
-            length,
-            period
+            length.days(),
+            period.value()
         );
 
         let response = self.client.get(&url)
@@ -47,6 +48,126 @@ impl BoursoWebClient {
     }
 }
 
+/// Interval between two ticks
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TickPeriod {
+    /// Intraday ticks every given number of minutes (1, 5, 15 or 30)
+    Minutes(u32),
+    Hourly,
+    #[default]
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl TickPeriod {
+    /// Value of the `period` parameter of the `GetTicksEOD` endpoint
+    pub fn value(&self) -> i64 {
+        match self {
+            TickPeriod::Minutes(minutes) => *minutes as i64,
+            TickPeriod::Hourly => 60,
+            TickPeriod::Daily => 0,
+            TickPeriod::Weekly => 7,
+            TickPeriod::Monthly => 31,
+        }
+    }
+
+    /// Period from the `Xperiod` value returned by the `GetTicksEOD` endpoint
+    pub fn from_value(value: i64) -> Option<TickPeriod> {
+        match value {
+            0 => Some(TickPeriod::Daily),
+            1 | 5 | 15 | 30 => Some(TickPeriod::Minutes(value as u32)),
+            60 => Some(TickPeriod::Hourly),
+            7 => Some(TickPeriod::Weekly),
+            31 => Some(TickPeriod::Monthly),
+            _ => None,
+        }
+    }
+
+    /// Intraday ticks are dated in minutes since the epoch, the others in days since the epoch
+    pub fn is_intraday(&self) -> bool {
+        matches!(self, TickPeriod::Minutes(_) | TickPeriod::Hourly)
+    }
+}
+
+impl std::str::FromStr for TickPeriod {
+    type Err = anyhow::Error;
+
+    /// Parse a period such as `5m`, `1h`, `1d`, `1w` or `1mo`. `0` is the default daily period
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "1m" => Ok(TickPeriod::Minutes(1)),
+            "5m" => Ok(TickPeriod::Minutes(5)),
+            "15m" => Ok(TickPeriod::Minutes(15)),
+            "30m" => Ok(TickPeriod::Minutes(30)),
+            "1h" => Ok(TickPeriod::Hourly),
+            "0" | "1d" => Ok(TickPeriod::Daily),
+            "1w" => Ok(TickPeriod::Weekly),
+            "1mo" => Ok(TickPeriod::Monthly),
+            _ => Err(anyhow::anyhow!("Invalid tick period: {}", s)),
+        }
+    }
+}
+
+/// Time frame of the ticks
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TickLength {
+    Day,
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    HalfYear,
+    Year,
+    FiveYears,
+    TenYears,
+}
+
+impl TickLength {
+    /// Value of the `length` parameter of the `GetTicksEOD` endpoint, in days
+    pub fn days(&self) -> i64 {
+        match self {
+            TickLength::Day => 1,
+            TickLength::Week => 5,
+            TickLength::Month => 30,
+            TickLength::Quarter => 90,
+            TickLength::HalfYear => 180,
+            TickLength::Year => 365,
+            TickLength::FiveYears => 1825,
+            TickLength::TenYears => 3650,
+        }
+    }
+}
+
+impl TryFrom<i64> for TickLength {
+    type Error = anyhow::Error;
+
+    fn try_from(days: i64) -> Result<Self> {
+        match days {
+            1 => Ok(TickLength::Day),
+            5 => Ok(TickLength::Week),
+            30 => Ok(TickLength::Month),
+            90 => Ok(TickLength::Quarter),
+            180 => Ok(TickLength::HalfYear),
+            365 => Ok(TickLength::Year),
+            1825 => Ok(TickLength::FiveYears),
+            3650 => Ok(TickLength::TenYears),
+            _ => Err(anyhow::anyhow!("Invalid tick length: {} days", days)),
+        }
+    }
+}
+
+impl std::str::FromStr for TickLength {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let days: i64 = s.parse().context(format!("Invalid tick length: {}", s))?;
+        TickLength::try_from(days)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetTicksEOD {
@@ -107,6 +228,17 @@ impl D {
     pub fn get_volume(&self) -> i64 {
         self.quote_tab.iter().map(|quote| quote.volume).sum()
     }
+
+    /// Get the period of the quotes, daily if unknown
+    pub fn get_period(&self) -> TickPeriod {
+        TickPeriod::from_value(self.xperiod).unwrap_or_default()
+    }
+
+    /// Get the quotes as OHLCV candles, dated according to the period of the quotes
+    pub fn get_candles(&self) -> Vec<Candle> {
+        let period = self.get_period();
+        self.quote_tab.iter().map(|quote| quote.to_candle(period)).collect()
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -126,6 +258,48 @@ pub struct QuoteTab {
     pub volume: i64,
 }
 
+impl QuoteTab {
+    /// Get the date of the quote. `date` is a number of days since the epoch for daily and longer periods,
+    /// a number of minutes since the epoch for intraday periods
+    pub fn get_datetime(&self, period: TickPeriod) -> DateTime<Utc> {
+        let epoch = DateTime::<Utc>::UNIX_EPOCH;
+        if period.is_intraday() {
+            epoch + Duration::minutes(self.date)
+        } else {
+            epoch + Duration::days(self.date)
+        }
+    }
+
+    /// Get the day of the quote
+    pub fn get_date(&self, period: TickPeriod) -> NaiveDate {
+        self.get_datetime(period).date_naive()
+    }
+
+    pub fn to_candle(&self, period: TickPeriod) -> Candle {
+        Candle {
+            time: self.get_datetime(period),
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+        }
+    }
+}
+
+/// OHLCV (open, high, low, close, volume) quote of a period
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Candle {
+    /// Start of the period
+    pub time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,5 +481,47 @@ mod tests {
         assert_eq!(response.d.get_last_quote(), None);
         assert_eq!(response.d.get_second_to_last_quote(), None);
         assert_eq!(response.d.get_quotes().len(), 19);
+
+        let candles = response.d.get_candles();
+        assert_eq!(candles.len(), 19);
+        assert_eq!(candles[0].time.date_naive(), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(candles[0].close, 29.363);
+        assert_eq!(candles[18].volume, 16939);
+    }
+
+    #[test]
+    fn test_quote_dates() {
+        let quote = QuoteTab {
+            date: 19786,
+            ..Default::default()
+        };
+        assert_eq!(quote.get_date(TickPeriod::Daily), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(quote.get_date(TickPeriod::Weekly), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+
+        let quote = QuoteTab {
+            date: 28_492_380,
+            ..Default::default()
+        };
+        assert_eq!(
+            quote.get_datetime(TickPeriod::Minutes(5)).to_rfc3339(),
+            "2024-03-04T09:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_tick_period_and_length() {
+        assert_eq!("0".parse::<TickPeriod>().unwrap(), TickPeriod::Daily);
+        assert_eq!("5m".parse::<TickPeriod>().unwrap(), TickPeriod::Minutes(5));
+        assert!("2m".parse::<TickPeriod>().is_err());
+        for period in ["1m", "5m", "15m", "30m", "1h", "1d", "1w", "1mo"] {
+            let period = period.parse::<TickPeriod>().unwrap();
+            assert_eq!(TickPeriod::from_value(period.value()), Some(period));
+        }
+        assert!(TickPeriod::Hourly.is_intraday());
+        assert!(!TickPeriod::Daily.is_intraday());
+
+        assert_eq!("365".parse::<TickLength>().unwrap(), TickLength::Year);
+        assert_eq!(TickLength::FiveYears.days(), 1825);
+        assert!("42".parse::<TickLength>().is_err());
     }
 }
@@ -0,0 +1,209 @@
+use anyhow::{bail, Context, Result};
+use futures_util::{pin_mut, stream::Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::{validate_transfer, TransferOptions, TransferProgress};
+use crate::{account::Account, client::BoursoWebClient};
+
+/// A row of a batch of transfers between your accounts
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchTransfer {
+    /// Optional id of the transfer, unique in the batch, used to track it between runs
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Id of the debited account
+    pub from: String,
+    /// Id of the credited account
+    pub to: String,
+    pub amount: f64,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Parse a batch of transfers from a CSV file with a `from,to,amount,reason` header
+///
+/// The delimiter can be a comma or a semicolon, and the reason and id columns are optional.
+pub fn parse_batch(content: &str) -> Result<Vec<BatchTransfer>> {
+    let header = content.lines().next().unwrap_or_default();
+    let delimiter = if header.contains(';') { b';' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(true)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut transfer: BatchTransfer =
+                row.with_context(|| format!("Failed to parse the transfer of line {}", i + 2))?;
+            transfer.reason = transfer.reason.filter(|r| !r.is_empty());
+            transfer.id = transfer.id.filter(|id| !id.is_empty());
+            Ok(transfer)
+        })
+        .collect()
+}
+
+/// Check every transfer of a batch before executing any of them
+///
+/// # Arguments
+///
+/// * `transfers` - The transfers to check
+/// * `accounts` - Your accounts, the debited and credited accounts must be part of them
+///
+/// # Returns
+///
+/// An error listing every invalid transfer, by its line in the CSV file
+pub fn validate_batch(transfers: &[BatchTransfer], accounts: &[Account]) -> Result<()> {
+    let mut errors = Vec::new();
+    for (i, transfer) in transfers.iter().enumerate() {
+        let line = i + 2;
+        if !accounts.iter().any(|a| a.id == transfer.from) {
+            errors.push(format!(
+                "line {}: account {} not found",
+                line, transfer.from
+            ));
+        }
+        if !accounts.iter().any(|a| a.id == transfer.to) {
+            errors.push(format!("line {}: account {} not found", line, transfer.to));
+        }
+        if transfer.from == transfer.to {
+            errors.push(format!("line {}: same debited and credited account", line));
+        }
+        if let Err(e) = validate_transfer(transfer.amount, transfer.reason.as_deref()) {
+            errors.push(format!("line {}: {}", line, e));
+        }
+        if let Some(id) = &transfer.id {
+            if transfers[..i].iter().any(|t| t.id.as_ref() == Some(id)) {
+                errors.push(format!("line {}: duplicate id {}", line, id));
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid transfers:\n{}", errors.join("\n"));
+    }
+    Ok(())
+}
+
+impl BoursoWebClient {
+    /// Execute a batch of transfers one after the other, with the same session
+    ///
+    /// A failed transfer does not stop the batch. Check it first with [`validate_batch`].
+    ///
+    /// # Arguments
+    ///
+    /// * `transfers` - The transfers to execute, with the index used in the progress updates
+    /// * `accounts` - Your accounts, including the debited and credited accounts
+    /// * `dry_run` - Stop each transfer before confirming it, see [`TransferOptions::dry_run`]
+    ///
+    /// # Returns
+    ///
+    /// A stream of the progress updates of each transfer, with its index
+    #[cfg(not(tarpaulin_include))]
+    pub fn transfer_batch(
+        &self,
+        transfers: Vec<(usize, BatchTransfer)>,
+        accounts: Vec<Account>,
+        dry_run: bool,
+    ) -> impl Stream<Item = (usize, Result<TransferProgress>)> + '_ {
+        async_stream::stream! {
+            for (index, transfer) in transfers {
+                let from = accounts.iter().find(|a| a.id == transfer.from).cloned();
+                let to = accounts.iter().find(|a| a.id == transfer.to).cloned();
+                let (from, to) = match (from, to) {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => {
+                        yield (index, Err(anyhow::anyhow!("Account not found")));
+                        continue;
+                    }
+                };
+
+                let stream = self.transfer_funds(
+                    transfer.amount,
+                    from,
+                    to,
+                    TransferOptions {
+                        reason: transfer.reason,
+                        dry_run,
+                        ..Default::default()
+                    },
+                );
+                pin_mut!(stream);
+                while let Some(progress) = stream.next().await {
+                    yield (index, progress);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_validate_batch() {
+        let content = "from;to;amount;reason\n\
+                       a583f3c5842c34fb00b408486ef493e0;b419f2d28010bafb331badef11bba51e;150.5;Livret A\n\
+                       b419f2d28010bafb331badef11bba51e;a583f3c5842c34fb00b408486ef493e0;5;\n\
+                       a583f3c5842c34fb00b408486ef493e0;c3d1e5f28010bafb331badef11bba51e;20;Reason that is far too long to be accepted by Bourso";
+        let transfers = parse_batch(content).unwrap();
+        assert_eq!(transfers.len(), 3);
+        assert_eq!(
+            transfers[0],
+            BatchTransfer {
+                id: None,
+                from: "a583f3c5842c34fb00b408486ef493e0".to_string(),
+                to: "b419f2d28010bafb331badef11bba51e".to_string(),
+                amount: 150.5,
+                reason: Some("Livret A".to_string()),
+            }
+        );
+        assert_eq!(transfers[1].reason, None);
+
+        let accounts = [
+            "a583f3c5842c34fb00b408486ef493e0",
+            "b419f2d28010bafb331badef11bba51e",
+        ]
+        .iter()
+        .map(|id| Account {
+            id: id.to_string(),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+        assert!(validate_batch(&transfers[..1], &accounts).is_ok());
+
+        let error = validate_batch(&transfers, &accounts)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid transfers:\n\
+             line 3: Amount is below the minimum threshold (10 EUR)\n\
+             line 4: account c3d1e5f28010bafb331badef11bba51e not found\n\
+             line 4: Transfer reason is too long, max 50 characters"
+        );
+
+        let content = "from,to,amount\n\
+                       a583f3c5842c34fb00b408486ef493e0,b419f2d28010bafb331badef11bba51e,10";
+        assert_eq!(parse_batch(content).unwrap()[0].reason, None);
+
+        let content = "id,from,to,amount\n\
+                       rent,a583f3c5842c34fb00b408486ef493e0,b419f2d28010bafb331badef11bba51e,10\n\
+                       rent,a583f3c5842c34fb00b408486ef493e0,b419f2d28010bafb331badef11bba51e,20\n\
+                       ,a583f3c5842c34fb00b408486ef493e0,b419f2d28010bafb331badef11bba51e,30";
+        let transfers = parse_batch(content).unwrap();
+        assert_eq!(transfers[0].id.as_deref(), Some("rent"));
+        assert_eq!(transfers[2].id, None);
+        assert_eq!(
+            validate_batch(&transfers, &accounts)
+                .unwrap_err()
+                .to_string(),
+            "Invalid transfers:\nline 3: duplicate id rent"
+        );
+    }
+}
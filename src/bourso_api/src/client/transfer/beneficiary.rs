@@ -0,0 +1,274 @@
+use anyhow::{bail, Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::html::{attribute, element_text, elements};
+use crate::{
+    account::Account,
+    client::{error::ClientError, BoursoWebClient},
+    constants::BASE_URL,
+};
+
+lazy_static! {
+    static ref BENEFICIARY_LINK_REGEX: Regex =
+        Regex::new(r#"/virements/beneficiaires/(?P<id>[^/"]+)"#).unwrap();
+    static ref ADD_BENEFICIARY_TOKEN_REGEX: Regex =
+        Regex::new(r#"addBeneficiary\[_token\]"[^>]*?value="(?P<token>[^"]+)""#).unwrap();
+}
+
+/// An external account saved as a transfer beneficiary
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Beneficiary {
+    /// Id used as credit account in the transfer form
+    pub id: String,
+    pub name: String,
+    /// IBAN without spaces
+    pub iban: String,
+    /// Empty if Bourso doesn't show it
+    pub bic: String,
+}
+
+/// The account credited by a transfer: one of your accounts or a saved beneficiary
+#[derive(Debug, Clone, PartialEq)]
+pub enum CreditAccount {
+    Account(Account),
+    Beneficiary(Beneficiary),
+}
+
+impl CreditAccount {
+    /// Value of the `CreditAccount[credit]` field of the transfer form
+    pub fn id(&self) -> &str {
+        match self {
+            CreditAccount::Account(account) => &account.id,
+            CreditAccount::Beneficiary(beneficiary) => &beneficiary.id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            CreditAccount::Account(account) => &account.name,
+            CreditAccount::Beneficiary(beneficiary) => &beneficiary.name,
+        }
+    }
+}
+
+impl From<Account> for CreditAccount {
+    fn from(account: Account) -> Self {
+        CreditAccount::Account(account)
+    }
+}
+
+impl From<Beneficiary> for CreditAccount {
+    fn from(beneficiary: Beneficiary) -> Self {
+        CreditAccount::Beneficiary(beneficiary)
+    }
+}
+
+impl BoursoWebClient {
+    /// Get the external beneficiaries that can be credited from an account
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - The account to debit
+    ///
+    /// # Returns
+    ///
+    /// The saved beneficiaries
+    #[cfg(not(tarpaulin_include))]
+    pub async fn list_beneficiaries(&self, account_id: &str) -> Result<Vec<Beneficiary>> {
+        let res = self
+            .client
+            .get(beneficiaries_url(account_id))
+            .headers(self.get_headers())
+            .send()
+            .await?;
+
+        if res.status() != 200 {
+            debug!("Beneficiaries response: {:?}", res);
+            bail!("Failed to get the beneficiaries of account {}", account_id);
+        }
+
+        Ok(extract_beneficiaries(&res.text().await?))
+    }
+
+    /// Add an external beneficiary by its IBAN
+    ///
+    /// Bourso requires a strong authentication to add a beneficiary: the first call fails with
+    /// `ClientError::MfaRequired`. Validate it with [`BoursoWebClient::request_mfa`] and
+    /// [`BoursoWebClient::check_mfa`], then call this method again.
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - The account the beneficiary will be credited from
+    /// * `name` - The beneficiary name
+    /// * `iban` - The beneficiary IBAN, spaces are ignored
+    /// * `bic` - The beneficiary BIC, deduced from the IBAN by Bourso if not given
+    #[cfg(not(tarpaulin_include))]
+    pub async fn add_beneficiary(
+        &self,
+        account_id: &str,
+        name: &str,
+        iban: &str,
+        bic: Option<&str>,
+    ) -> Result<()> {
+        let iban = normalize_iban(iban);
+        if !is_valid_iban(&iban) {
+            bail!("Invalid IBAN {}", iban);
+        }
+
+        let url = format!("{}/nouveau", beneficiaries_url(account_id));
+        let res = self
+            .client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await?;
+        if is_strong_authentication_redirect(&res) {
+            bail!(ClientError::MfaRequired);
+        }
+        let page = res.text().await?;
+        let token = ADD_BENEFICIARY_TOKEN_REGEX
+            .captures(&page)
+            .and_then(|c| c.name("token"))
+            .map(|m| m.as_str().to_string())
+            .context("Failed to find the form to add a beneficiary")?;
+
+        let data = reqwest::multipart::Form::new()
+            .text("addBeneficiary[name]", name.to_string())
+            .text("addBeneficiary[iban]", iban.clone())
+            .text("addBeneficiary[bic]", bic.unwrap_or_default().to_string())
+            .text("addBeneficiary[_token]", token)
+            .text("submit", "".to_string());
+
+        let res = self
+            .client
+            .post(&url)
+            .multipart(data)
+            .headers(self.get_headers())
+            .send()
+            .await?;
+
+        if is_strong_authentication_redirect(&res) {
+            bail!(ClientError::MfaRequired);
+        }
+        if !res.status().is_success() && !res.status().is_redirection() {
+            debug!("Add beneficiary response: {:?}", res);
+            bail!("Failed to add the beneficiary {}", iban);
+        }
+
+        Ok(())
+    }
+}
+
+fn beneficiaries_url(account_id: &str) -> String {
+    format!(
+        "{}/compte/cav/{}/virements/beneficiaires",
+        BASE_URL, account_id
+    )
+}
+
+/// Whether Bourso redirects to the strong authentication page
+fn is_strong_authentication_redirect(res: &reqwest::Response) -> bool {
+    res.headers()
+        .get("location")
+        .and_then(|l| l.to_str().ok())
+        .is_some_and(|l| l.contains("/securisation"))
+}
+
+/// Extract the beneficiaries from the entries of the beneficiaries list, each entry being read
+/// on its own so that a missing field is never taken from the next one. The id of a beneficiary
+/// is read from the link to its details, and entries without an IBAN are skipped
+fn extract_beneficiaries(html: &str) -> Vec<Beneficiary> {
+    elements(html, "li", "c-beneficiary")
+        .into_iter()
+        .filter_map(|entry| {
+            let field = |name: &str| {
+                element_text(entry, "span", &format!("c-beneficiary__{}", name)).unwrap_or_default()
+            };
+            let id = BENEFICIARY_LINK_REGEX.captures(attribute(entry, "href")?)?["id"].to_string();
+            let iban = normalize_iban(&field("iban"));
+            if iban.is_empty() {
+                debug!("Skipping beneficiary {} without IBAN", id);
+                return None;
+            }
+            Some(Beneficiary {
+                id,
+                name: field("name"),
+                iban,
+                bic: field("bic"),
+            })
+        })
+        .collect()
+}
+
+/// Uppercase IBAN without spaces
+pub fn normalize_iban(iban: &str) -> String {
+    iban.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Check the length and the ISO 13616 checksum (mod 97) of a normalized IBAN
+pub fn is_valid_iban(iban: &str) -> bool {
+    if iban.len() < 15 || iban.len() > 34 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    // Move the country code and the check digits to the end, then letters become 10 to 35
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let value = c.to_digit(36).unwrap();
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iban() {
+        let iban = normalize_iban("fr76 3000 6000 0112 3456 7890 189");
+        assert_eq!(iban, "FR7630006000011234567890189");
+        assert!(is_valid_iban(&iban));
+        assert!(!is_valid_iban("FR7630006000011234567890188"));
+        assert!(!is_valid_iban("FR76"));
+    }
+
+    #[test]
+    fn test_extract_beneficiaries() {
+        let html = include_str!("fixtures/beneficiaries.html");
+
+        let beneficiaries = extract_beneficiaries(html);
+        assert_eq!(beneficiaries.len(), 3);
+        assert_eq!(
+            beneficiaries[0],
+            Beneficiary {
+                id: "4c2f1a".to_string(),
+                name: "M JOHN DOE".to_string(),
+                iban: "FR7630006000011234567890189".to_string(),
+                bic: "AGRIFRPP".to_string(),
+            }
+        );
+        // The entry without a BIC doesn't take the fields of the next one
+        assert_eq!(
+            beneficiaries[1],
+            Beneficiary {
+                id: "7d9e3b".to_string(),
+                name: "MME JANE DOE".to_string(),
+                iban: "DE89370400440532013000".to_string(),
+                bic: String::new(),
+            }
+        );
+        assert_eq!(beneficiaries[2].id, "a1b2c3");
+        assert_eq!(beneficiaries[2].iban, "FR1420041010050500013M02606");
+    }
+}
@@ -1,17 +1,76 @@
 use std::fmt;
 
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{html::text, limits::TransferLimit};
+
+lazy_static! {
+    static ref FORM_REGEX: Regex = Regex::new(r"(?ms)<form\b[^>]*>.*?</form>").unwrap();
+    static ref TEMPLATE_REGEX: Regex =
+        Regex::new(r"(?ms)<template\b[^>]*>.*?</template>").unwrap();
+    static ref FORM_ERROR_REGEX: Regex = Regex::new(
+        r#"(?ms)(?P<tag><(?:div|span|p|li)[^>]*class="[^"]*(?:form-error|form__error|alert--error|alert--danger)[^"]*"[^>]*>)(?P<message>.*?)</(?:div|span|p|li)>"#
+    )
+    .unwrap();
+}
+
+/// Response of a step of the transfer form that failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepFailure {
+    /// HTTP status of the response
+    pub status: u16,
+    /// Error message shown by Bourso in the returned form page, e.g. "Solde insuffisant"
+    pub message: Option<String>,
+}
+
 #[derive(Debug)]
 pub enum TransferError {
     AmountTooLow,
-    TransferInitiationFailed,
-    SetDebitAccountFailed,
-    SetCreditAccountFailed,
-    Step5Failed,
-    SetAmountFailed,
-    SetReasonFailed,
+    TransferInitiationFailed(StepFailure),
+    SetDebitAccountFailed(StepFailure),
+    SetCreditAccountFailed(StepFailure),
+    CreditAccountNotAllowed,
+    Step5Failed(StepFailure),
+    SetAmountFailed(StepFailure),
+    SetReasonFailed(StepFailure),
     ReasonIsTooLong,
-    SubmitTransferFailed,
-    InvalidTransfer,
+    InvalidSchedule,
+    /// The amount is above what remains of a ceiling of the debited account
+    LimitExceeded(TransferLimit),
+    /// None of the ceilings of the debited account applies to the transfer, or none could be read
+    NoApplicableLimit,
+    /// The confirmation step doesn't show the amount and the accounts of the transfer
+    IncompleteSummary,
+    /// The confirmation step shows another amount than the requested one
+    AmountMismatch {
+        requested: f64,
+        shown: f64,
+    },
+    /// The confirmation step shows another account than the requested one
+    AccountMismatch {
+        requested: String,
+        shown: String,
+    },
+    SubmitTransferFailed(StepFailure),
+    InvalidTransfer(StepFailure),
+}
+
+impl TransferError {
+    /// Response of the failed step, if the error comes from Bourso
+    pub fn failure(&self) -> Option<&StepFailure> {
+        match self {
+            TransferError::TransferInitiationFailed(failure)
+            | TransferError::SetDebitAccountFailed(failure)
+            | TransferError::SetCreditAccountFailed(failure)
+            | TransferError::Step5Failed(failure)
+            | TransferError::SetAmountFailed(failure)
+            | TransferError::SetReasonFailed(failure)
+            | TransferError::SubmitTransferFailed(failure)
+            | TransferError::InvalidTransfer(failure) => Some(failure),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for TransferError {
@@ -24,16 +83,138 @@ impl fmt::Display for TransferError {
             TransferError::ReasonIsTooLong => {
                 write!(f, "Transfer reason is too long, max 50 characters")
             }
-            TransferError::TransferInitiationFailed => write!(f, "Transfer initiation failed"),
-            TransferError::SetDebitAccountFailed => write!(f, "Setting debit account failed"),
-            TransferError::SetCreditAccountFailed => write!(f, "Setting credit account failed"),
-            TransferError::Step5Failed => write!(f, "Transfer step 5 failed"),
-            TransferError::SetAmountFailed => write!(f, "Setting transfer amount failed"),
-            TransferError::SetReasonFailed => write!(f, "Setting transfer reason failed"),
-            TransferError::SubmitTransferFailed => write!(f, "Submitting transfer failed"),
-            TransferError::InvalidTransfer => write!(f, "Invalid transfer. Check that the accounts exist and that you have enough balance. Some accounts (e.g. savings) may not allow transfers to certain other accounts, check first on the website that the transfer is possible."),
+            TransferError::InvalidSchedule => write!(
+                f,
+                "Invalid schedule, the execution dates must be in the future and the end date after the first execution"
+            ),
+            TransferError::LimitExceeded(limit) => write!(
+                f,
+                "The amount exceeds the {} ceiling of {:.2} EUR ({}), {:.2} EUR remaining",
+                format!("{:?}", limit.period).to_lowercase(),
+                limit.ceiling,
+                limit.label,
+                limit.remaining
+            ),
+            TransferError::NoApplicableLimit => write!(
+                f,
+                "The ceilings of the account cannot be checked, none of them applies to the transfer or could be read"
+            ),
+            TransferError::IncompleteSummary => write!(
+                f,
+                "The transfer summary doesn't show the amount and the accounts of the transfer"
+            ),
+            TransferError::AmountMismatch { requested, shown } => write!(
+                f,
+                "The transfer summary shows an amount of {:.2} EUR instead of {:.2} EUR",
+                shown, requested
+            ),
+            TransferError::AccountMismatch { requested, shown } => write!(
+                f,
+                "The transfer summary shows the account '{}' instead of '{}'",
+                shown, requested
+            ),
+            TransferError::TransferInitiationFailed(_) => write!(f, "Transfer initiation failed"),
+            TransferError::SetDebitAccountFailed(_) => write!(f, "Setting debit account failed"),
+            TransferError::SetCreditAccountFailed(_) => {
+                write!(f, "Setting credit account failed")
+            }
+            TransferError::CreditAccountNotAllowed => write!(
+                f,
+                "The destination account cannot be credited from the source account"
+            ),
+            TransferError::Step5Failed(_) => write!(f, "Transfer step 5 failed"),
+            TransferError::SetAmountFailed(_) => write!(f, "Setting transfer amount failed"),
+            TransferError::SetReasonFailed(_) => write!(f, "Setting transfer reason failed"),
+            TransferError::SubmitTransferFailed(_) => write!(f, "Submitting transfer failed"),
+            TransferError::InvalidTransfer(StepFailure { message: None, .. }) => write!(f, "Invalid transfer. Check that the accounts exist and that you have enough balance. Some accounts (e.g. savings) may not allow transfers to certain other accounts, check first on the website that the transfer is possible."),
+            TransferError::InvalidTransfer(_) => write!(f, "Invalid transfer"),
+        }?;
+
+        match self.failure() {
+            Some(StepFailure {
+                status,
+                message: Some(message),
+            }) => write!(f, " (HTTP {}): {}", status, message),
+            Some(StepFailure { status, .. }) => write!(f, " (HTTP {})", status),
+            None => Ok(()),
         }
     }
 }
 
 impl std::error::Error for TransferError {}
+
+/// Extract the error messages shown by Bourso in the form of a page of the transfer form
+///
+/// Only the errors of the form are read: banners of the rest of the page, hidden errors and
+/// `<template>` elements are ignored, as they are not about the submitted step.
+pub(crate) fn extract_error_message(html: &str) -> Option<String> {
+    let mut messages: Vec<String> = Vec::new();
+    for form in FORM_REGEX.find_iter(html) {
+        let form = TEMPLATE_REGEX.replace_all(form.as_str(), "");
+        for capture in FORM_ERROR_REGEX.captures_iter(&form) {
+            if capture["tag"].contains(" hidden") {
+                continue;
+            }
+            let message = text(&capture["message"]);
+            if !message.is_empty() && !messages.contains(&message) {
+                messages.push(message);
+            }
+        }
+    }
+    (!messages.is_empty()).then(|| messages.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_error_message() {
+        let html = r#"
+        <form name="Amount">
+          <input type="text" name="Amount[amount]" value="5000,00">
+          <div class="c-field__error form-error">
+            <ul><li class="form-error">Le solde de votre compte est insuffisant.</li></ul>
+          </div>
+          <span class="form-error">Le plafond journalier de vos virements est atteint.</span>
+        </form>"#;
+
+        assert_eq!(
+            extract_error_message(html),
+            Some(
+                "Le solde de votre compte est insuffisant. Le plafond journalier de vos virements est atteint."
+                    .to_string()
+            )
+        );
+        assert_eq!(extract_error_message("<form></form>"), None);
+
+        let error = TransferError::SetAmountFailed(StepFailure {
+            status: 200,
+            message: extract_error_message(html),
+        });
+        assert!(error
+            .to_string()
+            .starts_with("Setting transfer amount failed (HTTP 200): Le solde"));
+        assert_eq!(
+            TransferError::Step5Failed(StepFailure {
+                status: 500,
+                message: None
+            })
+            .to_string(),
+            "Transfer step 5 failed (HTTP 500)"
+        );
+
+        // Banners outside the form, hidden errors, templates and empty errors are ignored
+        let html = r#"
+        <div class="c-banner alert--danger">Maintenance prévue ce soir.</div>
+        <form name="Amount">
+          <input type="text" name="Amount[amount]" value="50,00">
+          <div class="c-field__error form-error"></div>
+          <span class="form-error" hidden>Le montant est invalide.</span>
+          <template id="error-template">
+            <div class="form-error">Une erreur est survenue.</div>
+          </template>
+        </form>"#;
+        assert_eq!(extract_error_message(html), None);
+    }
+}
@@ -0,0 +1,252 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::{
+    html::{attribute, element_text, elements},
+    parse_amount,
+};
+use crate::{client::BoursoWebClient, constants::BASE_URL};
+
+lazy_static! {
+    static ref TRANSFER_LINK_REGEX: Regex =
+        Regex::new(r#"/virements/suivi/(?P<id>[^/?"]+)"#).unwrap();
+}
+
+/// Stop following the history pages after this many of them
+const MAX_PAGES: usize = 50;
+
+/// Status of a transfer, as shown in the history
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferStatus {
+    /// Waiting to be executed
+    #[default]
+    Pending,
+    Executed,
+    Rejected,
+    Cancelled,
+    /// A status not known by this library, with its label
+    Unknown(String),
+}
+
+impl TransferStatus {
+    /// Parse the status label of the history, e.g. "Exécuté"
+    fn from_label(label: &str) -> TransferStatus {
+        let lowercase = label.to_lowercase();
+        if lowercase.starts_with("exécuté") || lowercase.starts_with("execute") {
+            TransferStatus::Executed
+        } else if lowercase.starts_with("en cours") || lowercase.starts_with("en attente") {
+            TransferStatus::Pending
+        } else if lowercase.starts_with("rejeté") || lowercase.starts_with("refusé") {
+            TransferStatus::Rejected
+        } else if lowercase.starts_with("annulé") {
+            TransferStatus::Cancelled
+        } else {
+            TransferStatus::Unknown(label.to_string())
+        }
+    }
+
+    /// Short name of the status, or the label of an unknown status
+    pub fn name(&self) -> &str {
+        match self {
+            TransferStatus::Pending => "pending",
+            TransferStatus::Executed => "executed",
+            TransferStatus::Rejected => "rejected",
+            TransferStatus::Cancelled => "cancelled",
+            TransferStatus::Unknown(label) => label,
+        }
+    }
+}
+
+/// How a transfer was scheduled, see [`super::schedule::TransferSchedule`]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulingType {
+    #[default]
+    Immediate,
+    Deferred,
+    Recurring,
+}
+
+impl SchedulingType {
+    /// Parse the scheduling label of the history, e.g. "Permanent"
+    fn from_label(label: &str) -> SchedulingType {
+        let label = label.to_lowercase();
+        if label.starts_with("différé") {
+            SchedulingType::Deferred
+        } else if label.starts_with("permanent") || label.starts_with("périodique") {
+            SchedulingType::Recurring
+        } else {
+            SchedulingType::Immediate
+        }
+    }
+}
+
+/// A transfer of the history of an account
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRecord {
+    pub id: String,
+    /// Execution date, or planned execution date of a pending transfer
+    pub date: NaiveDate,
+    pub amount: f64,
+    /// Name of the credited account or beneficiary
+    pub counterparty: String,
+    pub label: String,
+    pub status: TransferStatus,
+    pub scheduling: SchedulingType,
+}
+
+impl BoursoWebClient {
+    /// Get the transfers made from an account, with their status
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - The debited account id
+    /// * `range` - The dates of the transfers, inclusive
+    ///
+    /// # Returns
+    ///
+    /// The transfers of the range, most recent first
+    #[cfg(not(tarpaulin_include))]
+    pub async fn list_transfers(
+        &self,
+        account_id: &str,
+        range: RangeInclusive<NaiveDate>,
+    ) -> Result<Vec<TransferRecord>> {
+        let mut transfers = Vec::new();
+
+        for page in 1..=MAX_PAGES {
+            let res = self
+                .client
+                .get(transfers_url(account_id, &range, page))
+                .headers(self.get_headers())
+                .send()
+                .await?;
+
+            if res.status() != 200 {
+                debug!("Transfers response: {:?}", res);
+                bail!("Failed to get the transfers of account {}", account_id);
+            }
+
+            let html = res.text().await?;
+            let records = extract_transfers(&html);
+            if records.is_empty() {
+                break;
+            }
+            transfers.extend(records);
+            if !has_next_page(&html) {
+                break;
+            }
+        }
+
+        Ok(transfers)
+    }
+}
+
+fn transfers_url(account_id: &str, range: &RangeInclusive<NaiveDate>, page: usize) -> String {
+    format!(
+        "{}/compte/cav/{}/virements/suivi?fromDate={}&toDate={}&page={}",
+        BASE_URL,
+        account_id,
+        range.start().format("%d/%m/%Y"),
+        range.end().format("%d/%m/%Y"),
+        page
+    )
+}
+
+/// Extract the transfers from the rows of the transfers history table. The id of a transfer is
+/// read from the link to its details
+fn extract_transfers(html: &str) -> Vec<TransferRecord> {
+    elements(html, "tr", "c-table__row")
+        .into_iter()
+        .filter_map(|row| {
+            let cell = |name: &str| {
+                element_text(row, "td", &format!("c-table__cell--{}", name)).unwrap_or_default()
+            };
+            // Skip the rows without a date or a link, e.g. the header
+            let date = NaiveDate::parse_from_str(&cell("date"), "%d/%m/%Y").ok()?;
+            let id = TRANSFER_LINK_REGEX.captures(attribute(row, "href")?)?["id"].to_string();
+
+            let amount = match parse_amount(&cell("amount")) {
+                Some(amount) => amount,
+                None => {
+                    debug!("Skipping transfer {} without amount", id);
+                    return None;
+                }
+            };
+            let status = TransferStatus::from_label(&cell("status"));
+            if let TransferStatus::Unknown(label) = &status {
+                debug!("Unknown status of transfer {}: {}", id, label);
+            }
+
+            Some(TransferRecord {
+                id,
+                date,
+                amount,
+                counterparty: cell("beneficiary"),
+                label: cell("label"),
+                status,
+                scheduling: SchedulingType::from_label(&cell("type")),
+            })
+        })
+        .collect()
+}
+
+/// Whether the history has a next page
+fn has_next_page(html: &str) -> bool {
+    !elements(html, "a", "c-pagination__link--next").is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_transfers() {
+        let html = include_str!("fixtures/history.html");
+
+        let transfers = extract_transfers(html);
+        assert_eq!(transfers.len(), 3);
+        assert_eq!(
+            transfers[0],
+            TransferRecord {
+                id: "c81f2a".to_string(),
+                date: NaiveDate::from_ymd_opt(2026, 10, 15).unwrap(),
+                amount: -1500.0,
+                counterparty: "LIVRET A".to_string(),
+                label: "Epargne".to_string(),
+                status: TransferStatus::Executed,
+                scheduling: SchedulingType::Recurring,
+            }
+        );
+        assert_eq!(transfers[1].status, TransferStatus::Pending);
+        assert_eq!(transfers[1].scheduling, SchedulingType::Deferred);
+        assert_eq!(
+            transfers[2].status,
+            TransferStatus::Unknown("Suspendu".to_string())
+        );
+        assert_eq!(transfers[2].status.name(), "Suspendu");
+        assert!(has_next_page(html));
+        assert!(!has_next_page("<nav class=\"c-pagination\"></nav>"));
+
+        assert_eq!(
+            transfers_url(
+                "a583f3c5842c34fb00b408486ef493e0",
+                &(NaiveDate::from_ymd_opt(2026, 9, 1).unwrap()
+                    ..=NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()),
+                2
+            ),
+            format!(
+                "{}/compte/cav/a583f3c5842c34fb00b408486ef493e0/virements/suivi?fromDate=01/09/2026&toDate=18/10/2026&page=2",
+                BASE_URL
+            )
+        );
+    }
+}
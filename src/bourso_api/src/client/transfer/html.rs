@@ -0,0 +1,85 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref TAG_REGEX: Regex = Regex::new(r"(?ms)<[^>]*>").unwrap();
+    static ref SPACES_REGEX: Regex = Regex::new(r"\s+").unwrap();
+}
+
+/// Elements with the given tag and class, e.g. the rows (`tr`, `c-table__row`) of a table
+///
+/// The class must be one of the classes of the element: `c-table__cell` doesn't match an element
+/// with the `c-table__cell--date` class only. Elements are matched up to their first closing tag,
+/// so they must not contain an element with the same tag.
+///
+/// # Returns
+///
+/// The HTML of each element, tags included
+pub(crate) fn elements<'a>(html: &'a str, tag: &str, class: &str) -> Vec<&'a str> {
+    let regex = Regex::new(&format!(
+        r#"(?ms)<{tag}\b[^>]*\bclass="(?:[^"]*\s)?{class}(?:\s[^"]*)?"[^>]*>.*?</{tag}>"#,
+        tag = tag,
+        class = regex::escape(class)
+    ))
+    .unwrap();
+    regex.find_iter(html).map(|m| m.as_str()).collect()
+}
+
+/// Text of the first element with the given tag and class, see [`elements`]
+pub(crate) fn element_text(html: &str, tag: &str, class: &str) -> Option<String> {
+    elements(html, tag, class)
+        .first()
+        .map(|element| text(element))
+}
+
+/// Value of the first attribute with the given name, e.g. the `href` of a link
+pub(crate) fn attribute<'a>(html: &'a str, name: &str) -> Option<&'a str> {
+    let regex = Regex::new(&format!(r#"\b{}="(?P<value>[^"]*)""#, regex::escape(name))).unwrap();
+    regex
+        .captures(html)
+        .and_then(|c| c.name("value"))
+        .map(|m| m.as_str())
+}
+
+/// Text of an HTML fragment, with its non-breaking spaces and collapsed whitespaces
+pub(crate) fn text(html: &str) -> String {
+    let text = TAG_REGEX
+        .replace_all(html, " ")
+        .replace("&nbsp;", " ")
+        .replace("&#160;", " ")
+        .replace("&amp;", "&");
+    SPACES_REGEX.replace_all(&text, " ").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elements() {
+        let html = r#"
+        <tr class="c-table__row">
+          <td class="c-table__cell c-table__cell--label"><span>LIVRET&nbsp;A</span></td>
+          <td class="c-table__cell--amount">1&#160;500,00 €</td>
+          <td class="c-table__cell"><a href="/virements/permanents/8f3a2c/suppression">Supprimer</a></td>
+        </tr>
+        <tr class="c-table__row c-table__row--empty"></tr>"#;
+
+        let rows = elements(html, "tr", "c-table__row");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(elements(rows[0], "td", "c-table__cell").len(), 2);
+        assert_eq!(
+            element_text(rows[0], "td", "c-table__cell--label").as_deref(),
+            Some("LIVRET A")
+        );
+        assert_eq!(
+            element_text(rows[0], "td", "c-table__cell--amount").as_deref(),
+            Some("1 500,00 €")
+        );
+        assert_eq!(element_text(rows[1], "td", "c-table__cell--amount"), None);
+        assert_eq!(
+            attribute(rows[0], "href"),
+            Some("/virements/permanents/8f3a2c/suppression")
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::{
+    beneficiary::CreditAccount,
+    error::TransferError,
+    html::{element_text, elements},
+    parse_amount,
+};
+use crate::{client::BoursoWebClient, constants::BASE_URL};
+
+/// Period over which a ceiling applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl LimitPeriod {
+    /// Parse the period label of the limits table, e.g. "Journalier"
+    fn from_label(label: &str) -> Option<LimitPeriod> {
+        let label = label.to_lowercase();
+        if label.starts_with("journalier") || label.starts_with("quotidien") {
+            Some(LimitPeriod::Daily)
+        } else if label.starts_with("hebdomadaire") {
+            Some(LimitPeriod::Weekly)
+        } else if label.starts_with("mensuel") {
+            Some(LimitPeriod::Monthly)
+        } else {
+            None
+        }
+    }
+}
+
+/// Transfers a ceiling applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitScope {
+    /// Transfers to external beneficiaries
+    Beneficiaries,
+    /// Transfers between your own accounts
+    Accounts,
+    /// Every transfer
+    All,
+}
+
+impl LimitScope {
+    /// Scope of a ceiling from its label, e.g. "Virements vers vos bénéficiaires"
+    fn from_label(label: &str) -> LimitScope {
+        let label = label.to_lowercase();
+        if label.contains("bénéficiaire") || label.contains("externe") {
+            LimitScope::Beneficiaries
+        } else if label.contains("entre vos comptes") || label.contains("interne") {
+            LimitScope::Accounts
+        } else {
+            LimitScope::All
+        }
+    }
+
+    /// Whether a transfer to the given account is counted in the ceiling
+    pub fn applies_to(&self, to: &CreditAccount) -> bool {
+        matches!(
+            (self, to),
+            (LimitScope::All, _)
+                | (LimitScope::Beneficiaries, CreditAccount::Beneficiary(_))
+                | (LimitScope::Accounts, CreditAccount::Account(_))
+        )
+    }
+}
+
+/// An outgoing transfers ceiling (plafond) of an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferLimit {
+    /// e.g. "Virements vers vos bénéficiaires"
+    pub label: String,
+    pub scope: LimitScope,
+    pub period: LimitPeriod,
+    pub ceiling: f64,
+    /// Amount that can still be transferred over the current period
+    pub remaining: f64,
+}
+
+impl BoursoWebClient {
+    /// Get the outgoing transfers ceilings of an account, and how much is still available
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - The debited account id
+    ///
+    /// # Returns
+    ///
+    /// The ceilings of the account
+    #[cfg(not(tarpaulin_include))]
+    pub async fn get_transfer_limits(&self, account_id: &str) -> Result<Vec<TransferLimit>> {
+        let res = self
+            .client
+            .get(format!(
+                "{}/compte/cav/{}/virements/plafonds",
+                BASE_URL, account_id
+            ))
+            .headers(self.get_headers())
+            .send()
+            .await?;
+
+        if res.status() != 200 {
+            debug!("Transfer limits response: {:?}", res);
+            bail!(
+                "Failed to get the transfer limits of account {}",
+                account_id
+            );
+        }
+
+        Ok(extract_limits(&res.text().await?))
+    }
+}
+
+/// Check that an amount fits in the remaining amount of every ceiling applying to a transfer
+/// to the given account
+///
+/// The check fails if no ceiling applies, e.g. when none of them could be read, as the amount
+/// could not be checked.
+pub fn check_limits(
+    limits: &[TransferLimit],
+    amount: f64,
+    to: &CreditAccount,
+) -> Result<(), TransferError> {
+    let mut applying = limits
+        .iter()
+        .filter(|limit| limit.scope.applies_to(to))
+        .peekable();
+    if applying.peek().is_none() {
+        return Err(TransferError::NoApplicableLimit);
+    }
+    match applying.find(|limit| amount > limit.remaining) {
+        Some(limit) => Err(TransferError::LimitExceeded(limit.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Extract the ceilings from the rows of the limits table. Rows without a known period, e.g.
+/// the header, are skipped, and so are the ceilings whose amounts can't be read
+fn extract_limits(html: &str) -> Vec<TransferLimit> {
+    elements(html, "tr", "c-table__row")
+        .into_iter()
+        .filter_map(|row| {
+            let cell = |name: &str| {
+                element_text(row, "td", &format!("c-table__cell--{}", name)).unwrap_or_default()
+            };
+            let period = LimitPeriod::from_label(&cell("period"))?;
+            let label = cell("label");
+            match (
+                parse_amount(&cell("ceiling")),
+                parse_amount(&cell("remaining")),
+            ) {
+                (Some(ceiling), Some(remaining)) => Some(TransferLimit {
+                    scope: LimitScope::from_label(&label),
+                    label,
+                    period,
+                    ceiling,
+                    remaining,
+                }),
+                _ => {
+                    warn!(
+                        "Ignoring the {:?} ceiling '{}', its amounts can't be read",
+                        period, label
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_and_check_limits() {
+        let html = include_str!("fixtures/limits.html");
+
+        let limits = extract_limits(html);
+        // The ceiling without amounts is skipped
+        assert_eq!(limits.len(), 3);
+        assert_eq!(
+            limits[0],
+            TransferLimit {
+                label: "Virements vers vos bénéficiaires".to_string(),
+                scope: LimitScope::Beneficiaries,
+                period: LimitPeriod::Daily,
+                ceiling: 5000.0,
+                remaining: 1200.0,
+            }
+        );
+        assert_eq!(limits[1].period, LimitPeriod::Monthly);
+        assert_eq!(limits[2].scope, LimitScope::Accounts);
+        assert_eq!(limits[2].remaining, 48500.0);
+
+        let beneficiary = CreditAccount::Beneficiary(Default::default());
+        let account = CreditAccount::Account(Default::default());
+        assert!(check_limits(&limits, 1200.0, &beneficiary).is_ok());
+        assert_eq!(
+            check_limits(&limits, 1500.0, &beneficiary)
+                .unwrap_err()
+                .to_string(),
+            "The amount exceeds the daily ceiling of 5000.00 EUR (Virements vers vos bénéficiaires), 1200.00 EUR remaining"
+        );
+        // Only the ceiling of the transfers between your accounts applies
+        assert!(check_limits(&limits, 1500.0, &account).is_ok());
+        assert!(matches!(
+            check_limits(&limits[..2], 1500.0, &account),
+            Err(TransferError::NoApplicableLimit)
+        ));
+        assert!(matches!(
+            check_limits(&[], 10.0, &beneficiary),
+            Err(TransferError::NoApplicableLimit)
+        ));
+        assert!(matches!(
+            check_limits(&limits, 50000.0, &account),
+            Err(TransferError::LimitExceeded(TransferLimit {
+                scope: LimitScope::Accounts,
+                ..
+            }))
+        ));
+    }
+}
@@ -1,11 +1,41 @@
 #[cfg(not(tarpaulin_include))]
 use crate::account::{Account, AccountKind};
-use crate::{client::transfer::error::TransferError, client::BoursoWebClient, constants::BASE_URL};
+use crate::{client::BoursoWebClient, constants::BASE_URL};
 use anyhow::{bail, Context, Result};
 use futures_util::stream::Stream;
-use tracing::debug;
-
-mod error;
+use tracing::{debug, warn};
+
+pub mod batch;
+pub mod beneficiary;
+pub mod error;
+pub mod history;
+mod html;
+pub mod limits;
+pub mod preview;
+pub mod schedule;
+
+use beneficiary::CreditAccount;
+use error::{extract_error_message, StepFailure, TransferError};
+use preview::{
+    check_summary, extract_credit_accounts, extract_receipt, extract_summary, TransferReceipt,
+    TransferSummary,
+};
+use schedule::TransferSchedule;
+
+/// Optional parameters of a transfer
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TransferOptions {
+    /// Reason for the transfer (max 50 characters), defaults to "Virement depuis BoursoBank"
+    pub reason: Option<String>,
+    /// When the transfer is executed, right away by default
+    pub schedule: TransferSchedule,
+    /// Stop before confirming the transfer, the last progress update being its preview
+    pub dry_run: bool,
+    /// Check the amount against the ceilings of the debited account before starting the
+    /// transfer, see [`BoursoWebClient::get_transfer_limits`]. Only immediate transfers are
+    /// checked, as the ceilings at the execution date of a scheduled transfer are not known
+    pub check_limits: bool,
+}
 
 #[derive(Debug, Clone)]
 pub enum TransferProgress {
@@ -18,7 +48,10 @@ pub enum TransferProgress {
     SubmittingStep5,
     SettingReason,
     ConfirmingTransfer,
-    Completed,
+    /// Receipt of the confirmed transfer
+    Completed(TransferReceipt),
+    /// Summary of a dry run transfer, which is not confirmed
+    Preview(TransferSummary),
 }
 
 impl TransferProgress {
@@ -34,7 +67,8 @@ impl TransferProgress {
             TransferProgress::SubmittingStep5 => 7,
             TransferProgress::SettingReason => 8,
             TransferProgress::ConfirmingTransfer => 9,
-            TransferProgress::Completed => 10,
+            TransferProgress::Completed(_) => 10,
+            TransferProgress::Preview(_) => 10,
         }
     }
 
@@ -54,7 +88,8 @@ impl TransferProgress {
             TransferProgress::SubmittingStep5 => "Submitting intermediate step",
             TransferProgress::SettingReason => "Setting transfer reason",
             TransferProgress::ConfirmingTransfer => "Confirming transfer",
-            TransferProgress::Completed => "Transfer completed",
+            TransferProgress::Completed(_) => "Transfer completed",
+            TransferProgress::Preview(_) => "Transfer ready to be confirmed",
         }
     }
 }
@@ -72,7 +107,12 @@ impl BoursoWebClient {
 
         if res.status() != 302 {
             debug!("Init transfer response: {:?}", res);
-            bail!(TransferError::TransferInitiationFailed);
+            let status = res.status().as_u16();
+            let message = extract_error_message(&res.text().await?);
+            bail!(TransferError::TransferInitiationFailed(StepFailure {
+                status,
+                message
+            }));
         }
 
         let location = res
@@ -97,12 +137,7 @@ impl BoursoWebClient {
     async fn extract_flow_instance(&self, url: &str) -> Result<String> {
         let res = self.client.get(url).send().await?;
 
-        if res.status() != 200 {
-            debug!("First transfer step response: {:?}", res);
-            bail!(TransferError::TransferInitiationFailed);
-        }
-
-        let res_text = res.text().await?;
+        let res_text = check_step(res, TransferError::TransferInitiationFailed).await?;
         let re = regex::Regex::new(r#"name="flow_ImmediateCashTransfer_instance" value="([^"]+)""#)
             .unwrap();
         let flow_instance = re
@@ -116,13 +151,15 @@ impl BoursoWebClient {
     }
 
     /// Set the debit account (step 2)
+    ///
+    /// Returns the credit account step page, listing the accounts that can be credited
     #[cfg(not(tarpaulin_include))]
     async fn set_debit_account(
         &self,
         from_account: &str,
         transfer_id: &str,
         flow_instance: &str,
-    ) -> Result<()> {
+    ) -> Result<String> {
         let data = reqwest::multipart::Form::new()
             .text(
                 "flow_ImmediateCashTransfer_instance",
@@ -138,12 +175,7 @@ impl BoursoWebClient {
 
         let res = self.client.post(&url).multipart(data).send().await?;
 
-        if res.status() != 200 {
-            debug!("Set debit account response: {:?}", res);
-            bail!(TransferError::SetDebitAccountFailed);
-        }
-
-        Ok(())
+        check_step(res, TransferError::SetDebitAccountFailed).await
     }
 
     /// Set the credit account (step 3)
@@ -177,10 +209,7 @@ impl BoursoWebClient {
 
         let res = self.client.post(&url).multipart(data).send().await?;
 
-        if res.status() != 200 {
-            debug!("Set credit account response: {:?}", res);
-            bail!(TransferError::SetCreditAccountFailed);
-        }
+        check_step(res, TransferError::SetCreditAccountFailed).await?;
 
         Ok(())
     }
@@ -211,10 +240,7 @@ impl BoursoWebClient {
 
         let res = self.client.post(&url).multipart(data).send().await?;
 
-        if res.status() != 200 {
-            debug!("Set amount response: {:?}", res);
-            bail!(TransferError::SetAmountFailed);
-        }
+        check_step(res, TransferError::SetAmountFailed).await?;
 
         Ok(())
     }
@@ -247,15 +273,14 @@ impl BoursoWebClient {
             .send()
             .await?;
 
-        if res.status() != 200 {
-            debug!("Submit transfer response: {:?}", res);
-            bail!(TransferError::Step5Failed);
-        }
+        check_step(res, TransferError::Step5Failed).await?;
 
         Ok(())
     }
 
-    /// Set the transfer reason (step 8)
+    /// Set the transfer reason and its scheduling (step 8)
+    ///
+    /// Returns the confirmation step page, summarizing the transfer
     #[cfg(not(tarpaulin_include))]
     async fn set_transfer_reason(
         &self,
@@ -263,15 +288,19 @@ impl BoursoWebClient {
         transfer_id: &str,
         flow_instance: &str,
         transfer_reason: &str,
-    ) -> Result<()> {
-        let data = reqwest::multipart::Form::new()
+        schedule: &TransferSchedule,
+    ) -> Result<String> {
+        let mut data = reqwest::multipart::Form::new()
             .text(
                 "flow_ImmediateCashTransfer_instance",
                 flow_instance.to_string(),
             )
             .text("flow_ImmediateCashTransfer_step", "7".to_string())
-            .text("Characteristics[label]", transfer_reason.to_string())
-            .text("Characteristics[schedulingType]", "1".to_string()) // 1 = unique
+            .text("Characteristics[label]", transfer_reason.to_string());
+        for (name, value) in schedule.form_fields() {
+            data = data.text(name, value);
+        }
+        let data = data
             .text("flow_ImmediateCashTransfer_transition", "".to_string())
             .text("flow_ImmediateCashTransfer_transition", "".to_string())
             .text("submit", "".to_string());
@@ -283,22 +312,19 @@ impl BoursoWebClient {
 
         let res = self.client.post(&url).multipart(data).send().await?;
 
-        if res.status() != 200 {
-            debug!("Set reason response: {:?}", res);
-            bail!(TransferError::SetReasonFailed);
-        }
-
-        Ok(())
+        check_step(res, TransferError::SetReasonFailed).await
     }
 
     /// Confirm and finalize the transfer (step 10)
+    ///
+    /// Returns the receipt parsed from the confirmation page
     #[cfg(not(tarpaulin_include))]
     async fn confirm_transfer(
         &self,
         from_account: &str,
         transfer_id: &str,
         flow_instance: &str,
-    ) -> Result<()> {
+    ) -> Result<TransferReceipt> {
         let data = reqwest::multipart::Form::new()
             .text(
                 "flow_ImmediateCashTransfer_instance",
@@ -319,18 +345,26 @@ impl BoursoWebClient {
             .send()
             .await?;
 
-        if res.status() != 200 {
-            debug!("Confirm transfer response: {:?}", res);
-            bail!(TransferError::SubmitTransferFailed);
-        }
-
-        let body = res.text().await?;
+        let status = res.status().as_u16();
+        let body = check_step(res, TransferError::SubmitTransferFailed).await?;
 
         if body.as_str().contains("Confirmation") {
-            Ok(())
+            let receipt = extract_receipt(&body);
+            if receipt.reference.is_none() {
+                warn!("The transfer is confirmed but its reference is not shown, check it in your account");
+                debug!("Cannot find the transfer reference in response {:?}", body);
+            }
+            if receipt.amount.is_none() {
+                warn!("The transfer is confirmed but its amount is not shown, check it in your account");
+                debug!("Cannot find the transfer amount in response {:?}", body);
+            }
+            Ok(receipt)
         } else {
             debug!("Cannot find confirmation message in response {:?}", body);
-            bail!(TransferError::InvalidTransfer);
+            bail!(TransferError::InvalidTransfer(StepFailure {
+                status,
+                message: extract_error_message(&body),
+            }));
         }
     }
 
@@ -339,8 +373,8 @@ impl BoursoWebClient {
     /// ## Arguments
     /// - `amount`: Amount to transfer (must be >= 10.0)
     /// - `from_account`: Source account
-    /// - `to_account`: Destination account
-    /// - `reason`: Optional reason for the transfer (max 50 characters)
+    /// - `to_account`: Destination account, one of your accounts or a saved beneficiary
+    /// - `options`: Reason (max 50 characters) and scheduling of the transfer
     ///
     /// ## Returns
     /// A stream of progress updates for the transfer.
@@ -349,40 +383,59 @@ impl BoursoWebClient {
         &self,
         amount: f64,
         from_account: Account,
-        to_account: Account,
-        reason: Option<String>,
+        to_account: impl Into<CreditAccount>,
+        options: TransferOptions,
     ) -> impl Stream<Item = Result<TransferProgress>> + '_ {
+        let to_account = to_account.into();
+
         async_stream::stream! {
             // Validation
             yield Ok(TransferProgress::Validating);
 
-            if amount < 10.0 {
-                yield Err(TransferError::AmountTooLow.into());
+            if let Err(e) = validate_transfer(amount, options.reason.as_deref()) {
+                yield Err(e.into());
                 return;
             }
 
+            if !options.schedule.is_valid(chrono::Local::now().date_naive()) {
+                yield Err(TransferError::InvalidSchedule.into());
+                return;
+            }
+
+            // The remaining amounts are today's ones: they say nothing about the ceilings at the
+            // execution dates of a scheduled transfer
+            if options.check_limits && options.schedule != TransferSchedule::Immediate {
+                warn!("The ceilings are only checked for immediate transfers, this one is not checked");
+            } else if options.check_limits {
+                match self.get_transfer_limits(&from_account.id).await {
+                    Ok(limits) => {
+                        if let Err(e) = limits::check_limits(&limits, amount, &to_account) {
+                            yield Err(e.into());
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+            }
+
             debug!(
                 "Initiating transfer of {:.2} EUR from account {} to account {}",
                 amount,
                 from_account.id,
-                to_account.id
+                to_account.id()
             );
 
             let transfer_from_banking = from_account.kind == AccountKind::Banking;
             let from_account_id = from_account.id.clone();
-            let to_account_id = to_account.id.clone();
+            let to_account_id = to_account.id().to_string();
 
-            // Default reason if none provided, else use provided reason and
-            // warn if the reason is too long (> 50 characters)
-            let transfer_reason = if let Some(r) = reason {
-                if r.len() > 50 {
-                    yield Err(TransferError::ReasonIsTooLong.into());
-                    return;
-                }
-                r
-            } else {
-                "Virement depuis BoursoBank".to_string()
-            };
+            // Default reason if none provided
+            let transfer_reason = options
+                .reason
+                .unwrap_or_else(|| "Virement depuis BoursoBank".to_string());
 
             // Step 1: Initialize transfer and get transfer ID
             yield Ok(TransferProgress::InitializingTransfer);
@@ -411,9 +464,23 @@ impl BoursoWebClient {
 
             // Step 2: Set debit account
             yield Ok(TransferProgress::SettingDebitAccount);
-            if let Err(e) = self.set_debit_account(&from_account_id, &transfer_id, &flow_instance)
+            let credit_step_page = match self
+                .set_debit_account(&from_account_id, &transfer_id, &flow_instance)
                 .await {
-                yield Err(e);
+                Ok(page) => page,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            // Fail early when the destination is not offered for this debit account
+            let allowed_credit_accounts = extract_credit_accounts(&credit_step_page);
+            if !allowed_credit_accounts.is_empty()
+                && !allowed_credit_accounts.contains(&to_account_id)
+            {
+                debug!("Allowed credit accounts: {:?}", allowed_credit_accounts);
+                yield Err(TransferError::CreditAccountNotAllowed.into());
                 return;
             }
 
@@ -449,26 +516,113 @@ impl BoursoWebClient {
 
             // Step 10: Set reason
             yield Ok(TransferProgress::SettingReason);
-            if let Err(e) = self.set_transfer_reason(
+            let confirmation_step_page = match self.set_transfer_reason(
                 &from_account_id,
                 &transfer_id,
                 &flow_instance,
                 &transfer_reason,
+                &options.schedule,
             )
             .await {
-                yield Err(e);
+                Ok(page) => page,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            if options.dry_run {
+                match extract_summary(&confirmation_step_page) {
+                    Some(summary) => match check_summary(&summary, amount, &from_account, &to_account) {
+                        Ok(()) => yield Ok(TransferProgress::Preview(summary)),
+                        Err(e) => yield Err(e.into()),
+                    },
+                    None => {
+                        debug!("Confirmation step page: {:?}", confirmation_step_page);
+                        yield Err(TransferError::IncompleteSummary.into());
+                    }
+                }
                 return;
             }
 
             // Step 12: Confirm transfer
             yield Ok(TransferProgress::ConfirmingTransfer);
-            if let Err(e) = self.confirm_transfer(&from_account_id, &transfer_id, &flow_instance)
+            match self.confirm_transfer(&from_account_id, &transfer_id, &flow_instance)
                 .await {
-                yield Err(e);
-                return;
+                Ok(receipt) => yield Ok(TransferProgress::Completed(receipt)),
+                Err(e) => yield Err(e),
             }
-
-            yield Ok(TransferProgress::Completed);
         }
     }
 }
+
+/// Check the response of a step of the transfer form
+///
+/// The step fails if the response is not a 200, or if the form of the returned page shows an
+/// error, e.g. an insufficient balance. See [`extract_error_message`].
+///
+/// # Returns
+///
+/// The page returned by the step
+#[cfg(not(tarpaulin_include))]
+async fn check_step(
+    res: reqwest::Response,
+    error: fn(StepFailure) -> TransferError,
+) -> Result<String> {
+    let status = res.status().as_u16();
+    let page = res.text().await?;
+    let message = extract_error_message(&page);
+
+    if status != 200 || message.is_some() {
+        debug!("Transfer step response ({}): {:?}", status, page);
+        bail!(error(StepFailure { status, message }));
+    }
+
+    Ok(page)
+}
+
+/// Check a transfer against Bourso rules: at least 10 EUR, and a reason of at most 50 characters
+pub(crate) fn validate_transfer(amount: f64, reason: Option<&str>) -> Result<(), TransferError> {
+    if amount < 10.0 {
+        return Err(TransferError::AmountTooLow);
+    }
+    if reason.is_some_and(|r| r.len() > 50) {
+        return Err(TransferError::ReasonIsTooLong);
+    }
+    Ok(())
+}
+
+/// Parse an amount such as "1 500,00 €", "1.500,00 €" or "−50,00 €". Spaces and dots are
+/// thousands separators, the comma is the decimal separator
+///
+/// # Returns
+///
+/// The amount, or None if the value doesn't contain a valid amount
+pub(crate) fn parse_amount(value: &str) -> Option<f64> {
+    value
+        .chars()
+        .filter_map(|c| match c {
+            '0'..='9' => Some(c),
+            ',' => Some('.'),
+            '-' | '−' => Some('-'),
+            _ => None,
+        })
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("1 500,00 €"), Some(1500.0));
+        assert_eq!(parse_amount("1.500,00 €"), Some(1500.0));
+        assert_eq!(parse_amount("−50,50 €"), Some(-50.5));
+        assert_eq!(parse_amount("5\u{a0}000,00\u{a0}€"), Some(5000.0));
+        assert_eq!(parse_amount("Illimité"), None);
+        assert_eq!(parse_amount(""), None);
+    }
+}
@@ -0,0 +1,279 @@
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{beneficiary::CreditAccount, error::TransferError, html::text, parse_amount};
+use crate::account::Account;
+
+lazy_static! {
+    static ref CREDIT_ACCOUNT_REGEX: Regex =
+        Regex::new(r#"name="CreditAccount\[credit\]"[^>]*?value="(?P<id>[^"]+)""#).unwrap();
+    static ref SUMMARY_ROW_REGEX: Regex = Regex::new(
+        r#"(?ms)<dt[^>]*>\s*(?P<label>.*?)\s*</dt>\s*<dd[^>]*>\s*(?P<value>.*?)\s*</dd>"#
+    )
+    .unwrap();
+}
+
+/// Summary of a transfer, as shown by Bourso before it is confirmed
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSummary {
+    pub amount: f64,
+    /// Debited account, as labelled by Bourso
+    pub debit_account: String,
+    /// Credited account or beneficiary, as labelled by Bourso
+    pub credit_account: String,
+    /// DD/MM/YYYY
+    pub execution_date: String,
+    pub fees: f64,
+}
+
+/// Receipt of a confirmed transfer, to link it to the resulting transaction
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferReceipt {
+    /// Reference of the transfer given by Bourso, None if it is not shown on the confirmation page
+    pub reference: Option<String>,
+    pub execution_date: Option<NaiveDate>,
+    /// None if it is not shown on the confirmation page
+    pub amount: Option<f64>,
+}
+
+/// Ids of the accounts that can be credited from the debited account, offered by the
+/// credit account step of the transfer form
+pub(crate) fn extract_credit_accounts(html: &str) -> Vec<String> {
+    CREDIT_ACCOUNT_REGEX
+        .captures_iter(html)
+        .map(|c| c["id"].to_string())
+        .collect()
+}
+
+/// Extract the summary from the confirmation step of the transfer form
+///
+/// # Returns
+///
+/// The summary, or None if the amount or one of the accounts is not shown or can't be parsed
+pub(crate) fn extract_summary(html: &str) -> Option<TransferSummary> {
+    let mut summary = TransferSummary::default();
+    let mut amount = None;
+    for row in SUMMARY_ROW_REGEX.captures_iter(html) {
+        let label = text(&row["label"]).to_lowercase();
+        let value = text(&row["value"]);
+        if label.starts_with("montant") {
+            amount = parse_amount(&value);
+        } else if label.starts_with("compte à débiter") || label.starts_with("compte débité") {
+            summary.debit_account = value;
+        } else if label.starts_with("compte à créditer")
+            || label.starts_with("compte crédité")
+            || label.starts_with("bénéficiaire")
+        {
+            summary.credit_account = value;
+        } else if label.starts_with("date") {
+            summary.execution_date = value;
+        } else if label.starts_with("frais") {
+            // e.g. "Gratuit"
+            summary.fees = parse_amount(&value).unwrap_or(0.0);
+        }
+    }
+    if summary.debit_account.is_empty() || summary.credit_account.is_empty() {
+        return None;
+    }
+    summary.amount = amount?;
+    Some(summary)
+}
+
+/// Check that a summary shows the requested amount and accounts
+///
+/// An account matches when its label contains the name of the account, ignoring the case and
+/// the spaces. A beneficiary also matches when the label contains its IBAN.
+pub(crate) fn check_summary(
+    summary: &TransferSummary,
+    amount: f64,
+    from: &Account,
+    to: &CreditAccount,
+) -> Result<(), TransferError> {
+    if (summary.amount - amount).abs() >= 0.005 {
+        return Err(TransferError::AmountMismatch {
+            requested: amount,
+            shown: summary.amount,
+        });
+    }
+    if !shows_account(&summary.debit_account, &[&from.name]) {
+        return Err(TransferError::AccountMismatch {
+            requested: from.name.clone(),
+            shown: summary.debit_account.clone(),
+        });
+    }
+    let credited = match to {
+        CreditAccount::Account(account) => vec![account.name.as_str()],
+        CreditAccount::Beneficiary(beneficiary) => {
+            vec![beneficiary.name.as_str(), beneficiary.iban.as_str()]
+        }
+    };
+    if !shows_account(&summary.credit_account, &credited) {
+        return Err(TransferError::AccountMismatch {
+            requested: to.name().to_string(),
+            shown: summary.credit_account.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Whether a label contains one of the given names, ignoring the case and the spaces
+fn shows_account(label: &str, names: &[&str]) -> bool {
+    let normalize = |value: &str| {
+        value
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase()
+    };
+    let label = normalize(label);
+    names
+        .iter()
+        .map(|name| normalize(name))
+        .any(|name| !name.is_empty() && label.contains(&name))
+}
+
+/// Extract the receipt from the confirmation page of the transfer form
+pub(crate) fn extract_receipt(html: &str) -> TransferReceipt {
+    let mut receipt = TransferReceipt::default();
+    for row in SUMMARY_ROW_REGEX.captures_iter(html) {
+        let label = text(&row["label"]).to_lowercase();
+        let value = text(&row["value"]);
+        if label.starts_with("référence") {
+            receipt.reference = Some(value).filter(|reference| !reference.is_empty());
+        } else if label.starts_with("montant") {
+            receipt.amount = parse_amount(&value);
+        } else if label.starts_with("date") {
+            receipt.execution_date = NaiveDate::parse_from_str(&value, "%d/%m/%Y").ok();
+        }
+    }
+    receipt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::transfer::beneficiary::Beneficiary;
+
+    #[test]
+    fn test_extract_credit_accounts() {
+        let html = r#"
+        <input type="radio" id="CreditAccount_credit_0" name="CreditAccount[credit]" value="b419f2d28010bafb331badef11bba51e">
+        <input type="radio" id="CreditAccount_credit_1" name="CreditAccount[credit]" value="4c2f1a">"#;
+        assert_eq!(
+            extract_credit_accounts(html),
+            vec!["b419f2d28010bafb331badef11bba51e", "4c2f1a"]
+        );
+    }
+
+    #[test]
+    fn test_extract_summary() {
+        let html = r#"
+        <dl class="c-summary">
+          <dt>Montant</dt><dd><strong>1 500,00 €</strong></dd>
+          <dt>Compte à débiter</dt>
+          <dd>BOURSOBANK <span>M JOHN DOE</span></dd>
+          <dt>Compte à créditer</dt><dd>LIVRET A</dd>
+          <dt>Date d'exécution</dt><dd>01/11/2026</dd>
+          <dt>Frais</dt><dd>0,00 €</dd>
+        </dl>"#;
+
+        assert_eq!(
+            extract_summary(html),
+            Some(TransferSummary {
+                amount: 1500.0,
+                debit_account: "BOURSOBANK M JOHN DOE".to_string(),
+                credit_account: "LIVRET A".to_string(),
+                execution_date: "01/11/2026".to_string(),
+                fees: 0.0,
+            })
+        );
+
+        let html = r#"
+        <dl class="c-summary">
+          <dt>Compte à débiter</dt><dd>BOURSOBANK M JOHN DOE</dd>
+          <dt>Compte à créditer</dt><dd>LIVRET A</dd>
+        </dl>"#;
+        assert_eq!(extract_summary(html), None);
+    }
+
+    #[test]
+    fn test_check_summary() {
+        let summary = TransferSummary {
+            amount: 1500.0,
+            debit_account: "BOURSOBANK M JOHN DOE".to_string(),
+            credit_account: "Livret A - M John Doe".to_string(),
+            execution_date: "01/11/2026".to_string(),
+            fees: 0.0,
+        };
+        let from = Account {
+            name: "BoursoBank".to_string(),
+            ..Default::default()
+        };
+        let livret = CreditAccount::Account(Account {
+            name: "LIVRET A".to_string(),
+            ..Default::default()
+        });
+
+        assert!(check_summary(&summary, 1500.0, &from, &livret).is_ok());
+        assert!(matches!(
+            check_summary(&summary, 1400.0, &from, &livret),
+            Err(TransferError::AmountMismatch { .. })
+        ));
+        assert_eq!(
+            check_summary(
+                &summary,
+                1500.0,
+                &from,
+                &CreditAccount::Account(Account {
+                    name: "LDDS".to_string(),
+                    ..Default::default()
+                })
+            )
+            .unwrap_err()
+            .to_string(),
+            "The transfer summary shows the account 'Livret A - M John Doe' instead of 'LDDS'"
+        );
+
+        // A beneficiary is also matched by its IBAN
+        let summary = TransferSummary {
+            credit_account: "Mme Jane Doe FR76 3000 6000 0112 3456 7890 189".to_string(),
+            ..summary
+        };
+        let beneficiary = CreditAccount::Beneficiary(Beneficiary {
+            name: "Jane".to_string(),
+            iban: "FR7630006000011234567890189".to_string(),
+            ..Default::default()
+        });
+        assert!(check_summary(&summary, 1500.0, &from, &beneficiary).is_ok());
+    }
+
+    #[test]
+    fn test_extract_receipt() {
+        let html = r#"
+        <h2>Confirmation de votre virement</h2>
+        <dl class="c-summary">
+          <dt>Référence</dt><dd>VIR-20261018-4F2A91</dd>
+          <dt>Montant</dt><dd>1 500,00 €</dd>
+          <dt>Date d'exécution</dt><dd>18/10/2026</dd>
+        </dl>"#;
+
+        assert_eq!(
+            extract_receipt(html),
+            TransferReceipt {
+                reference: Some("VIR-20261018-4F2A91".to_string()),
+                execution_date: NaiveDate::from_ymd_opt(2026, 10, 18),
+                amount: Some(1500.0),
+            }
+        );
+
+        let html = r#"
+        <dl class="c-summary">
+          <dt>Référence</dt><dd>VIR-20261018-4F2A91</dd>
+        </dl>"#;
+        assert_eq!(extract_receipt(html).amount, None);
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::{
+    html::{attribute, element_text, elements},
+    parse_amount,
+};
+use crate::{client::BoursoWebClient, constants::BASE_URL};
+
+lazy_static! {
+    static ref CANCEL_LINK_REGEX: Regex =
+        Regex::new(r#"/virements/permanents/(?P<id>[^/"]+)/suppression"#).unwrap();
+    static ref CANCEL_TOKEN_REGEX: Regex =
+        Regex::new(r#"deleteTransfer\[_token\]"[^>]*?value="(?P<token>[^"]+)""#).unwrap();
+}
+
+/// Frequency of a recurring transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFrequency {
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+impl TransferFrequency {
+    /// Value of the `Characteristics[periodicity]` field of the transfer form
+    pub fn value(&self) -> &str {
+        match self {
+            TransferFrequency::Weekly => "WEEKLY",
+            TransferFrequency::Monthly => "MONTHLY",
+            TransferFrequency::Quarterly => "QUARTERLY",
+        }
+    }
+}
+
+impl std::str::FromStr for TransferFrequency {
+    type Err = anyhow::Error;
+
+    /// Parse a frequency such as `week`, `month` or `quarter`
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "week" | "weekly" => Ok(TransferFrequency::Weekly),
+            "month" | "monthly" => Ok(TransferFrequency::Monthly),
+            "quarter" | "quarterly" => Ok(TransferFrequency::Quarterly),
+            _ => Err(anyhow::anyhow!("Invalid transfer frequency: {}", s)),
+        }
+    }
+}
+
+/// When a transfer is executed
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferSchedule {
+    /// Executed right away
+    #[default]
+    Immediate,
+    /// Executed once, at the given date
+    Deferred(NaiveDate),
+    /// Standing order, executed at each period from `start` until `end` (or until cancelled)
+    Recurring {
+        frequency: TransferFrequency,
+        start: NaiveDate,
+        end: Option<NaiveDate>,
+    },
+}
+
+impl TransferSchedule {
+    /// Whether the schedule can be submitted: its dates must be after today, and a recurring
+    /// transfer must end after its first execution
+    pub fn is_valid(&self, today: NaiveDate) -> bool {
+        match self {
+            TransferSchedule::Immediate => true,
+            TransferSchedule::Deferred(date) => *date > today,
+            TransferSchedule::Recurring { start, end, .. } => {
+                *start > today && end.is_none_or(|end| end > *start)
+            }
+        }
+    }
+
+    /// Fields of the "characteristics" step of the transfer form
+    pub fn form_fields(&self) -> Vec<(&'static str, String)> {
+        let format = |date: &NaiveDate| date.format("%d/%m/%Y").to_string();
+        match self {
+            // 1 = unique
+            TransferSchedule::Immediate => vec![("Characteristics[schedulingType]", "1".into())],
+            // 2 = deferred
+            TransferSchedule::Deferred(date) => vec![
+                ("Characteristics[schedulingType]", "2".into()),
+                ("Characteristics[executionDate]", format(date)),
+            ],
+            // 3 = permanent
+            TransferSchedule::Recurring {
+                frequency,
+                start,
+                end,
+            } => vec![
+                ("Characteristics[schedulingType]", "3".into()),
+                ("Characteristics[periodicity]", frequency.value().into()),
+                ("Characteristics[firstExecutionDate]", format(start)),
+                (
+                    "Characteristics[lastExecutionDate]",
+                    end.as_ref().map(format).unwrap_or_default(),
+                ),
+            ],
+        }
+    }
+}
+
+/// A deferred transfer or a standing order, not executed yet
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandingOrder {
+    /// Id used to cancel it
+    pub id: String,
+    /// Name of the credited account or beneficiary
+    pub beneficiary: String,
+    pub label: String,
+    pub amount: f64,
+    /// e.g. "Mensuel", None for a deferred transfer
+    pub frequency: Option<String>,
+    pub next_execution: NaiveDate,
+}
+
+impl BoursoWebClient {
+    /// Get the deferred transfers and standing orders of an account
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - The debited account id
+    ///
+    /// # Returns
+    ///
+    /// The transfers not executed yet
+    #[cfg(not(tarpaulin_include))]
+    pub async fn list_standing_orders(&self, account_id: &str) -> Result<Vec<StandingOrder>> {
+        let res = self
+            .client
+            .get(standing_orders_url(account_id))
+            .headers(self.get_headers())
+            .send()
+            .await?;
+
+        if res.status() != 200 {
+            debug!("Standing orders response: {:?}", res);
+            bail!(
+                "Failed to get the standing orders of account {}",
+                account_id
+            );
+        }
+
+        Ok(extract_standing_orders(&res.text().await?))
+    }
+
+    /// Cancel a deferred transfer or a standing order
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - The debited account id
+    /// * `order_id` - The id of the standing order, see [`BoursoWebClient::list_standing_orders`]
+    ///
+    /// The standing orders are listed again afterwards to check that it is gone, as Bourso
+    /// redirects to a page both when the cancellation succeeds and when it fails
+    #[cfg(not(tarpaulin_include))]
+    pub async fn cancel_standing_order(&self, account_id: &str, order_id: &str) -> Result<()> {
+        let url = format!(
+            "{}/{}/suppression",
+            standing_orders_url(account_id),
+            order_id
+        );
+
+        let page = self
+            .client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await?
+            .text()
+            .await?;
+        let token = CANCEL_TOKEN_REGEX
+            .captures(&page)
+            .and_then(|c| c.name("token"))
+            .map(|m| m.as_str().to_string())
+            .context("Failed to find the cancellation form, does the standing order exist?")?;
+
+        let res = self
+            .client
+            .post(&url)
+            .headers(self.get_headers())
+            .form(&[("deleteTransfer[_token]", token.as_str())])
+            .send()
+            .await?;
+
+        if !res.status().is_success() && !res.status().is_redirection() {
+            debug!("Cancel standing order response: {:?}", res);
+            bail!("Failed to cancel the standing order {}", order_id);
+        }
+
+        if self
+            .list_standing_orders(account_id)
+            .await?
+            .iter()
+            .any(|order| order.id == order_id)
+        {
+            debug!("Cancel standing order response: {:?}", res);
+            bail!(
+                "The standing order {} is still listed after its cancellation",
+                order_id
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn standing_orders_url(account_id: &str) -> String {
+    format!(
+        "{}/compte/cav/{}/virements/permanents",
+        BASE_URL, account_id
+    )
+}
+
+/// Extract the standing orders from the rows of the standing orders table. The id of an order
+/// is read from its cancellation link, and rows without a valid amount or next execution date
+/// are skipped
+fn extract_standing_orders(html: &str) -> Vec<StandingOrder> {
+    elements(html, "tr", "c-table__row")
+        .into_iter()
+        .filter_map(|row| {
+            let cell = |name: &str| {
+                element_text(row, "td", &format!("c-table__cell--{}", name)).unwrap_or_default()
+            };
+            let id = CANCEL_LINK_REGEX.captures(attribute(row, "href")?)?["id"].to_string();
+
+            let next_execution =
+                match NaiveDate::parse_from_str(&cell("next-execution"), "%d/%m/%Y") {
+                    Ok(date) => date,
+                    Err(_) => {
+                        debug!("Skipping standing order {} without next execution", id);
+                        return None;
+                    }
+                };
+            let amount = match parse_amount(&cell("amount")) {
+                Some(amount) => amount,
+                None => {
+                    debug!("Skipping standing order {} without amount", id);
+                    return None;
+                }
+            };
+            let frequency = cell("frequency");
+
+            Some(StandingOrder {
+                id,
+                beneficiary: cell("beneficiary"),
+                label: cell("label"),
+                amount,
+                frequency: (!frequency.is_empty()).then_some(frequency),
+                next_execution,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form_fields() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let today = date("2026-10-18");
+
+        let deferred = TransferSchedule::Deferred(date("2026-11-01"));
+        assert!(deferred.is_valid(today));
+        assert!(!TransferSchedule::Deferred(today).is_valid(today));
+        assert_eq!(
+            deferred.form_fields(),
+            vec![
+                ("Characteristics[schedulingType]", "2".to_string()),
+                ("Characteristics[executionDate]", "01/11/2026".to_string()),
+            ]
+        );
+
+        let recurring = TransferSchedule::Recurring {
+            frequency: "month".parse().unwrap(),
+            start: date("2026-11-05"),
+            end: None,
+        };
+        assert!(recurring.is_valid(today));
+        assert_eq!(
+            recurring.form_fields()[1..],
+            [
+                ("Characteristics[periodicity]", "MONTHLY".to_string()),
+                (
+                    "Characteristics[firstExecutionDate]",
+                    "05/11/2026".to_string()
+                ),
+                ("Characteristics[lastExecutionDate]", "".to_string()),
+            ]
+        );
+        assert!(!TransferSchedule::Recurring {
+            frequency: TransferFrequency::Weekly,
+            start: date("2026-11-05"),
+            end: Some(date("2026-11-01")),
+        }
+        .is_valid(today));
+    }
+
+    #[test]
+    fn test_extract_standing_orders() {
+        let html = include_str!("fixtures/standing_orders.html");
+
+        let orders = extract_standing_orders(html);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(
+            orders[0],
+            StandingOrder {
+                id: "8f3a2c".to_string(),
+                beneficiary: "LIVRET A".to_string(),
+                label: "Epargne".to_string(),
+                amount: 1500.0,
+                frequency: Some("Mensuel".to_string()),
+                next_execution: NaiveDate::from_ymd_opt(2026, 11, 5).unwrap(),
+            }
+        );
+        assert_eq!(orders[1].id, "9b1d7e");
+        assert_eq!(orders[1].amount, 50.5);
+        assert_eq!(orders[1].frequency, None);
+    }
+}
@@ -3,4 +3,8 @@ pub const SAVINGS_PATTERN: &str = r"(?ms)data-summary-savings>(.*?)</ul>";
 pub const BANKING_PATTERN: &str = r"(?ms)data-summary-bank>(.*?)</div>";
 pub const TRADING_PATTERN: &str = r"(?ms)data-summary-trading>(.*?)</div>";
 pub const LOANS_PATTERN: &str = r"(?ms)data-summary-loan>(.*?)</div>";
-pub const ACCOUNT_PATTERN: &str = r"(?ms)/compte/(.*?)?/?(?P<id>[a-f0-9]{32})/(.*?)Solde\s:\s(?P<balance>[\d\s−-]+,?\d{0,2})\s€.+?c-info-box__account-label.+?>(?P<name>.+?)</span>.+?c-info-box__account-sub-label.+?>(?P<bank_name>.+?)</span>";
\ No newline at end of file
+pub const ACCOUNT_PATTERN: &str = r"(?ms)/compte/(.*?)?/?(?P<id>[a-f0-9]{32})/(.*?)Solde\s:\s(?P<balance>[\d\s−-]+,?\d{0,2})\s€.+?c-info-box__account-label.+?>(?P<name>.+?)</span>.+?c-info-box__account-sub-label.+?>(?P<bank_name>.+?)</span>";
+/// API used for public data (quotes) when no configuration has been fetched, i.e. without logging in
+pub const PUBLIC_API_URL: &str = "https://api.boursobank.com/services/api/v1.7";
+/// Default number of requests sent at the same time by the batch methods of the client
+pub const DEFAULT_CONCURRENCY: usize = 4;
@@ -0,0 +1,376 @@
+use anyhow::{Context, Result};
+use bourso_api::client::{
+    trade::{
+        calendar::ExchangeCalendar,
+        feed::InstrumentQuoteResponse,
+        tick::{GetTicksEOD, TickLength, TickPeriod},
+    },
+    BoursoWebClient,
+};
+use chrono::{DateTime, Duration, Utc};
+use futures_util::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+use crate::settings::get_bourso_dir;
+
+const CACHE_DIR: &str = "cache";
+/// How long a quote fetched while the market is open stays fresh
+const QUOTE_TTL_MINUTES: i64 = 1;
+/// How long end of day ticks fetched while the market is open stay fresh
+const TICKS_TTL_MINUTES: i64 = 5;
+
+/// Public market data cached on disk, in `~/.bourso/cache`
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// A cached response
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry<T> {
+    fetched_at: DateTime<Utc>,
+    value: T,
+}
+
+/// Fetches the public market data through the cache, if any
+pub struct MarketData<'a> {
+    web_client: &'a BoursoWebClient,
+    cache: Option<Cache>,
+}
+
+impl Cache {
+    /// Open the cache located in `~/.bourso/cache`
+    #[cfg(not(tarpaulin_include))]
+    pub fn open_default() -> Result<Cache> {
+        Cache::open(get_bourso_dir()?.join(CACHE_DIR))
+    }
+
+    pub fn open(dir: impl AsRef<Path>) -> Result<Cache> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+        Ok(Cache { dir })
+    }
+
+    /// Remove every cached response
+    ///
+    /// # Returns
+    ///
+    /// The number of removed files
+    pub fn clear(&self) -> Result<usize> {
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Read an entry, a missing or unreadable entry being a cache miss
+    fn read<T: DeserializeOwned>(&self, key: &str) -> Option<CacheEntry<T>> {
+        let content = fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write<T: Serialize>(&self, key: &str, entry: &CacheEntry<T>) -> Result<()> {
+        let path = self.path(key);
+        fs::write(&path, serde_json::to_string(entry)?)
+            .with_context(|| format!("Failed to write cache file {}", path.display()))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let key = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+impl<'a> MarketData<'a> {
+    /// # Arguments
+    ///
+    /// * `web_client` - The client used on a cache miss
+    /// * `cache` - The cache, `None` to always fetch the data
+    pub fn new(web_client: &'a BoursoWebClient, cache: Option<Cache>) -> MarketData<'a> {
+        MarketData { web_client, cache }
+    }
+
+    /// Same as `BoursoWebClient::instrument_quote`, through the cache
+    #[cfg(not(tarpaulin_include))]
+    pub async fn instrument_quote(&self, symbol: &str) -> Result<InstrumentQuoteResponse> {
+        let cache = match self.cache.as_ref() {
+            Some(cache) => cache,
+            None => return self.web_client.instrument_quote(symbol).await,
+        };
+
+        let key = quote_key(symbol);
+        if let Some(entry) = cache.read::<InstrumentQuoteResponse>(&key) {
+            let calendar = ExchangeCalendar::from_quote(&entry.value);
+            if is_fresh(
+                &calendar,
+                entry.fetched_at,
+                Duration::minutes(QUOTE_TTL_MINUTES),
+                Utc::now(),
+            ) {
+                debug!("Quote of {} read from the cache", symbol);
+                return Ok(entry.value);
+            }
+        }
+
+        let quote = self.web_client.instrument_quote(symbol).await?;
+        cache.write(
+            &key,
+            &CacheEntry {
+                fetched_at: Utc::now(),
+                value: quote.clone(),
+            },
+        )?;
+        Ok(quote)
+    }
+
+    /// Same as `BoursoWebClient::instrument_quotes`, through the cache
+    #[cfg(not(tarpaulin_include))]
+    pub async fn instrument_quotes(
+        &self,
+        symbols: &[&str],
+    ) -> Vec<(String, Result<InstrumentQuoteResponse>)> {
+        if self.cache.is_none() {
+            return self.web_client.instrument_quotes(symbols).await;
+        }
+
+        futures_util::stream::iter(
+            symbols.iter().map(|symbol| async move {
+                (symbol.to_string(), self.instrument_quote(symbol).await)
+            }),
+        )
+        .buffered(self.web_client.concurrency())
+        .collect()
+        .await
+    }
+
+    /// Same as `BoursoWebClient::get_ticks`, through the cache
+    ///
+    /// End of day quotes do not change once the day is over: when the cached ticks are stale,
+    /// only the last days are fetched and merged into them.
+    #[cfg(not(tarpaulin_include))]
+    pub async fn get_ticks(
+        &self,
+        symbol: &str,
+        length: TickLength,
+        period: TickPeriod,
+    ) -> Result<GetTicksEOD> {
+        let cache = match self.cache.as_ref() {
+            Some(cache) => cache,
+            None => return self.web_client.get_ticks(symbol, length, period).await,
+        };
+
+        let key = format!("ticks-{}-{}-{}", symbol, length.days(), period.value());
+        let cached = cache.read::<GetTicksEOD>(&key);
+        let ttl = match period {
+            TickPeriod::Minutes(minutes) => Duration::minutes(minutes as i64),
+            TickPeriod::Hourly => Duration::hours(1),
+            _ => Duration::minutes(TICKS_TTL_MINUTES),
+        };
+
+        let ticks = match cached {
+            Some(entry) => {
+                let calendar = self.calendar(symbol).await?;
+                if is_fresh(&calendar, entry.fetched_at, ttl, Utc::now()) {
+                    debug!("Ticks of {} read from the cache", symbol);
+                    return Ok(entry.value);
+                }
+                match recent_length(length, period) {
+                    Some(recent) => {
+                        let mut ticks = entry.value;
+                        let recent = self.web_client.get_ticks(symbol, recent, period).await?;
+                        merge_ticks(&mut ticks, recent, length);
+                        ticks
+                    }
+                    None => self.web_client.get_ticks(symbol, length, period).await?,
+                }
+            }
+            None => self.web_client.get_ticks(symbol, length, period).await?,
+        };
+
+        cache.write(
+            &key,
+            &CacheEntry {
+                fetched_at: Utc::now(),
+                value: ticks.clone(),
+            },
+        )?;
+        Ok(ticks)
+    }
+
+    /// Calendar of the exchange of the symbol, from its cached quote even if it is stale
+    #[cfg(not(tarpaulin_include))]
+    async fn calendar(&self, symbol: &str) -> Result<ExchangeCalendar> {
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.read::<InstrumentQuoteResponse>(&quote_key(symbol)));
+        let quote = match cached {
+            Some(entry) => entry.value,
+            None => self.instrument_quote(symbol).await?,
+        };
+        Ok(ExchangeCalendar::from_quote(&quote))
+    }
+}
+
+fn quote_key(symbol: &str) -> String {
+    format!("quote-{}", symbol)
+}
+
+/// Whether data fetched at the given instant is still fresh: data fetched while the market is
+/// open expires after the TTL, data fetched while it is closed expires at the next opening
+pub fn is_fresh(
+    calendar: &ExchangeCalendar,
+    fetched_at: DateTime<Utc>,
+    ttl: Duration,
+    now: DateTime<Utc>,
+) -> bool {
+    if calendar.is_open(&fetched_at) {
+        return now < fetched_at + ttl;
+    }
+    match calendar.next_open(&fetched_at) {
+        Some(open) => now < open.with_timezone(&Utc),
+        None => now < fetched_at + ttl,
+    }
+}
+
+/// Length of the recent ticks fetched to refresh cached ticks, `None` when the whole length
+/// has to be fetched again
+fn recent_length(length: TickLength, period: TickPeriod) -> Option<TickLength> {
+    let recent = match period {
+        TickPeriod::Minutes(_) | TickPeriod::Hourly => return None,
+        TickPeriod::Daily => TickLength::Week,
+        TickPeriod::Weekly => TickLength::Month,
+        TickPeriod::Monthly => TickLength::Quarter,
+    };
+    (recent.days() < length.days()).then_some(recent)
+}
+
+/// Replace the cached quotes from the first date of the recent quotes, then drop the quotes
+/// that fell out of the time frame
+pub fn merge_ticks(cached: &mut GetTicksEOD, recent: GetTicksEOD, length: TickLength) {
+    if let Some(first) = recent.d.quote_tab.first().map(|q| q.date) {
+        cached.d.quote_tab.retain(|q| q.date < first);
+        cached.d.quote_tab.extend(recent.d.quote_tab);
+    }
+    if let Some(last) = cached.d.quote_tab.last().map(|q| q.date) {
+        cached.d.quote_tab.retain(|q| q.date > last - length.days());
+    }
+    cached.d.last_quote = recent.d.last_quote;
+    cached.d.second_to_last_quote = recent.d.second_to_last_quote;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bourso_api::client::trade::tick::{QuoteTab, D};
+    use chrono::TimeZone;
+
+    fn ticks(dates: &[i64], close: f64) -> GetTicksEOD {
+        GetTicksEOD {
+            d: D {
+                quote_tab: dates
+                    .iter()
+                    .map(|date| QuoteTab {
+                        date: *date,
+                        close,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_merge_ticks() {
+        let mut cached = ticks(&(19_990..=20_020).collect::<Vec<_>>(), 1.0);
+        merge_ticks(
+            &mut cached,
+            ticks(&[20_018, 20_019, 20_020, 20_023], 2.0),
+            TickLength::Month,
+        );
+
+        let dates = cached
+            .d
+            .quote_tab
+            .iter()
+            .map(|q| q.date)
+            .collect::<Vec<_>>();
+        assert_eq!(dates.first(), Some(&19_994));
+        assert_eq!(dates.last(), Some(&20_023));
+        assert_eq!(
+            cached.d.quote_tab.iter().filter(|q| q.close == 2.0).count(),
+            4
+        );
+        // No duplicated date
+        assert_eq!(dates.windows(2).filter(|w| w[0] >= w[1]).count(), 0);
+
+        assert_eq!(
+            recent_length(TickLength::Year, TickPeriod::Daily),
+            Some(TickLength::Week)
+        );
+        assert_eq!(recent_length(TickLength::Week, TickPeriod::Daily), None);
+        assert_eq!(recent_length(TickLength::Day, TickPeriod::Minutes(5)), None);
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let calendar = ExchangeCalendar::from_exchange_code("XPAR").unwrap();
+        let ttl = Duration::minutes(5);
+        // Friday 2024-03-08 at 10:00 in Paris, the market is open
+        let open = Utc.with_ymd_and_hms(2024, 3, 8, 9, 0, 0).unwrap();
+        assert!(is_fresh(&calendar, open, ttl, open + Duration::minutes(4)));
+        assert!(!is_fresh(&calendar, open, ttl, open + Duration::minutes(6)));
+
+        // Friday at 18:00 in Paris, fresh until Monday 09:00
+        let closed = Utc.with_ymd_and_hms(2024, 3, 8, 17, 0, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2024, 3, 11, 8, 0, 0).unwrap();
+        assert!(is_fresh(
+            &calendar,
+            closed,
+            ttl,
+            monday - Duration::minutes(1)
+        ));
+        assert!(!is_fresh(&calendar, closed, ttl, monday));
+    }
+
+    #[test]
+    fn test_cache_read_write_and_clear() {
+        let dir = std::env::temp_dir().join(format!("bourso-cache-{}", std::process::id()));
+        let cache = Cache::open(&dir).unwrap();
+        cache.clear().unwrap();
+
+        let entry = CacheEntry {
+            fetched_at: Utc::now(),
+            value: ticks(&[20_000], 1.0),
+        };
+        cache.write("ticks-1rTCW8-30-0", &entry).unwrap();
+        assert_eq!(cache.read::<GetTicksEOD>("ticks-1rTCW8-30-0"), Some(entry));
+        assert_eq!(cache.read::<GetTicksEOD>("ticks-1rPAI-30-0"), None);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert_eq!(cache.read::<GetTicksEOD>("ticks-1rTCW8-30-0"), None);
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use bourso_api::{
+    account::Account,
+    client::{
+        trade::{
+            error::OrderError,
+            order::{AmountOrder, OrderSide},
+        },
+        BoursoWebClient,
+    },
+};
+use std::{collections::HashMap, fs};
+use tracing::{info, warn};
+
+use crate::settings::get_bourso_dir;
+
+const CARRY_OVER_FILE: &str = "carry_over.json";
+
+/// Amounts left over by previous `--amount` orders, keyed by account and symbol
+///
+/// Stored in `~/.bourso/carry_over.json` so that the next order for the same account and symbol
+/// can use them, e.g. investing 300 EUR every month in a 450 EUR share buys one share every
+/// other month.
+#[derive(Default, Debug)]
+pub struct CarryOver {
+    amounts: HashMap<String, f64>,
+}
+
+impl CarryOver {
+    #[cfg(not(tarpaulin_include))]
+    pub fn load() -> Result<CarryOver> {
+        let path = get_bourso_dir()?.join(CARRY_OVER_FILE);
+        let amounts = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).with_context(|| {
+                format!("Failed to deserialize carry over file {}", path.display())
+            })?,
+            Err(_) => HashMap::new(),
+        };
+        Ok(CarryOver { amounts })
+    }
+
+    #[cfg(not(tarpaulin_include))]
+    pub fn save(&self) -> Result<()> {
+        let path = get_bourso_dir()?.join(CARRY_OVER_FILE);
+        let json = serde_json::to_string_pretty(&self.amounts)
+            .context("Failed to serialize carry over")?;
+        fs::write(&path, json).context("Failed to write carry over file")?;
+        Ok(())
+    }
+
+    /// Amount left over for the given account and symbol
+    pub fn get(&self, account_id: &str, symbol: &str) -> f64 {
+        self.amounts
+            .get(&key(account_id, symbol))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn set(&mut self, account_id: &str, symbol: &str, amount: f64) {
+        // Round to the cent to avoid accumulating floating point errors between runs
+        let amount = (amount * 100.0).round() / 100.0;
+        if amount > 0.0 {
+            self.amounts.insert(key(account_id, symbol), amount);
+        } else {
+            self.amounts.remove(&key(account_id, symbol));
+        }
+    }
+}
+
+/// Place an order for an amount, optionally adding the amount left over by the previous orders
+/// for the same account and symbol and saving what is left of this one.
+///
+/// # Returns
+/// The order, or None if the amount was too low to buy a single share and was saved for the next order
+#[cfg(not(tarpaulin_include))]
+pub async fn order_by_amount(
+    web_client: &BoursoWebClient,
+    side: OrderSide,
+    account: &Account,
+    symbol: &str,
+    amount: f64,
+    price_limit: Option<f64>,
+    carry: bool,
+) -> Result<Option<AmountOrder>> {
+    let mut carry_over = if carry {
+        CarryOver::load()?
+    } else {
+        CarryOver::default()
+    };
+    let left_over = carry_over.get(&account.id, symbol);
+    let budget = amount + left_over;
+    if left_over > 0.0 {
+        info!(
+            "Adding {:.2} left over by the previous orders, investing {:.2}",
+            left_over, budget
+        );
+    }
+
+    match web_client
+        .order_by_amount(side, account, symbol, budget, price_limit)
+        .await
+    {
+        Ok(order) => {
+            if carry {
+                carry_over.set(&account.id, symbol, budget - order.cost);
+                // The order is placed, failing to save what is left over must not fail it
+                match carry_over.save() {
+                    Ok(()) => info!(
+                        "{:.2} left over saved for the next order",
+                        carry_over.get(&account.id, symbol)
+                    ),
+                    Err(e) => warn!("Failed to save the left over of the order: {}", e),
+                }
+            }
+            Ok(Some(order))
+        }
+        Err(e) => match e.downcast_ref() {
+            Some(OrderError::AmountTooLow { .. }) if carry => {
+                warn!("{}", e);
+                carry_over.set(&account.id, symbol, budget);
+                match carry_over.save() {
+                    Ok(()) => info!("{:.2} saved for the next order", budget),
+                    Err(e) => warn!("Failed to save {:.2} for the next order: {}", budget, e),
+                }
+                Ok(None)
+            }
+            _ => Err(e),
+        },
+    }
+}
+
+fn key(account_id: &str, symbol: &str) -> String {
+    format!("{}:{}", account_id, symbol)
+}
@@ -0,0 +1,444 @@
+use bourso_api::client::trade::{indicators, tick::Candle};
+
+/// Width of the price axis on the left of the chart, e.g: " 1234.56 ┤"
+const AXIS_WIDTH: usize = 11;
+/// Width kept on the right of the chart for the highest and lowest markers, e.g: " ◀ 1234.56"
+const MARKER_WIDTH: usize = 11;
+/// Width used when the terminal width is unknown
+const DEFAULT_WIDTH: usize = 80;
+
+const BULLISH_BODY: char = '█';
+const BEARISH_BODY: char = '░';
+const WICK: char = '│';
+const SMA_DOT: char = '·';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartKind {
+    /// Closing prices drawn with braille characters
+    Line,
+    /// One candle per column, bullish bodies are filled, bearish bodies are shaded
+    Candle,
+}
+
+impl std::str::FromStr for ChartKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "line" => Ok(ChartKind::Line),
+            "candle" => Ok(ChartKind::Candle),
+            _ => Err(anyhow::anyhow!("Invalid chart kind: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartOptions {
+    pub kind: ChartKind,
+    /// Total width of the chart in characters, axis and markers included
+    pub width: usize,
+    /// Height of the plot in lines
+    pub height: usize,
+    /// Window of the simple moving average drawn over the prices
+    pub sma: Option<usize>,
+}
+
+/// Width of the terminal, from the `COLUMNS` environment variable or the terminal itself
+#[cfg(not(tarpaulin_include))]
+pub fn terminal_width() -> usize {
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse::<usize>().ok())
+    {
+        return columns;
+    }
+
+    #[cfg(unix)]
+    {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        // SAFETY: TIOCGWINSZ only writes into the given winsize struct
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+            && size.ws_col > 0
+        {
+            return size.ws_col as usize;
+        }
+    }
+
+    DEFAULT_WIDTH
+}
+
+/// Render the candles as a chart, one string per line
+///
+/// # Arguments
+///
+/// * `candles` - The candles to draw, oldest first
+/// * `options` - The kind, size and overlay of the chart
+/// * `intraday` - Whether the candles are dated with a time, or only with a day
+///
+/// # Returns
+///
+/// The lines of the chart: the plot with its price axis and the highest and lowest markers,
+/// then the time axis and a legend
+pub fn render(candles: &[Candle], options: &ChartOptions, intraday: bool) -> Vec<String> {
+    if candles.is_empty() {
+        return vec!["No quotes to draw".to_string()];
+    }
+
+    let columns = options
+        .width
+        .saturating_sub(AXIS_WIDTH + MARKER_WIDTH)
+        .max(10);
+    let height = options.height.max(3);
+
+    // Candles are merged when there are more candles than columns
+    let candles = match options.kind {
+        ChartKind::Line => candles.to_vec(),
+        ChartKind::Candle => resample(candles, columns),
+    };
+    let closes = candles.iter().map(|c| c.close).collect::<Vec<_>>();
+    let sma = options.sma.map(|window| indicators::sma(&closes, window));
+
+    let (highest, lowest) = match options.kind {
+        ChartKind::Line => (
+            extreme(&candles, |c| c.close, |a, b| a > b),
+            extreme(&candles, |c| c.close, |a, b| a < b),
+        ),
+        ChartKind::Candle => (
+            extreme(&candles, |c| c.high, |a, b| a > b),
+            extreme(&candles, |c| c.low, |a, b| a < b),
+        ),
+    };
+    let scale = Scale {
+        min: lowest.1,
+        max: highest.1,
+    };
+
+    let mut grid = match options.kind {
+        ChartKind::Line => draw_line(&closes, &scale, columns, height),
+        ChartKind::Candle => draw_candles(&candles, &scale, height),
+    };
+
+    // The moving average is drawn in the empty cells only, so it never hides the prices
+    if let Some(sma) = sma.as_ref() {
+        let width = grid[0].len();
+        let dots = (0..width).filter_map(|column| {
+            let index = match options.kind {
+                ChartKind::Line => column_index(column, width, sma.len()),
+                ChartKind::Candle => column,
+            };
+            let value = (*sma.get(index)?)?;
+            Some((scale.row(value, height), column))
+        });
+        for (row, column) in dots.collect::<Vec<_>>() {
+            if grid[row][column] == ' ' {
+                grid[row][column] = SMA_DOT;
+            }
+        }
+    }
+
+    let highest_row = scale.row(highest.1, height);
+    let lowest_row = scale.row(lowest.1, height);
+    let mut lines = Vec::new();
+    for (row, cells) in grid.iter().enumerate() {
+        let label = if row == 0 {
+            format!("{:>9.2} ┤", scale.max)
+        } else if row == height - 1 {
+            format!("{:>9.2} ┤", scale.min)
+        } else {
+            format!("{:>9} │", "")
+        };
+        let mut line = format!("{}{}", label, cells.iter().collect::<String>());
+        if row == highest_row {
+            line.push_str(&format!(" ◀ {:.2}", highest.1));
+        } else if row == lowest_row {
+            line.push_str(&format!(" ◀ {:.2}", lowest.1));
+        }
+        lines.push(line.trim_end().to_string());
+    }
+
+    let width = grid[0].len();
+    lines.push(format!("{:>9} └{}", "", "─".repeat(width)));
+    let first = format_time(&candles[0], intraday);
+    let last = format_time(&candles[candles.len() - 1], intraday);
+    lines.push(format!(
+        "{:>11}{}{:>padding$}",
+        "",
+        first,
+        last,
+        padding = width
+            .saturating_sub(first.chars().count())
+            .max(last.len() + 1)
+    ));
+
+    let mut legend = vec![
+        format!(
+            "Highest: {:.2} on {}",
+            highest.1,
+            format_time(&candles[highest.0], intraday)
+        ),
+        format!(
+            "Lowest: {:.2} on {}",
+            lowest.1,
+            format_time(&candles[lowest.0], intraday)
+        ),
+    ];
+    if let Some(window) = options.sma {
+        legend.push(format!("{} SMA {}", SMA_DOT, window));
+    }
+    if options.kind == ChartKind::Candle {
+        legend.push(format!("{} up, {} down", BULLISH_BODY, BEARISH_BODY));
+    }
+    lines.push(legend.join(", "));
+
+    lines
+}
+
+/// Merge consecutive candles so that there is at most `columns` candles
+pub fn resample(candles: &[Candle], columns: usize) -> Vec<Candle> {
+    if candles.len() <= columns || columns == 0 {
+        return candles.to_vec();
+    }
+    let size = candles.len().div_ceil(columns);
+    candles
+        .chunks(size)
+        .map(|chunk| Candle {
+            time: chunk[0].time,
+            open: chunk[0].open,
+            high: chunk.iter().map(|c| c.high).fold(f64::MIN, f64::max),
+            low: chunk.iter().map(|c| c.low).fold(f64::MAX, f64::min),
+            close: chunk[chunk.len() - 1].close,
+            volume: chunk.iter().map(|c| c.volume).sum(),
+        })
+        .collect()
+}
+
+/// Vertical scale of the chart
+struct Scale {
+    min: f64,
+    max: f64,
+}
+
+impl Scale {
+    /// Position of the value between 0 (top) and `steps - 1` (bottom)
+    fn row(&self, value: f64, steps: usize) -> usize {
+        if self.max <= self.min {
+            return steps / 2;
+        }
+        let ratio = ((self.max - value) / (self.max - self.min)).clamp(0.0, 1.0);
+        (ratio * (steps - 1) as f64).round() as usize
+    }
+}
+
+/// Index and value of the candle with the extreme value, the first one on equality
+fn extreme(
+    candles: &[Candle],
+    value: impl Fn(&Candle) -> f64,
+    better: impl Fn(f64, f64) -> bool,
+) -> (usize, f64) {
+    let mut result = (0, value(&candles[0]));
+    for (index, candle) in candles.iter().enumerate().skip(1) {
+        if better(value(candle), result.1) {
+            result = (index, value(candle));
+        }
+    }
+    result
+}
+
+/// Index of the value drawn at the given column when `len` values are spread over `width` columns
+fn column_index(column: usize, width: usize, len: usize) -> usize {
+    if width <= 1 {
+        return 0;
+    }
+    (column * (len - 1) + (width - 1) / 2) / (width - 1)
+}
+
+/// Draw the values with braille characters, each character holding 2x4 dots
+fn draw_line(values: &[f64], scale: &Scale, columns: usize, height: usize) -> Vec<Vec<char>> {
+    let dots_x = columns * 2;
+    let dots_y = height * 4;
+    let mut dots = vec![vec![0u8; dots_x.div_ceil(2)]; height];
+
+    let mut set = |x: usize, y: usize| {
+        // Braille dots are numbered column by column, the 4th row has its own bits
+        let bit = match (x % 2, y % 4) {
+            (0, 3) => 0x40,
+            (1, 3) => 0x80,
+            (0, row) => 1 << row,
+            (_, row) => 1 << (row + 3),
+        };
+        dots[y / 4][x / 2] |= bit;
+    };
+
+    let mut previous: Option<usize> = None;
+    for x in 0..dots_x {
+        // Values are interpolated when there are less values than dots
+        let position = x as f64 * (values.len() - 1) as f64 / (dots_x - 1) as f64;
+        let index = position.floor() as usize;
+        let next = (index + 1).min(values.len() - 1);
+        let value = values[index] + (values[next] - values[index]) * (position - index as f64);
+        let y = scale.row(value, dots_y);
+        // Link the dots vertically so that steep moves stay continuous
+        let (from, to) = match previous {
+            Some(p) if p < y => (p + 1, y),
+            Some(p) if p > y => (y, p - 1),
+            _ => (y, y),
+        };
+        for y in from..=to {
+            set(x, y);
+        }
+        previous = Some(y);
+    }
+
+    dots.iter()
+        .map(|row| {
+            row.iter()
+                .map(|bits| match bits {
+                    0 => ' ',
+                    bits => char::from_u32(0x2800 + *bits as u32).unwrap(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Draw one candle per column: the wick from the low to the high, the body from the open to the close
+fn draw_candles(candles: &[Candle], scale: &Scale, height: usize) -> Vec<Vec<char>> {
+    let mut grid = vec![vec![' '; candles.len()]; height];
+
+    for (column, candle) in candles.iter().enumerate() {
+        let (high, low) = (
+            scale.row(candle.high, height),
+            scale.row(candle.low, height),
+        );
+        for cells in grid[high..=low].iter_mut() {
+            cells[column] = WICK;
+        }
+        let body = if candle.close >= candle.open {
+            BULLISH_BODY
+        } else {
+            BEARISH_BODY
+        };
+        let open = scale.row(candle.open, height);
+        let close = scale.row(candle.close, height);
+        for cells in grid[open.min(close)..=open.max(close)].iter_mut() {
+            cells[column] = body;
+        }
+    }
+
+    grid
+}
+
+fn format_time(candle: &Candle, intraday: bool) -> String {
+    if intraday {
+        candle
+            .time
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    } else {
+        candle.time.format("%Y-%m-%d").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn candle(day: u32, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            time: Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap(),
+            open,
+            high,
+            low,
+            close,
+            volume: 10,
+        }
+    }
+
+    #[test]
+    fn test_candle_chart() {
+        let candles = [
+            candle(4, 10.0, 14.0, 10.0, 12.0),
+            candle(5, 12.0, 12.0, 11.0, 11.0),
+            candle(6, 13.5, 14.0, 13.5, 14.0),
+        ];
+        let options = ChartOptions {
+            kind: ChartKind::Candle,
+            width: 40,
+            height: 5,
+            sma: Some(2),
+        };
+
+        let lines = render(&candles, &options, false);
+        assert_eq!(
+            lines[..5],
+            [
+                "    14.00 ┤│ █ ◀ 14.00",
+                "          ││ █",
+                "          │█░·",
+                "          │█░",
+                "    10.00 ┤█   ◀ 10.00",
+            ]
+        );
+        assert_eq!(lines[5], format!("          └{}", "─".repeat(3)));
+        assert_eq!(lines[6], "           2024-03-04 2024-03-06");
+        assert_eq!(
+            lines[7],
+            "Highest: 14.00 on 2024-03-04, Lowest: 10.00 on 2024-03-04, · SMA 2, █ up, ░ down"
+        );
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let candles = (1..=4)
+            .map(|day| candle(day, 0.0, 0.0, 0.0, day as f64))
+            .collect::<Vec<_>>();
+        let options = ChartOptions {
+            kind: ChartKind::Line,
+            width: 32,
+            height: 1,
+            sma: None,
+        };
+
+        // The 4 values are interpolated over the 20 columns of dots
+        let lines = render(&candles, &options, false);
+        assert_eq!(
+            lines[..3],
+            [
+                "     4.00 ┤      ⢀⡠⠔⠊ ◀ 4.00",
+                "          │   ⢀⠤⠒⠁",
+                "     1.00 ┤⡠⠔⠊⠁       ◀ 1.00",
+            ]
+        );
+        assert_eq!(
+            lines[5],
+            "Highest: 4.00 on 2024-03-04, Lowest: 1.00 on 2024-03-01"
+        );
+    }
+
+    #[test]
+    fn test_resample() {
+        let candles = (1..=5)
+            .map(|day| {
+                candle(
+                    day,
+                    day as f64,
+                    day as f64 + 1.0,
+                    day as f64 - 1.0,
+                    day as f64,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let resampled = resample(&candles, 2);
+        assert_eq!(resampled.len(), 2);
+        assert_eq!(resampled[0].open, 1.0);
+        assert_eq!(resampled[0].close, 3.0);
+        assert_eq!(resampled[0].high, 4.0);
+        assert_eq!(resampled[0].low, 0.0);
+        assert_eq!(resampled[0].volume, 30);
+        assert_eq!(resampled[1].time, candles[3].time);
+        assert_eq!(resample(&candles, 10), candles.to_vec());
+    }
+}
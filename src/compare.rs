@@ -0,0 +1,95 @@
+use anyhow::Result;
+use bourso_api::client::trade::feed::InstrumentQuoteResponse;
+
+const HEADERS: [&str; 6] = ["symbol", "label", "last", "variation", "volume", "exchange"];
+
+/// Render the quotes of several symbols side by side, a failed quote being shown with its error
+pub fn render_table(quotes: &[(String, Result<InstrumentQuoteResponse>)]) -> String {
+    let mut rows: Vec<Vec<String>> = vec![HEADERS.iter().map(|h| h.to_string()).collect()];
+    let mut errors = Vec::new();
+
+    for (symbol, quote) in quotes {
+        match quote {
+            Ok(quote) => rows.push(vec![
+                symbol.clone(),
+                quote.label.clone(),
+                format!("{} {}", quote.last, quote.currency),
+                format_variation(quote),
+                quote.total_volume.to_string(),
+                quote.exchange_label.clone(),
+            ]),
+            Err(e) => errors.push(format!("{}: {}", symbol, e)),
+        }
+    }
+
+    let widths = (0..HEADERS.len())
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    let mut lines = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| match i {
+                    2..=4 => format!("{:>width$}", cell, width = widths[i]),
+                    _ => format!("{:<width$}", cell, width = widths[i]),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    if !errors.is_empty() {
+        lines.push(String::new());
+        lines.push("Failed quotes:".to_string());
+        lines.extend(errors);
+    }
+
+    lines.join("\n")
+}
+
+/// Variation since the previous close, e.g. "+1.25%"
+fn format_variation(quote: &InstrumentQuoteResponse) -> String {
+    if quote.previous_close == 0.0 {
+        return "n/a".to_string();
+    }
+    format!("{:+.2}%", (quote.last / quote.previous_close - 1.0) * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table() {
+        let quote = |last: f64, previous_close: f64, label: &str| InstrumentQuoteResponse {
+            label: label.to_string(),
+            last,
+            previous_close,
+            currency: "EUR".to_string(),
+            total_volume: 1200,
+            exchange_label: "Euronext Paris".to_string(),
+            ..Default::default()
+        };
+        let quotes = vec![
+            (
+                "1rTCW8".to_string(),
+                Ok(quote(505.0, 500.0, "AMUNDI MSCI WORLD")),
+            ),
+            ("1rPAI".to_string(), Ok(quote(180.5, 0.0, "AIR LIQUIDE"))),
+            ("1rPXX".to_string(), Err(anyhow::anyhow!("Not found"))),
+        ];
+
+        assert_eq!(
+            render_table(&quotes),
+            "symbol  label                   last  variation  volume  exchange\n\
+             1rTCW8  AMUNDI MSCI WORLD    505 EUR     +1.00%    1200  Euronext Paris\n\
+             1rPAI   AIR LIQUIDE        180.5 EUR        n/a    1200  Euronext Paris\n\
+             \n\
+             Failed quotes:\n\
+             1rPXX: Not found"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use bourso_api::client::trade::{
+    indicators,
+    tick::{QuoteTab, TickPeriod},
+};
+
+const NOT_ENOUGH_QUOTES: &str = "n/a (not enough quotes, increase --length)";
+
+/// Indicators requested with the `quote indicators` command
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct IndicatorOptions {
+    pub sma: Vec<usize>,
+    pub ema: Vec<usize>,
+    pub rsi: Option<usize>,
+    pub macd: bool,
+    pub bollinger: Option<usize>,
+    pub atr: Option<usize>,
+    pub drawdown: bool,
+    pub volatility: bool,
+    pub returns: Vec<usize>,
+}
+
+impl IndicatorOptions {
+    /// Indicators shown when none is requested
+    pub fn defaults() -> IndicatorOptions {
+        IndicatorOptions {
+            sma: vec![20],
+            rsi: Some(14),
+            macd: true,
+            bollinger: Some(20),
+            atr: Some(14),
+            drawdown: true,
+            volatility: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == IndicatorOptions::default()
+    }
+}
+
+/// Render the last value of each requested indicator, one per line
+pub fn render(quotes: &[QuoteTab], period: TickPeriod, options: &IndicatorOptions) -> Vec<String> {
+    let closes = indicators::closes(quotes);
+    let mut lines = Vec::new();
+
+    for window in options.sma.iter() {
+        lines.push(format!(
+            "SMA {}: {}",
+            window,
+            format_value(indicators::last(&indicators::sma(&closes, *window)))
+        ));
+    }
+    for window in options.ema.iter() {
+        lines.push(format!(
+            "EMA {}: {}",
+            window,
+            format_value(indicators::last(&indicators::ema(&closes, *window)))
+        ));
+    }
+    if let Some(window) = options.rsi {
+        lines.push(format!(
+            "RSI {}: {}",
+            window,
+            format_value(indicators::last(&indicators::rsi(&closes, window)))
+        ));
+    }
+    if options.macd {
+        let macd = indicators::last(&indicators::macd(&closes, 12, 26, 9));
+        lines.push(match macd {
+            Some(macd) => format!(
+                "MACD 12, 26, 9: {:.3}, signal {:.3}, histogram {:.3}",
+                macd.macd, macd.signal, macd.histogram
+            ),
+            None => format!("MACD 12, 26, 9: {}", NOT_ENOUGH_QUOTES),
+        });
+    }
+    if let Some(window) = options.bollinger {
+        let bands = indicators::last(&indicators::bollinger(&closes, window, 2.0));
+        lines.push(match bands {
+            Some(bands) => format!(
+                "Bollinger {}: lower {:.3}, middle {:.3}, upper {:.3}",
+                window, bands.lower, bands.middle, bands.upper
+            ),
+            None => format!("Bollinger {}: {}", window, NOT_ENOUGH_QUOTES),
+        });
+    }
+    if let Some(window) = options.atr {
+        lines.push(format!(
+            "ATR {}: {}",
+            window,
+            format_value(indicators::last(&indicators::atr(quotes, window)))
+        ));
+    }
+    if options.drawdown {
+        lines.push(format!(
+            "Max drawdown: {}",
+            format_percent(Some(-indicators::max_drawdown(&closes)))
+        ));
+    }
+    if options.volatility {
+        lines.push(format!(
+            "Annualized volatility: {}",
+            format_percent(indicators::volatility(
+                &closes,
+                indicators::periods_per_year(period)
+            ))
+        ));
+    }
+    for window in options.returns.iter() {
+        lines.push(format!(
+            "Returns over {} quotes: {}",
+            window,
+            format_percent(indicators::returns(&closes, *window))
+        ));
+    }
+
+    lines
+}
+
+fn format_value(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.3}", value),
+        None => NOT_ENOUGH_QUOTES.to_string(),
+    }
+}
+
+fn format_percent(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.2}%", value * 100.0),
+        None => NOT_ENOUGH_QUOTES.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let quotes = [100.0, 110.0, 99.0, 121.0]
+            .iter()
+            .map(|close| QuoteTab {
+                close: *close,
+                high: *close,
+                low: *close,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let options = IndicatorOptions {
+            sma: vec![2, 50],
+            drawdown: true,
+            returns: vec![3],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&quotes, TickPeriod::Daily, &options),
+            vec![
+                "SMA 2: 110.000".to_string(),
+                format!("SMA 50: {}", NOT_ENOUGH_QUOTES),
+                "Max drawdown: -10.00%".to_string(),
+                "Returns over 3 quotes: 21.00%".to_string(),
+            ]
+        );
+        assert!(!options.is_empty());
+        assert!(IndicatorOptions::default().is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::settings::get_bourso_dir;
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalStatus {
+    /// Recorded before executing the operation. Still the last status of an operation if its
+    /// outcome is unknown, e.g. after a network error while confirming it
+    Started,
+    Completed,
+    Failed,
+}
+
+/// A line of the journal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Local>,
+    /// Identifies the operation, e.g. `plan:msci-world:2026-10` for a planned buy. Also used as
+    /// idempotency key: a completed operation is not executed again with the same key
+    pub key: String,
+    pub status: JournalStatus,
+    /// Operation specific data (order id, quantity, error message...)
+    pub details: serde_json::Value,
+}
+
+impl JournalEntry {
+    pub fn new(key: &str, status: JournalStatus, details: serde_json::Value) -> JournalEntry {
+        JournalEntry {
+            timestamp: Local::now(),
+            key: key.to_string(),
+            status,
+            details,
+        }
+    }
+}
+
+/// Append-only journal of the operations executed by the CLI, stored as JSON lines. Every
+/// transfer and order attempt is recorded, with its outcome
+pub struct Journal {
+    path: PathBuf,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Open the journal located in `~/.bourso/journal.jsonl`
+    #[cfg(not(tarpaulin_include))]
+    pub fn open_default() -> Result<Journal> {
+        Journal::open(get_bourso_dir()?.join(JOURNAL_FILE))
+    }
+
+    /// Open the journal at the given path, it will be created on the first append
+    pub fn open(path: impl AsRef<Path>) -> Result<Journal> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = Vec::new();
+
+        if path.exists() {
+            let file = fs::File::open(&path)
+                .with_context(|| format!("Failed to open journal {}", path.display()))?;
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                entries.push(serde_json::from_str(&line).with_context(|| {
+                    format!(
+                        "Failed to parse line {} of journal {}",
+                        i + 1,
+                        path.display()
+                    )
+                })?);
+            }
+        }
+
+        Ok(Journal { path, entries })
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Whether an operation with the given key has already been completed
+    pub fn is_completed(&self, key: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.key == key && e.status == JournalStatus::Completed)
+    }
+
+    /// Last entry of the operation with the given key
+    pub fn last(&self, key: &str) -> Option<&JournalEntry> {
+        self.entries.iter().rev().find(|e| e.key == key)
+    }
+
+    /// Check that the operation with the given key can be executed: it must not have been
+    /// completed, nor have an unknown outcome, unless forced
+    pub fn guard(&self, key: &str, force: bool) -> Result<()> {
+        if force {
+            return Ok(());
+        }
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .rev()
+            .find(|e| e.key == key && e.status == JournalStatus::Completed)
+        {
+            bail!(
+                "Operation {} was already completed on {}. Use --force to execute it again",
+                key,
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        if let Some(entry) = self
+            .last(key)
+            .filter(|e| e.status == JournalStatus::Started)
+        {
+            bail!(
+                "Operation {} was started on {} but its outcome is unknown. Check your account, then use --force to execute it again",
+                key,
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        Ok(())
+    }
+
+    /// Append an entry to the journal file
+    pub fn append(&mut self, entry: JournalEntry) -> Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open journal {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+            .context("Failed to write journal entry")?;
+        self.entries.push(entry);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_append_and_reload() {
+        let path =
+            std::env::temp_dir().join(format!("bourso-journal-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut journal = Journal::open(&path).unwrap();
+        assert!(journal.entries().is_empty());
+        journal
+            .append(JournalEntry::new(
+                "plan:world:2026-10",
+                JournalStatus::Failed,
+                serde_json::json!({ "error": "Market closed" }),
+            ))
+            .unwrap();
+        assert!(!journal.is_completed("plan:world:2026-10"));
+        journal
+            .append(JournalEntry::new(
+                "plan:world:2026-10",
+                JournalStatus::Completed,
+                serde_json::json!({ "orderId": "123" }),
+            ))
+            .unwrap();
+
+        let journal = Journal::open(&path).unwrap();
+        assert_eq!(journal.entries().len(), 2);
+        assert!(journal.is_completed("plan:world:2026-10"));
+        assert!(!journal.is_completed("plan:world:2026-11"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_journal_guard() {
+        let path = std::env::temp_dir().join(format!("bourso-guard-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let key = "transfer:a583f3c5842c34fb00b408486ef493e0:b419f2d28010bafb331badef11bba51e:100.00:2026-10-18";
+
+        let mut journal = Journal::open(&path).unwrap();
+        assert!(journal.guard(key, false).is_ok());
+
+        // Started without an outcome, e.g. the CLI was killed while confirming the transfer
+        journal
+            .append(JournalEntry::new(
+                key,
+                JournalStatus::Started,
+                serde_json::Value::Null,
+            ))
+            .unwrap();
+        assert!(journal.guard(key, false).is_err());
+        assert!(journal.guard(key, true).is_ok());
+
+        journal
+            .append(JournalEntry::new(
+                key,
+                JournalStatus::Failed,
+                serde_json::json!({ "error": "Setting transfer amount failed" }),
+            ))
+            .unwrap();
+        assert!(journal.guard(key, false).is_ok());
+
+        journal
+            .append(JournalEntry::new(
+                key,
+                JournalStatus::Completed,
+                serde_json::Value::Null,
+            ))
+            .unwrap();
+        let error = journal.guard(key, false).unwrap_err().to_string();
+        assert!(error.contains("already completed"));
+        assert!(journal.guard(key, true).is_ok());
+        assert_eq!(journal.last(key).unwrap().status, JournalStatus::Completed);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
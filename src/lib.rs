@@ -2,21 +2,60 @@ use anyhow::{Context, Result};
 use bourso_api::{
     account::{Account, AccountKind, Transaction},
     client::{
-        trade::{order::OrderSide, tick::QuoteTab},
-        transfer::TransferProgress,
+        trade::{
+            error::OrderError,
+            order::OrderSide,
+            portfolio::PortfolioAccount,
+            tick::{QuoteTab, TickPeriod},
+        },
+        transfer::{
+            batch::{parse_batch, validate_batch},
+            beneficiary::{normalize_iban, CreditAccount},
+            schedule::TransferSchedule,
+            TransferOptions, TransferProgress,
+        },
         BoursoWebClient,
     },
     get_client,
 };
+use chrono::{Datelike, NaiveDate};
 use clap::ArgMatches;
 use futures_util::{pin_mut, StreamExt};
+use std::{collections::HashMap, io::Write};
 use tracing::{debug, info, warn};
 
+pub mod batch;
+pub mod cache;
+pub mod carry_over;
+pub mod chart;
+pub mod compare;
+pub mod indicators;
+pub mod journal;
+pub mod performance;
+pub mod plan;
+pub mod portfolio;
 pub mod qrcode;
+pub mod quote_export;
+pub mod rebalance;
 pub mod settings;
+pub mod sweep;
+pub mod tax;
 pub mod validate;
+pub mod watch;
 
+use batch::{batch_keys, default_run, BatchResult, BatchResults, BatchStatus};
+use cache::{Cache, MarketData};
+use chart::ChartOptions;
+use indicators::IndicatorOptions;
+use journal::{Journal, JournalEntry, JournalStatus};
+use performance::{Performance, Snapshot, SnapshotStore};
+use plan::Plan;
+use quote_export::{ExportFormat, ExportLayout, QuoteTable};
+use rebalance::{RebalanceMode, Targets};
 use settings::{get_settings, save_settings, Settings};
+use sweep::SweepRules;
+use tax::{Operation, TaxReport};
+use watch::{WatchState, Watchlist};
 
 #[cfg(not(tarpaulin_include))]
 pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
@@ -31,7 +70,7 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
         env!("CARGO_PKG_VERSION"),
         env!("CARGO_PKG_REPOSITORY")
     );
-    println!("");
+    println!();
 
     match matches.subcommand() {
         // These matches do not require authentication
@@ -50,10 +89,14 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
         Some(("quote", quote_matches)) => {
             info!("Fetching quotes...");
 
-            let symbol = quote_matches
-                .get_one::<String>("symbol")
-                .map(|s| s.as_str())
-                .unwrap();
+            let mut web_client: BoursoWebClient = get_client();
+            web_client.set_concurrency(*quote_matches.get_one::<usize>("concurrency").unwrap());
+            let market_data = MarketData::new(&web_client, open_cache(quote_matches)?);
+
+            let mut symbols = Vec::new();
+            for symbol in quote_matches.get_many::<String>("symbol").unwrap() {
+                symbols.push(web_client.resolve_symbol(symbol).await?);
+            }
             let length = quote_matches
                 .get_one::<String>("length")
                 .map(|s| s.as_str())
@@ -62,10 +105,71 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
                 .get_one::<String>("interval")
                 .map(|s| s.as_str())
                 .unwrap();
-            let web_client: BoursoWebClient = get_client();
 
-            let quotes = web_client
-                .get_ticks(symbol, length.parse()?, interval.parse()?)
+            let period: TickPeriod = interval.parse()?;
+
+            if let Some(("export", export_matches)) = quote_matches.subcommand() {
+                let format: ExportFormat = export_matches
+                    .get_one::<String>("format")
+                    .unwrap()
+                    .parse()?;
+                let layout: ExportLayout = export_matches
+                    .get_one::<String>("layout")
+                    .unwrap()
+                    .parse()?;
+                let output_path = export_matches
+                    .get_one::<String>("output")
+                    .map(|s| s.as_str());
+
+                let mut table = QuoteTable::new(layout);
+                for symbol in symbols.iter() {
+                    let quotes = market_data
+                        .get_ticks(symbol, length.parse()?, period)
+                        .await?;
+                    table.add_candles(symbol, &quotes.d.get_candles(), period.is_intraday());
+                }
+
+                if export_matches.get_flag("append") {
+                    let path = output_path.unwrap();
+                    if std::path::Path::new(path).exists() {
+                        let mut existing =
+                            QuoteTable::parse(&std::fs::read_to_string(path)?, format, layout)
+                                .context(format!("Failed to read the quotes of {}", path))?;
+                        existing.merge(table);
+                        table = existing;
+                    }
+                }
+
+                let content = table.render(format);
+                match output_path {
+                    Some(path) => {
+                        std::fs::write(path, &content)?;
+                        info!("{} rows of quotes exported to {}", table.len(), path);
+                    }
+                    None => {
+                        println!("{}", content);
+                    }
+                }
+
+                return Ok(());
+            }
+
+            if symbols.len() > 1 {
+                if quote_matches.subcommand().is_some() {
+                    warn!("Several symbols are only supported by the export command, or without a command to compare them");
+                    return Ok(());
+                }
+
+                let symbols = symbols.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                let quotes = market_data.instrument_quotes(&symbols).await;
+                println!("{}", compare::render_table(&quotes));
+
+                return Ok(());
+            }
+            let symbol = symbols.remove(0);
+
+            let quotes = market_data
+                .get_ticks(&symbol, length.parse()?, period)
                 .await?;
 
             match quote_matches.subcommand() {
@@ -86,14 +190,10 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
                     info!(average_quote, "Average quote: {:#?}", average_quote);
                 }
                 Some(("last", _)) => {
-                    let quote: QuoteTab;
-
-                    let last_quote = quotes.d.get_last_quote();
-                    if last_quote.is_some() {
-                        quote = last_quote.unwrap();
-                    } else {
-                        quote = quotes.d.quote_tab.last().unwrap().clone();
-                    }
+                    let quote: QuoteTab = match quotes.d.get_last_quote() {
+                        Some(last_quote) => last_quote,
+                        None => quotes.d.quote_tab.last().unwrap().clone(),
+                    };
 
                     info!(
                         close = quote.close, open = quote.open, high = quote.high, low = quote.low, volume = quote.volume,
@@ -101,13 +201,76 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
                         quote.close, quote.open, quote.high, quote.low, quote.volume
                     );
                 }
+                Some(("chart", chart_matches)) => {
+                    let options = ChartOptions {
+                        kind: chart_matches.get_one::<String>("kind").unwrap().parse()?,
+                        width: chart_matches
+                            .get_one::<usize>("width")
+                            .copied()
+                            .unwrap_or_else(chart::terminal_width),
+                        height: *chart_matches.get_one::<usize>("height").unwrap(),
+                        sma: chart_matches.get_one::<usize>("sma").copied(),
+                    };
+
+                    info!(
+                        "Chart of {} over {} quotes:",
+                        symbol,
+                        quotes.d.quote_tab.len()
+                    );
+                    println!();
+                    for line in
+                        chart::render(&quotes.d.get_candles(), &options, period.is_intraday())
+                    {
+                        println!("{}", line);
+                    }
+                }
+                Some(("indicators", indicators_matches)) => {
+                    let values = |id: &str| {
+                        indicators_matches
+                            .get_many::<usize>(id)
+                            .map(|v| v.copied().collect::<Vec<_>>())
+                            .unwrap_or_default()
+                    };
+                    let mut options = IndicatorOptions {
+                        sma: values("sma"),
+                        ema: values("ema"),
+                        rsi: indicators_matches.get_one::<usize>("rsi").copied(),
+                        macd: indicators_matches.get_flag("macd"),
+                        bollinger: indicators_matches.get_one::<usize>("bollinger").copied(),
+                        atr: indicators_matches.get_one::<usize>("atr").copied(),
+                        drawdown: indicators_matches.get_flag("drawdown"),
+                        volatility: indicators_matches.get_flag("volatility"),
+                        returns: values("returns"),
+                    };
+                    if options.is_empty() {
+                        options = IndicatorOptions::defaults();
+                    }
+
+                    info!(
+                        "Indicators of {} over {} quotes:",
+                        symbol,
+                        quotes.d.quote_tab.len()
+                    );
+                    for line in indicators::render(&quotes.d.quote_tab, period, &options) {
+                        info!("{}", line);
+                    }
+                }
                 _ => {
                     info!("Quotes:");
-                    for quote in quotes.d.quote_tab.iter() {
+                    for candle in quotes.d.get_candles().iter() {
+                        let date = if period.is_intraday() {
+                            candle
+                                .time
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                        } else {
+                            candle.time.format("%Y-%m-%d").to_string()
+                        };
                         info!(
-                            date = quote.date, close = quote.close, open = quote.open, high = quote.high, low = quote.low, volume = quote.volume,
-                            "Quote day {:#?}: Close: {:#?}, Open: {:#?}, High: {:#?}, Low: {:#?}, Volume: {:#?}",
-                            quote.date, quote.close, quote.open, quote.high, quote.low, quote.volume,
+                            date, close = candle.close, open = candle.open, high = candle.high, low = candle.low, volume = candle.volume,
+                            "Quote {}: Close: {:#?}, Open: {:#?}, High: {:#?}, Low: {:#?}, Volume: {:#?}",
+                            date, candle.close, candle.open, candle.high, candle.low, candle.volume,
                         );
                     }
                 }
@@ -115,10 +278,132 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
 
             return Ok(());
         }
+        Some(("search", search_matches)) => {
+            let query = search_matches
+                .get_one::<String>("query")
+                .map(|s| s.as_str())
+                .unwrap();
+            let web_client: BoursoWebClient = get_client();
+
+            let instruments = web_client.search_instruments(query).await?;
+
+            if instruments.is_empty() {
+                warn!("No instrument found for '{}'", query);
+                return Ok(());
+            }
+
+            for instrument in instruments.iter() {
+                info!(
+                    symbol = instrument.symbol,
+                    isin = instrument.isin,
+                    "{} - {} ({}, {}, {})",
+                    instrument.symbol,
+                    instrument.label,
+                    instrument.isin.as_deref().unwrap_or("no ISIN"),
+                    instrument.kind,
+                    instrument.exchange.as_deref().unwrap_or("unknown exchange")
+                );
+            }
+
+            return Ok(());
+        }
+        Some(("cache", cache_matches)) => {
+            if let Some(("clear", _)) = cache_matches.subcommand() {
+                let removed = Cache::open_default()?.clear()?;
+                info!("{} cached responses removed ✅", removed);
+            }
+            return Ok(());
+        }
+        Some(("watch", watch_matches)) => {
+            let watchlist = Watchlist::load(
+                watch_matches
+                    .get_one::<String>("watchlist")
+                    .map(|s| s.as_str()),
+            )?;
+            let mut state = WatchState::open_default()?;
+            let web_client: BoursoWebClient = get_client();
+            let market_data = MarketData::new(&web_client, open_cache(watch_matches)?);
+
+            info!(
+                "Watching {} symbols every {} seconds while their market is open",
+                watchlist.watches.len(),
+                watchlist.interval
+            );
+            watch::run(
+                &market_data,
+                &watchlist,
+                &mut state,
+                watch_matches.get_flag("once"),
+            )
+            .await?;
+
+            return Ok(());
+        }
+        Some(("plan", plan_matches)) => {
+            let (command, command_matches) = plan_matches.subcommand().unwrap();
+            let plan = Plan::load(
+                command_matches
+                    .get_one::<String>("plan")
+                    .map(|s| s.as_str()),
+            )?;
+            let journal = Journal::open_default()?;
+            let today = chrono::Local::now().date_naive();
+
+            match command {
+                "show" => {
+                    for buy in plan.buys.iter() {
+                        let key = buy.journal_key(today);
+                        let status = if journal.is_completed(&key) {
+                            "done"
+                        } else if journal
+                            .last(&key)
+                            .is_some_and(|entry| entry.status == JournalStatus::Started)
+                        {
+                            "outcome unknown, check your orders"
+                        } else if today >= buy.due_date(today) {
+                            "due"
+                        } else {
+                            "not yet due"
+                        };
+                        info!(
+                            "{} - {} {} on account {} ({:?}, due from {}): {}",
+                            buy.name(),
+                            buy.amount
+                                .map(|a| format!("{:.2}", a))
+                                .unwrap_or_else(|| format!("{} x", buy.quantity.unwrap())),
+                            buy.symbol,
+                            buy.account,
+                            buy.cadence,
+                            buy.due_date(today),
+                            status
+                        );
+                    }
+                    return Ok(());
+                }
+                _ => {
+                    let due = plan.due(&journal, today, command_matches.get_flag("force"));
+                    if due.is_empty() {
+                        info!("No buy is due, nothing to do");
+                        return Ok(());
+                    }
+                    for buy in due {
+                        info!("Buy '{}' is due ({})", buy.name(), buy.journal_key(today));
+                    }
+                    if command_matches.get_flag("dry-run") {
+                        return Ok(());
+                    }
+                }
+            }
+        }
         // These matches require authentication
         Some(("accounts", _))
         | Some(("export", _))
         | Some(("balance", _))
+        | Some(("beneficiaries", _))
+        | Some(("portfolio", _))
+        | Some(("rebalance", _))
+        | Some(("sweep", _))
+        | Some(("tax", _))
         | Some(("trade", _))
         | Some(("transfer", _)) => (),
         _ => unreachable!(),
@@ -135,7 +420,7 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
         customer_id
     );
     info!("If you want to change it, run `bourso config --username <customer_id>`");
-    println!("");
+    println!();
     info!("We'll need your password to log you in. It will not be stored anywhere and will be asked everytime you run a command. The password will be hidden while typing.");
 
     // Get password from stdin
@@ -157,77 +442,7 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
             Some(bourso_api::client::error::ClientError::MfaRequired) => {
                 warn!("An MFA is required.");
 
-                let (otp_id, form_state, token, mfa_type) = match web_client.request_mfa().await {
-                    Ok(mfa_info) => mfa_info,
-                    Err(e) => {
-                        debug!("{:#?}", e);
-                        return Err(e);
-                    }
-                };
-                info!("To validate your identity, please open the BoursoBank app and validate the login request.");
-
-                // Loop until MFA is ready, timeout after 5 minutes
-                let mut wait_time = 0;
-                let wait_interval = 5;
-                let max_wait_time = 300;
-                loop {
-                    info!(
-                        "Checking MFA status... (waited {}s/{})",
-                        wait_time, max_wait_time
-                    );
-                    match web_client
-                        .check_mfa(
-                            mfa_type.clone(),
-                            otp_id.clone(),
-                            form_state.clone(),
-                            token.clone(),
-                        )
-                        .await
-                    {
-                        Ok(mfa_validated) => {
-                            if mfa_validated {
-                                break;
-                            }
-
-                            if wait_time >= max_wait_time {
-                                return Err(anyhow::anyhow!(
-                                    "MFA validation timed out after {} seconds",
-                                    max_wait_time
-                                ));
-                            }
-
-                            wait_time += wait_interval;
-                            tokio::time::sleep(std::time::Duration::from_secs(wait_interval)).await;
-                        }
-                        Err(e) => match e.downcast_ref() {
-                            Some(bourso_api::client::error::ClientError::QRCodeRequired(code)) => {
-                                match qrcode::generate_qr_code(code) {
-                                    Ok(qr) => {
-                                        println!();
-                                        println!("{}", qrcode::render_to_terminal(&qr));
-                                        println!();
-                                    }
-                                    Err(e) => {
-                                        debug!("{:#?}", e);
-                                        return Err(e);
-                                    }
-                                }
-                                info!(
-                                    "Please scan the latest QR code in your BoursoBank app to validate the login request."
-                                );
-                                wait_time += wait_interval;
-                                tokio::time::sleep(std::time::Duration::from_secs(wait_interval))
-                                    .await;
-                            }
-                            _ => {
-                                debug!("{:#?}", e);
-                                return Err(e);
-                            }
-                        },
-                    }
-                }
-
-                info!("MFA successful ✅");
+                validate_mfa(&mut web_client).await?;
             }
             _ => {
                 debug!("{:#?}", e);
@@ -298,18 +513,18 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
                             ];
                             for tx in &transactions {
                                 lines.push(format!(
-                                    "{};{};{};{};{};{};{};{};{};{};{}",
+                                    "{};{};{};{};{};{};{:.2};{};{};{};{:.2}",
                                     tx.date_op,
                                     tx.date_val,
                                     tx.label,
                                     tx.category,
                                     tx.category_parent,
                                     tx.supplier_found,
-                                    format!("{:.2}", tx.amount),
+                                    tx.amount,
                                     tx.comment,
                                     tx.account_num,
                                     tx.account_label,
-                                    format!("{:.2}", tx.account_balance),
+                                    tx.account_balance,
                                 ));
                             }
                             lines.join("\n")
@@ -330,6 +545,447 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
             }
         }
 
+        Some(("portfolio", portfolio_matches)) => {
+            accounts = web_client.get_accounts(Some(AccountKind::Trading)).await?;
+
+            let command_matches = match portfolio_matches.subcommand() {
+                Some((_, command_matches)) => command_matches,
+                None => portfolio_matches,
+            };
+
+            // Either the given account or all the trading accounts
+            let accounts = match command_matches.get_one::<String>("account") {
+                Some(account_id) => vec![accounts
+                    .iter()
+                    .find(|a| &a.id == account_id)
+                    .context("Account not found. Are you sure you have access to it? Run `bourso accounts --trading` to list your trading accounts")?
+                    .clone()],
+                None => accounts,
+            };
+
+            info!(
+                "Fetching positions of {} trading accounts...",
+                accounts.len()
+            );
+
+            let portfolio = web_client.get_portfolio(&accounts).await?;
+            let snapshots = portfolio
+                .accounts
+                .iter()
+                .filter_map(|a| {
+                    a.summary
+                        .as_ref()
+                        .map(|summary| Snapshot::from_summary(&a.account, summary))
+                })
+                .collect::<Vec<_>>();
+
+            match portfolio_matches.subcommand() {
+                Some(("snapshot", _)) => {
+                    let mut store = SnapshotStore::open_default()?;
+                    store.append(&snapshots)?;
+                    info!("Recorded the snapshots of {} accounts", snapshots.len());
+                }
+                Some(("performance", performance_matches)) => {
+                    let since = performance_matches
+                        .get_one::<String>("since")
+                        .map(|s| s.as_str())
+                        .unwrap();
+                    let since = NaiveDate::parse_from_str(since, "%Y-%m-%d")
+                        .context(format!("Invalid date {}, expected YYYY-MM-DD", since))?;
+                    let format = performance_matches
+                        .get_one::<String>("format")
+                        .map(|s| s.as_str())
+                        .unwrap();
+
+                    // The recorded snapshots, ending with the current values
+                    let store = SnapshotStore::open_default()?;
+                    let mut history = store.snapshots().to_vec();
+                    history.extend(snapshots);
+
+                    let mut performances = Vec::new();
+                    for account in accounts.iter() {
+                        let points = performance::points(&history, &[account.id.as_str()]);
+                        match Performance::compute(&account.name, &points, since) {
+                            Some(performance) => performances.push(performance),
+                            None => warn!(
+                                "Not enough snapshots of {} since {}, run `bourso portfolio snapshot` regularly to record them",
+                                account.name, since
+                            ),
+                        }
+                    }
+                    if accounts.len() > 1 {
+                        let account_ids =
+                            accounts.iter().map(|a| a.id.as_str()).collect::<Vec<_>>();
+                        let points = performance::points(&history, &account_ids);
+                        if let Some(performance) =
+                            Performance::compute("All accounts", &points, since)
+                        {
+                            performances.push(performance);
+                        }
+                    }
+
+                    let benchmark = match performance_matches.get_one::<String>("benchmark") {
+                        Some(symbol) => {
+                            let start = performances.iter().map(|p| p.start).min().unwrap_or(since);
+                            let end = chrono::Local::now().date_naive();
+                            let market_data = MarketData::new(&web_client, open_cache(&matches)?);
+                            let ticks = market_data
+                                .get_ticks(
+                                    symbol,
+                                    performance::benchmark_length(start, end),
+                                    TickPeriod::Daily,
+                                )
+                                .await?;
+                            performance::benchmark_return(&ticks.d.get_candles(), start, end)
+                                .map(|r| (symbol.as_str(), r))
+                        }
+                        None => None,
+                    };
+
+                    let content = match format {
+                        "json" => serde_json::to_string_pretty(&serde_json::json!({
+                            "performances": performances,
+                            "benchmark": benchmark.map(|(symbol, r)| serde_json::json!({
+                                "symbol": symbol,
+                                "return": r,
+                            })),
+                        }))?,
+                        _ => performance::render_table(&performances, benchmark),
+                    };
+                    println!("{}", content);
+                }
+                _ => {
+                    let format = portfolio_matches
+                        .get_one::<String>("format")
+                        .map(|s| s.as_str())
+                        .unwrap();
+
+                    let content = match format {
+                        "json" => portfolio::render_json(&portfolio)?,
+                        "csv" => portfolio::render_csv(&portfolio)?,
+                        _ => portfolio::render_table(&portfolio),
+                    };
+                    println!("{}", content);
+                }
+            }
+        }
+
+        Some(("tax", tax_matches)) => match tax_matches.subcommand() {
+            Some(("report", report_matches)) => {
+                accounts = web_client.get_accounts(Some(AccountKind::Trading)).await?;
+
+                let year = match report_matches.get_one::<i32>("year") {
+                    Some(year) => *year,
+                    None => chrono::Local::now().year() - 1,
+                };
+                let format = report_matches
+                    .get_one::<String>("format")
+                    .map(|s| s.as_str())
+                    .unwrap();
+                let output_path = report_matches
+                    .get_one::<String>("output")
+                    .map(|s| s.as_str());
+
+                // Either the given account or all the trading accounts
+                let accounts = match report_matches.get_one::<String>("account") {
+                    Some(account_id) => vec![accounts
+                        .iter()
+                        .find(|a| &a.id == account_id)
+                        .context("Account not found. Are you sure you have access to it? Run `bourso accounts --trading` to list your trading accounts")?
+                        .clone()],
+                    None => accounts,
+                };
+
+                let portfolio = web_client.get_portfolio(&accounts).await?;
+                let end_date = NaiveDate::from_ymd_opt(year, 12, 31).context("Invalid year")?;
+
+                let mut reports = Vec::new();
+                let mut fiscalities = Vec::new();
+                for portfolio_account in portfolio.accounts.iter() {
+                    let account = &portfolio_account.account;
+
+                    // The whole history is needed to compute the PRU of the positions sold during the year
+                    let start_date = match portfolio_account.summary.as_ref().and_then(|s| {
+                        NaiveDate::parse_from_str(&s.activation_date, "%Y-%m-%d").ok()
+                    }) {
+                        Some(date) => date,
+                        None => {
+                            warn!(
+                                "Opening date of {} unknown, only the operations of {} are used",
+                                account.name, year
+                            );
+                            NaiveDate::from_ymd_opt(year, 1, 1).context("Invalid year")?
+                        }
+                    };
+                    info!(
+                        "Fetching the operations of {} since {}...",
+                        account.name, start_date
+                    );
+                    let operations = web_client
+                        .get_transactions(
+                            &account.id,
+                            &start_date.format("%d/%m/%Y").to_string(),
+                            &end_date.format("%d/%m/%Y").to_string(),
+                        )
+                        .await?
+                        .iter()
+                        .filter_map(Operation::from_transaction)
+                        .collect::<Vec<_>>();
+
+                    // Bourso knows the account type and its gains, given any symbol it holds
+                    let prepare = match portfolio_account.positions.first() {
+                        Some(position) => {
+                            Some(web_client.prepare_order(account, &position.symbol).await?)
+                        }
+                        None => None,
+                    };
+                    let pea = match &prepare {
+                        Some(prepare) => prepare.account.type_field.starts_with("PEA"),
+                        None => account.name.to_uppercase().contains("PEA"),
+                    };
+
+                    let mut report = TaxReport::compute(&account.name, &operations, year, pea);
+                    // The current positions only match the history when it ends with the year
+                    if year >= chrono::Local::now().year() {
+                        report.check_positions(&portfolio_account.positions);
+                    }
+                    reports.push(report);
+                    fiscalities.push(prepare.map(|p| p.account_fiscality));
+                }
+
+                let content = match format {
+                    "json" => tax::render_json(&reports)?,
+                    "csv" => tax::render_csv(&reports),
+                    _ => reports
+                        .iter()
+                        .zip(fiscalities.iter())
+                        .map(|(report, fiscality)| tax::render_table(report, fiscality.as_ref()))
+                        .collect::<Vec<_>>()
+                        .join("\n\n"),
+                };
+                match output_path {
+                    Some(path) => {
+                        std::fs::write(path, &content)?;
+                        info!("Tax report exported to {}", path);
+                    }
+                    None => println!("{}", content),
+                }
+            }
+            _ => unreachable!(),
+        },
+
+        Some(("rebalance", rebalance_matches)) => {
+            accounts = web_client.get_accounts(Some(AccountKind::Trading)).await?;
+
+            let account_id = rebalance_matches
+                .get_one::<String>("account")
+                .map(|s| s.as_str())
+                .unwrap();
+            let account = accounts
+                .iter()
+                .find(|a| a.id == account_id)
+                .context("Account not found. Are you sure you have access to it? Run `bourso accounts --trading` to list your trading accounts")?;
+            let targets = Targets::load(
+                rebalance_matches
+                    .get_one::<String>("targets")
+                    .map(|s| s.as_str())
+                    .unwrap(),
+            )?;
+            let mode = match rebalance_matches
+                .get_one::<String>("mode")
+                .map(|s| s.as_str())
+                .unwrap()
+            {
+                "full" => RebalanceMode::Full,
+                _ => RebalanceMode::BuyOnly,
+            };
+
+            let summary = web_client.get_trading_summary(account.clone()).await?;
+            let portfolio_account = PortfolioAccount::from_summary(account.clone(), summary);
+            let cash = match rebalance_matches.get_one::<f64>("cash") {
+                Some(cash) => *cash,
+                None => portfolio_account
+                    .summary
+                    .as_ref()
+                    .map(|s| s.cash.value)
+                    .unwrap_or(0.0),
+            };
+
+            let mut holdings = portfolio_account
+                .positions
+                .iter()
+                .map(|p| rebalance::Holding {
+                    symbol: p.symbol.clone(),
+                    quantity: p.quantity.value,
+                    price: p.last.value,
+                })
+                .collect::<Vec<_>>();
+            // Fetch the price of the targets that are not held yet
+            for target in targets.targets.iter() {
+                if !holdings.iter().any(|h| h.symbol == target.symbol) {
+                    let quote = web_client.instrument_quote(&target.symbol).await?;
+                    holdings.push(rebalance::Holding {
+                        symbol: target.symbol.clone(),
+                        quantity: 0.0,
+                        price: quote.last,
+                    });
+                }
+            }
+
+            let mut orders = rebalance::compute(&targets, &holdings, cash, mode)?;
+
+            info!("Estimating the fees of {} orders...", orders.len());
+            // Quantity each order was estimated for, lowering an order to fit the cash
+            // changes its fees so it is estimated again until every quantity is settled
+            let mut estimated: HashMap<String, usize> = HashMap::new();
+            loop {
+                for order in orders.iter_mut() {
+                    if estimated.get(&order.symbol) == Some(&order.quantity) {
+                        continue;
+                    }
+                    let estimate = web_client
+                        .estimate_order(order.side, account, &order.symbol, order.quantity)
+                        .await?;
+                    order.price = estimate.price;
+                    order.fees = estimate.fees;
+                    estimated.insert(order.symbol.clone(), order.quantity);
+                }
+                rebalance::fit_to_cash(&mut orders, cash);
+                if orders
+                    .iter()
+                    .all(|order| estimated.get(&order.symbol) == Some(&order.quantity))
+                {
+                    break;
+                }
+            }
+
+            if orders.is_empty() {
+                info!("Nothing to rebalance with {:.2} of cash ✅", cash);
+                return Ok(());
+            }
+
+            println!("{}", rebalance::render_table(&orders));
+            println!();
+
+            let date = chrono::Local::now().date_naive();
+            let order_key = |order: &rebalance::RebalanceOrder| {
+                format!(
+                    "rebalance:{}:{}:{}:{}x:{}",
+                    account.id,
+                    match order.side {
+                        OrderSide::Buy => "buy",
+                        OrderSide::Sell => "sell",
+                    },
+                    order.symbol,
+                    order.quantity,
+                    date
+                )
+            };
+            let mut journal = Journal::open_default()?;
+            for order in orders.iter() {
+                journal.guard(&order_key(order), rebalance_matches.get_flag("force"))?;
+            }
+
+            if !rebalance_matches.get_flag("yes") {
+                print!("Place these {} orders? [y/N] ", orders.len());
+                std::io::stdout().flush()?;
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                if !answer.trim().eq_ignore_ascii_case("y") {
+                    info!("No order placed");
+                    return Ok(());
+                }
+            }
+
+            // Sell orders come first so that their proceeds can fund the buy orders
+            for order in orders.iter() {
+                let key = order_key(order);
+                journal.append(JournalEntry::new(
+                    &key,
+                    JournalStatus::Started,
+                    serde_json::json!({ "account": account.id, "symbol": order.symbol }),
+                ))?;
+
+                match web_client
+                    .order(order.side, account, &order.symbol, order.quantity, None)
+                    .await
+                {
+                    Ok((order_id, price)) => journal.append(JournalEntry::new(
+                        &key,
+                        JournalStatus::Completed,
+                        serde_json::json!({
+                            "orderId": order_id,
+                            "quantity": order.quantity,
+                            "price": price,
+                        }),
+                    ))?,
+                    // The order may have been placed, keep it started so that it is not placed
+                    // again without --force
+                    Err(e)
+                        if matches!(
+                            e.downcast_ref::<OrderError>(),
+                            Some(OrderError::ConfirmationFailed)
+                        ) =>
+                    {
+                        warn!("The outcome of the order is unknown, check your orders before running the rebalance again");
+                        return Err(e);
+                    }
+                    Err(e) => {
+                        journal.append(JournalEntry::new(
+                            &key,
+                            JournalStatus::Failed,
+                            serde_json::json!({ "error": e.to_string() }),
+                        ))?;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        Some(("plan", plan_matches)) => {
+            let (_, run_matches) = plan_matches.subcommand().unwrap();
+            let plan = Plan::load(run_matches.get_one::<String>("plan").map(|s| s.as_str()))?;
+            let mut journal = Journal::open_default()?;
+
+            plan::run(
+                &web_client,
+                &plan,
+                &mut journal,
+                chrono::Local::now().date_naive(),
+                run_matches.get_flag("force"),
+            )
+            .await?;
+        }
+
+        Some(("sweep", sweep_matches)) => {
+            let rules =
+                SweepRules::load(sweep_matches.get_one::<String>("rules").map(|s| s.as_str()))?;
+            accounts = web_client.get_accounts(None).await?;
+
+            let movements = rules.compute(&accounts)?;
+            if movements.is_empty() {
+                info!("Every account follows its rules, nothing to do ✅");
+                return Ok(());
+            }
+
+            println!("{}", sweep::render_table(&movements, &accounts));
+            println!();
+
+            if sweep_matches.get_flag("dry-run") {
+                return Ok(());
+            }
+
+            let mut journal = Journal::open_default()?;
+            sweep::run(
+                &web_client,
+                &movements,
+                &accounts,
+                &mut journal,
+                sweep_matches.get_flag("force"),
+            )
+            .await?;
+        }
+
         Some(("trade", trade_matches)) => {
             accounts = web_client.get_accounts(Some(AccountKind::Trading)).await?;
 
@@ -349,15 +1005,125 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
                                 .context("Account not found. Are you sure you have access to it? Run `bourso accounts` to list your accounts")?;
 
                             let side = new_order_matches.get_one::<OrderSide>("side").unwrap();
-                            let quantity = new_order_matches.get_one::<usize>("quantity").unwrap();
-                            let symbol = new_order_matches
-                                .get_one::<String>("symbol")
-                                .map(|s| s.as_str())
-                                .unwrap();
-
-                            let _ = web_client
-                                .order(side.to_owned(), account, symbol, quantity.to_owned(), None)
+                            let symbol = web_client
+                                .resolve_symbol(
+                                    new_order_matches
+                                        .get_one::<String>("symbol")
+                                        .map(|s| s.as_str())
+                                        .unwrap(),
+                                )
                                 .await?;
+                            let symbol = symbol.as_str();
+                            let price_limit = new_order_matches.get_one::<f64>("limit").copied();
+
+                            let amount = new_order_matches.get_one::<f64>("amount");
+                            let quantity = new_order_matches.get_one::<usize>("quantity");
+                            let key = match new_order_matches.get_one::<String>("key") {
+                                Some(key) => key.clone(),
+                                None => format!(
+                                    "order:{}:{}:{}:{}:{}",
+                                    account.id,
+                                    match side {
+                                        OrderSide::Buy => "buy",
+                                        OrderSide::Sell => "sell",
+                                    },
+                                    symbol,
+                                    match amount {
+                                        Some(amount) => format!("{:.2}", amount),
+                                        None => format!("{}x", quantity.unwrap()),
+                                    },
+                                    chrono::Local::now().date_naive()
+                                ),
+                            };
+                            let mut journal = Journal::open_default()?;
+                            journal.guard(&key, new_order_matches.get_flag("force"))?;
+                            journal.append(JournalEntry::new(
+                                &key,
+                                JournalStatus::Started,
+                                serde_json::json!({ "account": account.id, "symbol": symbol }),
+                            ))?;
+
+                            let result = match amount {
+                                Some(amount) => {
+                                    let carry = new_order_matches.get_flag("carry-over");
+                                    carry_over::order_by_amount(
+                                        &web_client,
+                                        side.to_owned(),
+                                        account,
+                                        symbol,
+                                        *amount,
+                                        price_limit,
+                                        carry,
+                                    )
+                                    .await
+                                    .map(|order| match order {
+                                        Some(order) => serde_json::json!({
+                                            "orderId": order.order_id,
+                                            "quantity": order.quantity,
+                                            "cost": order.cost,
+                                        }),
+                                        None => serde_json::json!({ "carriedOver": amount }),
+                                    })
+                                }
+                                None => match price_limit {
+                                    Some(price_limit) => {
+                                        web_client
+                                            .limit_order(
+                                                side.to_owned(),
+                                                account,
+                                                symbol,
+                                                quantity.unwrap().to_owned(),
+                                                price_limit,
+                                            )
+                                            .await
+                                    }
+                                    None => {
+                                        web_client
+                                            .order(
+                                                side.to_owned(),
+                                                account,
+                                                symbol,
+                                                quantity.unwrap().to_owned(),
+                                                None,
+                                            )
+                                            .await
+                                    }
+                                }
+                                .map(|(order_id, price)| {
+                                    serde_json::json!({
+                                        "orderId": order_id,
+                                        "quantity": quantity,
+                                        "price": price,
+                                    })
+                                }),
+                            };
+
+                            match result {
+                                Ok(details) => journal.append(JournalEntry::new(
+                                    &key,
+                                    JournalStatus::Completed,
+                                    details,
+                                ))?,
+                                // The order may have been placed, keep it started so that it is
+                                // not placed again without --force
+                                Err(e)
+                                    if matches!(
+                                        e.downcast_ref::<OrderError>(),
+                                        Some(OrderError::ConfirmationFailed)
+                                    ) =>
+                                {
+                                    warn!("The outcome of the order is unknown, check your orders before placing it again");
+                                    return Err(e);
+                                }
+                                Err(e) => {
+                                    journal.append(JournalEntry::new(
+                                        &key,
+                                        JournalStatus::Failed,
+                                        serde_json::json!({ "error": e.to_string() }),
+                                    ))?;
+                                    return Err(e);
+                                }
+                            }
                         }
                         _ => unreachable!(),
                     }
@@ -366,81 +1132,611 @@ pub async fn parse_matches(matches: ArgMatches) -> Result<()> {
             }
         }
 
-        Some(("transfer", transfer_matches)) => {
-            accounts = web_client.get_accounts(None).await?;
+        Some(("beneficiaries", beneficiaries_matches)) => {
+            match beneficiaries_matches.subcommand() {
+                Some(("list", list_matches)) => {
+                    let account_id = list_matches
+                        .get_one::<String>("account")
+                        .map(|s| s.as_str())
+                        .unwrap();
 
-            let from_account_id = transfer_matches
-                .get_one::<String>("account")
-                .map(|s| s.as_str())
-                .unwrap();
-            let to_account_id = transfer_matches
-                .get_one::<String>("to_account")
-                .map(|s| s.as_str())
-                .unwrap();
-            let amount = transfer_matches
-                .get_one::<String>("amount")
-                .map(|s| s.parse::<f64>().unwrap())
-                .unwrap();
-            let reason = transfer_matches
-                .get_one::<String>("reason")
-                .map(|s| s.as_str());
+                    let beneficiaries = web_client.list_beneficiaries(account_id).await?;
+                    info!("Found {} beneficiaries", beneficiaries.len());
+                    for beneficiary in beneficiaries.iter() {
+                        println!(
+                            "{}  {:<34}  {:<11}  {}",
+                            beneficiary.id, beneficiary.iban, beneficiary.bic, beneficiary.name
+                        );
+                    }
+                }
+                Some(("add", add_matches)) => {
+                    let account_id = add_matches
+                        .get_one::<String>("account")
+                        .map(|s| s.as_str())
+                        .unwrap();
+                    let name = add_matches
+                        .get_one::<String>("name")
+                        .map(|s| s.as_str())
+                        .unwrap();
+                    let iban = add_matches
+                        .get_one::<String>("iban")
+                        .map(|s| s.as_str())
+                        .unwrap();
+                    let bic = add_matches.get_one::<String>("bic").map(|s| s.as_str());
 
-            // Get from_account from previously fetched accounts
-            let from_account = accounts
-                .iter()
-                .find(|a| a.id == from_account_id)
-                .context("From account not found. Are you sure you have access to it? Run `bourso accounts` to list your accounts")?;
+                    match web_client
+                        .add_beneficiary(account_id, name, iban, bic)
+                        .await
+                    {
+                        Ok(_) => (),
+                        Err(e) => match e.downcast_ref() {
+                            Some(bourso_api::client::error::ClientError::MfaRequired) => {
+                                warn!("Adding a beneficiary requires a strong authentication.");
+                                validate_mfa(&mut web_client).await?;
+                                web_client
+                                    .add_beneficiary(account_id, name, iban, bic)
+                                    .await?;
+                            }
+                            _ => return Err(e),
+                        },
+                    }
+                    info!("Beneficiary {} added ✅", name);
+                }
+                _ => unreachable!(),
+            }
+        }
 
-            // Get to_account from previously fetched accounts
-            let to_account = accounts
-                .iter()
-                .find(|a| a.id == to_account_id)
-                .context("To account not found. Are you sure you have access to it? Run `bourso accounts` to list your accounts")?;
-
-            let stream = web_client.transfer_funds(
-                amount,
-                from_account.clone(),
-                to_account.clone(),
-                reason.map(|s| s.to_string()),
-            );
+        Some(("transfer", transfer_matches)) => match transfer_matches.subcommand() {
+            Some(("limits", limits_matches)) => {
+                let account_id = limits_matches
+                    .get_one::<String>("account")
+                    .map(|s| s.as_str())
+                    .unwrap();
+
+                let limits = web_client.get_transfer_limits(account_id).await?;
+                if limits.is_empty() {
+                    info!("No transfer ceiling found for account {}", account_id);
+                }
+                for limit in limits.iter() {
+                    println!(
+                        "{:<8}  {:>10.2}  {:>10.2} remaining  {}",
+                        format!("{:?}", limit.period).to_lowercase(),
+                        limit.ceiling,
+                        limit.remaining,
+                        limit.label
+                    );
+                }
+            }
+            Some(("list", list_matches)) => {
+                let account_id = list_matches
+                    .get_one::<String>("account")
+                    .map(|s| s.as_str())
+                    .unwrap();
+                let today = chrono::Local::now().date_naive();
+                let until = list_matches
+                    .get_one::<NaiveDate>("until")
+                    .copied()
+                    .unwrap_or(today);
+                let since = list_matches
+                    .get_one::<NaiveDate>("since")
+                    .copied()
+                    .unwrap_or(today - chrono::Days::new(30));
 
-            pin_mut!(stream);
-
-            // Track progress and update display
-            while let Some(progress_result) = stream.next().await {
-                let progress = progress_result?;
-                let step = progress.step_number();
-                let total = TransferProgress::total_steps();
-                let percentage = (step as f32 / total as f32 * 100.0) as u8;
-
-                // Create a simple progress bar
-                let bar_length = 30;
-                let filled = (bar_length as f32 * step as f32 / total as f32) as usize;
-                let bar: String = "█".repeat(filled) + &"░".repeat(bar_length - filled);
-
-                // Use ANSI escape code to clear the line before printing
-                // \x1B[2K clears the entire line, \r returns cursor to start
-                print!(
-                    "\x1B[2K\r[{}] {:3}% - {}/{} - {}",
-                    bar,
-                    percentage,
-                    step,
-                    total,
-                    progress.description()
+                let transfers = web_client.list_transfers(account_id, since..=until).await?;
+                if transfers.is_empty() {
+                    info!(
+                        "No transfer from account {} between {} and {}",
+                        account_id, since, until
+                    );
+                }
+                for transfer in transfers.iter() {
+                    println!(
+                        "{}  {}  {:>10.2}  {:<24}  {:<9}  {:<9}  {}",
+                        transfer.id,
+                        transfer.date,
+                        transfer.amount,
+                        transfer.counterparty,
+                        transfer.status.name(),
+                        format!("{:?}", transfer.scheduling).to_lowercase(),
+                        transfer.label
+                    );
+                }
+            }
+            Some(("standing-orders", standing_matches)) => match standing_matches.subcommand() {
+                Some(("list", list_matches)) => {
+                    let account_id = list_matches
+                        .get_one::<String>("account")
+                        .map(|s| s.as_str())
+                        .unwrap();
+
+                    let orders = web_client.list_standing_orders(account_id).await?;
+                    if orders.is_empty() {
+                        info!(
+                            "No deferred transfer nor standing order on account {}",
+                            account_id
+                        );
+                    }
+                    for order in orders.iter() {
+                        println!(
+                            "{}  {:>10.2}  {:<24}  {:<10}  next: {}  {}",
+                            order.id,
+                            order.amount,
+                            order.beneficiary,
+                            order.frequency.as_deref().unwrap_or("once"),
+                            order.next_execution,
+                            order.label
+                        );
+                    }
+                }
+                Some(("cancel", cancel_matches)) => {
+                    let account_id = cancel_matches
+                        .get_one::<String>("account")
+                        .map(|s| s.as_str())
+                        .unwrap();
+                    let order_id = cancel_matches
+                        .get_one::<String>("id")
+                        .map(|s| s.as_str())
+                        .unwrap();
+
+                    web_client
+                        .cancel_standing_order(account_id, order_id)
+                        .await?;
+                    info!("Standing order {} cancelled ✅", order_id);
+                }
+                _ => unreachable!(),
+            },
+            _ if transfer_matches.contains_id("batch") => {
+                accounts = web_client.get_accounts(None).await?;
+
+                let batch_path = transfer_matches
+                    .get_one::<String>("batch")
+                    .map(|s| s.as_str())
+                    .unwrap();
+                let run = match transfer_matches.get_one::<String>("run") {
+                    Some(run) => run.clone(),
+                    None => default_run(),
+                };
+                let results_path = match transfer_matches.get_one::<String>("results") {
+                    Some(path) => path.clone(),
+                    None => BatchResults::default_path(batch_path, &run),
+                };
+                let dry_run = transfer_matches.get_flag("dry-run");
+
+                let transfers = parse_batch(
+                    &std::fs::read_to_string(batch_path)
+                        .context(format!("Failed to read the batch file {}", batch_path))?,
+                )?;
+                validate_batch(&transfers, &accounts)?;
+
+                let keys = batch_keys(&transfers);
+                let mut results = BatchResults::open(&results_path, &run)?;
+                let mut journal = Journal::open_default()?;
+                let force = transfer_matches.get_flag("force");
+                // Scoped to the results file and the run, like the completed transfers of the batch
+                let journal_key = |key: &str| format!("batch:{}:{}:{}", results_path, run, key);
+
+                let mut pending = Vec::new();
+                let mut skipped = 0;
+                for (index, transfer) in transfers.iter().enumerate() {
+                    if results.is_completed(&keys[index]) {
+                        skipped += 1;
+                        continue;
+                    }
+                    // Line 1 is the header
+                    if let Err(e) = journal.guard(&journal_key(&keys[index]), force) {
+                        warn!("line {}: {}", index + 2, e);
+                        continue;
+                    }
+                    pending.push((index, transfer.clone()));
+                }
+                if skipped > 0 {
+                    info!(
+                        "{} transfers already completed in run {} according to {}, they are skipped",
+                        skipped, run, results_path
+                    );
+                }
+
+                let stream = web_client.transfer_batch(pending, accounts.clone(), dry_run);
+                pin_mut!(stream);
+
+                let (mut completed, mut failed) = (0, 0);
+                let mut current = None;
+                let mut step = TransferProgress::Validating;
+                while let Some((index, progress)) = stream.next().await {
+                    let line = index + 2;
+                    let transfer = &transfers[index];
+                    let key = &keys[index];
+                    let mut details = serde_json::json!({
+                        "from": transfer.from,
+                        "to": transfer.to,
+                        "amount": transfer.amount,
+                        "line": line,
+                    });
+                    if current != Some(index) {
+                        current = Some(index);
+                        step = TransferProgress::Validating;
+                        if !dry_run {
+                            journal.append(JournalEntry::new(
+                                &journal_key(key),
+                                JournalStatus::Started,
+                                details.clone(),
+                            ))?;
+                        }
+                    }
+
+                    match progress {
+                        Ok(TransferProgress::Completed(receipt)) => {
+                            println!();
+                            info!(
+                                "line {}: transfer of {} from account {} to account {} successful ✅ (reference: {})",
+                                line,
+                                transfer.amount,
+                                transfer.from,
+                                transfer.to,
+                                receipt.reference.as_deref().unwrap_or("unknown")
+                            );
+                            details["receipt"] = serde_json::json!(receipt);
+                            journal.append(JournalEntry::new(
+                                &journal_key(key),
+                                JournalStatus::Completed,
+                                details,
+                            ))?;
+                            results.record(BatchResult {
+                                reference: receipt.reference,
+                                ..BatchResult::new(
+                                    key,
+                                    line,
+                                    transfer,
+                                    BatchStatus::Completed,
+                                    None,
+                                )
+                            })?;
+                            completed += 1;
+                        }
+                        Ok(TransferProgress::Preview(summary)) => {
+                            println!();
+                            info!(
+                                "line {}: you are about to transfer {:.2} € from {} to {} on {} (fees: {:.2} €)",
+                                line,
+                                summary.amount,
+                                summary.debit_account,
+                                summary.credit_account,
+                                summary.execution_date,
+                                summary.fees
+                            );
+                        }
+                        Ok(progress) => {
+                            print_transfer_progress(&format!("line {} ", line), &progress);
+                            step = progress;
+                        }
+                        Err(e) => {
+                            println!();
+                            warn!("line {}: transfer failed: {}", line, e);
+                            if !dry_run {
+                                // The transfer may have been confirmed, keep it started so that
+                                // it is not executed again without --force
+                                if matches!(step, TransferProgress::ConfirmingTransfer) {
+                                    warn!("line {}: the outcome of the transfer is unknown, check your account before running it again", line);
+                                } else {
+                                    details["step"] = serde_json::json!(step.description());
+                                    details["error"] = serde_json::json!(e.to_string());
+                                    journal.append(JournalEntry::new(
+                                        &journal_key(key),
+                                        JournalStatus::Failed,
+                                        details,
+                                    ))?;
+                                }
+                                results.record(BatchResult::new(
+                                    key,
+                                    line,
+                                    transfer,
+                                    BatchStatus::Failed,
+                                    Some(e.to_string()),
+                                ))?;
+                            }
+                            failed += 1;
+                        }
+                    }
+                }
+
+                if !dry_run {
+                    info!(
+                        "{} transfers completed, {} failed. Results saved in {}",
+                        completed, failed, results_path
+                    );
+                }
+                if failed > 0 {
+                    warn!("Run the same command again to retry the failed transfers only");
+                }
+            }
+            _ => {
+                accounts = web_client.get_accounts(None).await?;
+
+                let from_account_id = transfer_matches
+                    .get_one::<String>("account")
+                    .map(|s| s.as_str())
+                    .unwrap();
+                let amount = transfer_matches
+                    .get_one::<String>("amount")
+                    .map(|s| s.parse::<f64>().unwrap())
+                    .unwrap();
+                let reason = transfer_matches
+                    .get_one::<String>("reason")
+                    .map(|s| s.as_str());
+                let on = transfer_matches.get_one::<NaiveDate>("on").copied();
+                let schedule = match transfer_matches.get_one::<String>("every") {
+                    Some(every) => TransferSchedule::Recurring {
+                        frequency: every.parse()?,
+                        start: on
+                            .unwrap_or(chrono::Local::now().date_naive() + chrono::Days::new(1)),
+                        end: transfer_matches.get_one::<NaiveDate>("until").copied(),
+                    },
+                    None => match on {
+                        Some(date) => TransferSchedule::Deferred(date),
+                        None => TransferSchedule::Immediate,
+                    },
+                };
+
+                // Get from_account from previously fetched accounts
+                let from_account = accounts
+                    .iter()
+                    .find(|a| a.id == from_account_id)
+                    .context("From account not found. Are you sure you have access to it? Run `bourso accounts` to list your accounts")?;
+
+                let to_account: CreditAccount = match transfer_matches.get_one::<String>("to_iban")
+                {
+                    Some(iban) => {
+                        let iban = normalize_iban(iban);
+                        web_client
+                            .list_beneficiaries(from_account_id)
+                            .await?
+                            .into_iter()
+                            .find(|b| b.iban == iban)
+                            .context("Beneficiary not found. Run `bourso beneficiaries list` to list your beneficiaries, or `bourso beneficiaries add` to add it")?
+                            .into()
+                    }
+                    // Get to_account from previously fetched accounts
+                    None => {
+                        let to_account_id = transfer_matches
+                            .get_one::<String>("to_account")
+                            .map(|s| s.as_str())
+                            .unwrap();
+                        accounts
+                            .iter()
+                            .find(|a| a.id == to_account_id)
+                            .context("To account not found. Are you sure you have access to it? Run `bourso accounts` to list your accounts")?
+                            .clone()
+                            .into()
+                    }
+                };
+
+                let dry_run = transfer_matches.get_flag("dry-run");
+                let key = match transfer_matches.get_one::<String>("key") {
+                    Some(key) => key.clone(),
+                    None => format!(
+                        "transfer:{}:{}:{:.2}:{}",
+                        from_account.id,
+                        to_account.id(),
+                        amount,
+                        chrono::Local::now().date_naive()
+                    ),
+                };
+                let details = serde_json::json!({
+                    "from": from_account.id,
+                    "to": to_account.id(),
+                    "amount": amount,
+                    "schedule": schedule,
+                });
+                let mut journal = Journal::open_default()?;
+                journal.guard(&key, transfer_matches.get_flag("force"))?;
+                if !dry_run {
+                    journal.append(JournalEntry::new(
+                        &key,
+                        JournalStatus::Started,
+                        details.clone(),
+                    ))?;
+                }
+
+                let stream = web_client.transfer_funds(
+                    amount,
+                    from_account.clone(),
+                    to_account.clone(),
+                    TransferOptions {
+                        reason: reason.map(|s| s.to_string()),
+                        schedule: schedule.clone(),
+                        dry_run,
+                        check_limits: transfer_matches.get_flag("check-limits"),
+                    },
                 );
-                use std::io::Write;
-                std::io::stdout().flush().unwrap();
+
+                pin_mut!(stream);
+
+                // Track progress and update display
+                let mut preview = None;
+                let mut receipt = None;
+                let mut step = TransferProgress::Validating;
+                while let Some(progress_result) = stream.next().await {
+                    let progress = match progress_result {
+                        Ok(progress) => progress,
+                        Err(e) => {
+                            println!();
+                            // The transfer may have been confirmed, keep it started so that
+                            // it is not executed again without --force
+                            if matches!(step, TransferProgress::ConfirmingTransfer) {
+                                warn!("The outcome of the transfer is unknown, check your account before running it again");
+                            } else if !dry_run {
+                                let mut details = details;
+                                details["step"] = serde_json::json!(step.description());
+                                details["error"] = serde_json::json!(e.to_string());
+                                journal.append(JournalEntry::new(
+                                    &key,
+                                    JournalStatus::Failed,
+                                    details,
+                                ))?;
+                            }
+                            return Err(e);
+                        }
+                    };
+                    print_transfer_progress("", &progress);
+                    step = progress.clone();
+
+                    match progress {
+                        TransferProgress::Preview(summary) => preview = Some(summary),
+                        TransferProgress::Completed(result) => receipt = Some(result),
+                        _ => {}
+                    }
+                }
+                println!(); // New line after progress is complete
+
+                if let Some(summary) = preview {
+                    info!(
+                        "You are about to transfer {:.2} € from {} to {} on {} (fees: {:.2} €). Run the command again without --dry-run to confirm it",
+                        summary.amount,
+                        summary.debit_account,
+                        summary.credit_account,
+                        summary.execution_date,
+                        summary.fees
+                    );
+                    return Ok(());
+                }
+                let mut details = details;
+                details["receipt"] = serde_json::json!(receipt);
+                journal.append(JournalEntry::new(&key, JournalStatus::Completed, details))?;
+
+                match schedule {
+                    TransferSchedule::Immediate => info!(
+                        "Transfer of {} from account {} to account {} successful ✅",
+                        amount, from_account.id, to_account.id()
+                    ),
+                    TransferSchedule::Deferred(date) => info!(
+                        "Transfer of {} from account {} to account {} scheduled on {} ✅",
+                        amount, from_account.id, to_account.id(), date
+                    ),
+                    TransferSchedule::Recurring { frequency, start, .. } => info!(
+                        "Standing order of {} from account {} to account {} created, {:?} from {} ✅",
+                        amount, from_account.id, to_account.id(), frequency, start
+                    ),
+                }
+                if let Some(reference) = receipt.and_then(|receipt| receipt.reference) {
+                    info!("Transfer reference: {}", reference);
+                }
             }
-            println!(); // New line after progress is complete
+        },
 
-            info!(
-                "Transfer of {} from account {} to account {} successful ✅",
-                amount, from_account.id, to_account.id
-            );
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+/// Open the cache of public market data, unless disabled with `--no-cache`
+#[cfg(not(tarpaulin_include))]
+fn open_cache(matches: &ArgMatches) -> Result<Option<Cache>> {
+    if matches.get_flag("no-cache") {
+        return Ok(None);
+    }
+    Ok(Some(Cache::open_default()?))
+}
+
+/// Ask the user to validate a strong authentication in the BoursoBank app, and wait for it
+#[cfg(not(tarpaulin_include))]
+async fn validate_mfa(web_client: &mut BoursoWebClient) -> Result<()> {
+    let (otp_id, form_state, token, mfa_type) = match web_client.request_mfa().await {
+        Ok(mfa_info) => mfa_info,
+        Err(e) => {
+            debug!("{:#?}", e);
+            return Err(e);
         }
+    };
+    info!("To validate your identity, please open the BoursoBank app and validate the request.");
 
-        _ => unreachable!(),
+    // Loop until MFA is ready, timeout after 5 minutes
+    let mut wait_time = 0;
+    let wait_interval = 5;
+    let max_wait_time = 300;
+    loop {
+        info!(
+            "Checking MFA status... (waited {}s/{})",
+            wait_time, max_wait_time
+        );
+        match web_client
+            .check_mfa(
+                mfa_type.clone(),
+                otp_id.clone(),
+                form_state.clone(),
+                token.clone(),
+            )
+            .await
+        {
+            Ok(mfa_validated) => {
+                if mfa_validated {
+                    break;
+                }
+
+                if wait_time >= max_wait_time {
+                    return Err(anyhow::anyhow!(
+                        "MFA validation timed out after {} seconds",
+                        max_wait_time
+                    ));
+                }
+
+                wait_time += wait_interval;
+                tokio::time::sleep(std::time::Duration::from_secs(wait_interval)).await;
+            }
+            Err(e) => match e.downcast_ref() {
+                Some(bourso_api::client::error::ClientError::QRCodeRequired(code)) => {
+                    match qrcode::generate_qr_code(code) {
+                        Ok(qr) => {
+                            println!();
+                            println!("{}", qrcode::render_to_terminal(&qr));
+                            println!();
+                        }
+                        Err(e) => {
+                            debug!("{:#?}", e);
+                            return Err(e);
+                        }
+                    }
+                    info!(
+                        "Please scan the latest QR code in your BoursoBank app to validate the request."
+                    );
+                    wait_time += wait_interval;
+                    tokio::time::sleep(std::time::Duration::from_secs(wait_interval)).await;
+                }
+                _ => {
+                    debug!("{:#?}", e);
+                    return Err(e);
+                }
+            },
+        }
     }
 
+    info!("MFA successful ✅");
+
     Ok(())
 }
+
+/// Print a progress bar of a transfer, replacing the current line
+#[cfg(not(tarpaulin_include))]
+fn print_transfer_progress(prefix: &str, progress: &TransferProgress) {
+    let step = progress.step_number();
+    let total = TransferProgress::total_steps();
+    let percentage = (step as f32 / total as f32 * 100.0) as u8;
+
+    // Create a simple progress bar
+    let bar_length = 30;
+    let filled = (bar_length as f32 * step as f32 / total as f32) as usize;
+    let bar: String = "█".repeat(filled) + &"░".repeat(bar_length - filled);
+
+    // Use ANSI escape code to clear the line before printing
+    // \x1B[2K clears the entire line, \r returns cursor to start
+    print!(
+        "\x1B[2K\r{}[{}] {:3}% - {}/{} - {}",
+        prefix,
+        bar,
+        percentage,
+        step,
+        total,
+        progress.description()
+    );
+    std::io::stdout().flush().unwrap();
+}
@@ -1,9 +1,10 @@
 use anyhow::Result;
 use bourso_api::client::trade::order::OrderSide;
 use bourso_cli::{settings::init_logger, validate::validate_account_id};
+use chrono::NaiveDate;
 use clap::{
     builder::{PossibleValue, ValueParser},
-    Arg, Command,
+    Arg, ArgAction, Command,
 };
 
 #[tokio::main]
@@ -20,12 +21,45 @@ async fn main() -> Result<()> {
         .value_parser(ValueParser::new(validate_account_id))
         .required(true);
 
+    let plan_arg = Arg::new("plan")
+        .long("plan")
+        .help("The path to the plan file. Defaults to ~/.bourso/plan.toml")
+        .value_parser(clap::value_parser!(String))
+        .required(false);
+
+    let key_arg = Arg::new("key")
+        .long("key")
+        .help("The idempotency key of the operation, recorded in ~/.bourso/journal.jsonl. An operation completed with the same key is not executed again. Defaults to a key made of the operation parameters and the current date")
+        .value_parser(clap::value_parser!(String))
+        .required(false);
+
+    let force_arg = Arg::new("force")
+        .long("force")
+        .help("Execute the operation even if the journal shows it was already completed with the same key")
+        .action(ArgAction::SetTrue);
+
     let matches = Command::new("bourso")
         .version(VERSION.unwrap_or("0.0.1"))
         .author("@azerpas")
         .about("BoursoBank/Boursorama CLI")
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .arg(
+            Arg::new("no-cache")
+                .long("no-cache")
+                .help("Always fetch the public market data (quotes, ticks) instead of reading them from the cache in ~/.bourso/cache")
+                .action(ArgAction::SetTrue)
+                .global(true)
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("Manage the cache of public market data")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("clear")
+                        .about("Remove every cached quote and tick")
+                )
+        )
         .subcommand(
             Command::new("accounts")
                 .about("Manage your saving accounts")
@@ -50,6 +84,101 @@ async fn main() -> Result<()> {
                         .help("List all your loans")
                 )
         )
+        .subcommand(
+            Command::new("portfolio")
+                .about("Show the positions of your trading accounts")
+                .arg(
+                    account_arg
+                        .clone()
+                        .help("The trading account to use by its 'id'. Defaults to all your trading accounts (e.g: PEA and CTO)")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .help("Output format")
+                        .default_value("table")
+                        .value_parser(["table", "csv", "json"])
+                )
+                .subcommand(
+                    Command::new("snapshot")
+                        .about("Record the value and contributions of your trading accounts in ~/.bourso/snapshots.jsonl. Run it regularly (e.g: daily with cron) to track your performance")
+                        .arg(
+                            account_arg
+                                .clone()
+                                .help("The trading account to record by its 'id'. Defaults to all your trading accounts")
+                                .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("performance")
+                        .about("Show the time-weighted and money-weighted (XIRR) returns of your trading accounts from their snapshots")
+                        .arg(
+                            account_arg
+                                .clone()
+                                .help("The trading account to use by its 'id'. Defaults to all your trading accounts, each one and together")
+                                .required(false)
+                        )
+                        .arg(
+                            Arg::new("since")
+                                .long("since")
+                                .help("Start of the period, as YYYY-MM-DD")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("benchmark")
+                                .long("benchmark")
+                                .short('b')
+                                .help("The symbol to compare the returns with (e.g: 1rTCW8)")
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .short('f')
+                                .help("Output format")
+                                .default_value("table")
+                                .value_parser(["table", "json"])
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("tax")
+                .about("Compute the capital gains and dividends of your trading accounts for your tax return")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("report")
+                        .about("Realized gains with the weighted-average cost (PRU) method, dividends and PEA withdrawals of a year")
+                        .arg(
+                            account_arg
+                                .clone()
+                                .help("The trading account to use by its 'id'. Defaults to all your trading accounts (e.g: PEA and CTO)")
+                                .required(false)
+                        )
+                        .arg(
+                            Arg::new("year")
+                                .long("year")
+                                .short('y')
+                                .help("The tax year. Defaults to the previous year")
+                                .value_parser(clap::value_parser!(i32))
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .short('f')
+                                .help("Output format")
+                                .default_value("table")
+                                .value_parser(["table", "csv", "json"])
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .help("Output file path (defaults to stdout)")
+                        )
+                )
+        )
         .subcommand(
             Command::new("config")
                 .about("Configure BoursoBank/Boursorama CLI")
@@ -86,7 +215,7 @@ async fn main() -> Result<()> {
                             .arg(
                                 Arg::new("symbol")
                                 .long("symbol")
-                                .help("The symbol id of the order (e.g: '1rTCW8')")
+                                .help("The symbol id or ISIN of the order (e.g: '1rTCW8' or 'FR0010756098')")
                                 .required(true)
                             )
                             .arg(
@@ -94,10 +223,31 @@ async fn main() -> Result<()> {
                                 .short('q')
                                 .long("quantity")
                                 .help("The quantity of the order (e.g: '1')")
-                                .required(true)
+                                .required_unless_present("amount")
                                 .value_parser(clap::value_parser!(usize))
                             )
-                            // Price limit
+                            .arg(
+                                Arg::new("amount")
+                                .long("amount")
+                                .help("The amount to invest instead of a quantity (e.g: '300' for 300 EUR). The quantity will be the largest number of shares fitting in the amount, fees included")
+                                .conflicts_with("quantity")
+                                .value_parser(clap::value_parser!(f64))
+                            )
+                            .arg(
+                                Arg::new("carry-over")
+                                .long("carry-over")
+                                .help("Add the amount left over by the previous `--amount` order for this account and symbol, and save what is left of this one for the next order")
+                                .requires("amount")
+                                .action(ArgAction::SetTrue)
+                            )
+                            .arg(
+                                Arg::new("limit")
+                                .long("limit")
+                                .help("The price limit of the order (e.g: '29.5'). Defaults to the last price of the symbol")
+                                .value_parser(clap::value_parser!(f64))
+                            )
+                            .arg(key_arg.clone())
+                            .arg(force_arg.clone())
                             // Validity date
                             // TODO: handle other types of orders
                     )
@@ -110,6 +260,88 @@ async fn main() -> Result<()> {
                 )
                 .subcommand_required(true)
         )
+        .subcommand(
+            Command::new("rebalance")
+                .about("Compute and place the orders needed to reach your target allocations")
+                .arg(account_arg.clone().help("The trading account to rebalance by its 'id'"))
+                .arg(
+                    Arg::new("targets")
+                        .long("targets")
+                        .short('t')
+                        .help("The path to the TOML file describing your target allocations")
+                        .value_parser(clap::value_parser!(String))
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("mode")
+                        .long("mode")
+                        .help("Only buy with the available cash (e.g: for a PEA), or also sell the overweight positions")
+                        .default_value("buy-only")
+                        .value_parser(["buy-only", "full"])
+                )
+                .arg(
+                    Arg::new("cash")
+                        .long("cash")
+                        .help("The cash to invest. Defaults to the cash of the account")
+                        .value_parser(clap::value_parser!(f64))
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .help("Place the orders without asking for confirmation")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(force_arg.clone())
+        )
+        .subcommand(
+            Command::new("plan")
+                .about("Run your DCA (Dollar Cost Averaging) plan. Each buy is executed once per period, so it is safe to run it repeatedly from cron or a systemd timer")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("run")
+                        .about("Place the orders of the buys that are due")
+                        .arg(plan_arg.clone())
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only show the buys that are due, without logging in")
+                                .action(ArgAction::SetTrue)
+                        )
+                        .arg(force_arg.clone())
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Show the buys of the plan and their status for the current period")
+                        .arg(plan_arg.clone())
+                )
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Search the symbol id of an instrument by its ISIN, ticker or name. This action does not require authentication")
+                .arg(
+                    Arg::new("query")
+                    .help("The ISIN, ticker or name of the instrument (e.g: 'FR0010756098', 'CW8' or 'msci world')")
+                    .required(true)
+                )
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Watch the prices of a watchlist and raise alerts when they cross thresholds. This action does not require authentication")
+                .arg(
+                    Arg::new("watchlist")
+                        .long("watchlist")
+                        .help("Path to the watchlist TOML file (defaults to ~/.bourso/watchlist.toml)")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("once")
+                        .long("once")
+                        .help("Check the watchlist once and exit instead of polling, e.g. to run it with cron")
+                        .action(ArgAction::SetTrue)
+                )
+        )
         .subcommand(
             Command::new("quote")
                 .about("Get quote details for a given symbol over a timeframe. This action does not require authentication")
@@ -117,8 +349,16 @@ async fn main() -> Result<()> {
                 .arg(
                     Arg::new("symbol")
                     .long("symbol")
-                    .help("The symbol id of the stock (e.g: '1rTCW8')")
+                    .help("The symbol id or ISIN of the stock (e.g: '1rTCW8' or 'FR0010756098'). Several symbols separated by commas are compared, or exported with the export command")
                     .required(true)
+                    .value_delimiter(',')
+                )
+                .arg(
+                    Arg::new("concurrency")
+                    .long("concurrency")
+                    .help("Maximum number of quotes fetched at the same time when several symbols are given")
+                    .default_value("4")
+                    .value_parser(clap::value_parser!(usize))
                 )
                 .arg(
                     Arg::new("length")
@@ -139,11 +379,17 @@ async fn main() -> Result<()> {
                 .arg(
                     Arg::new("interval")
                     .long("interval")
-                    .help("The interval of the stock (e.g: '0' for default interval)")
-                    .default_value("0")
+                    .help("The interval between two quotes (e.g: '1d' for one quote per day)")
+                    .default_value("1d")
                     .value_parser([
-                        PossibleValue::new("0").help("Default interval")
-                        // TODO: add other intervals by documenting them on Boursorama
+                        PossibleValue::new("1m").help("1 minute, intraday"),
+                        PossibleValue::new("5m").help("5 minutes, intraday"),
+                        PossibleValue::new("15m").help("15 minutes, intraday"),
+                        PossibleValue::new("30m").help("30 minutes, intraday"),
+                        PossibleValue::new("1h").help("1 hour, intraday"),
+                        PossibleValue::new("1d").alias("0").help("1 day, default interval"),
+                        PossibleValue::new("1w").help("1 week"),
+                        PossibleValue::new("1mo").help("1 month"),
                     ])
                 )
                 .subcommand(
@@ -166,6 +412,133 @@ async fn main() -> Result<()> {
                     Command::new("last")
                         .about("Get the last value of the stock. Sets the `length` to 1 day and `interval` to 0")
                 )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export the dated OHLCV quotes of one or several symbols")
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .short('f')
+                                .help("Output format")
+                                .default_value("csv")
+                                .value_parser(["csv", "json", "ndjson"])
+                        )
+                        .arg(
+                            Arg::new("layout")
+                                .long("layout")
+                                .help("'long' for one row per date and symbol with the OHLCV values, 'wide' for one row per date with the closing price of each symbol")
+                                .default_value("long")
+                                .value_parser(["long", "wide"])
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .help("Output file path (defaults to stdout)")
+                                .required(false)
+                        )
+                        .arg(
+                            Arg::new("append")
+                                .long("append")
+                                .help("Add the quotes to the existing output file, replacing the quotes already exported for the same dates")
+                                .action(ArgAction::SetTrue)
+                                .requires("output")
+                        )
+                )
+                .subcommand(
+                    Command::new("chart")
+                        .about("Draw the quotes in the terminal")
+                        .arg(
+                            Arg::new("kind")
+                                .long("kind")
+                                .help("'line' for the closing prices, 'candle' for the open, high, low and close prices")
+                                .default_value("line")
+                                .value_parser(["line", "candle"])
+                        )
+                        .arg(
+                            Arg::new("sma")
+                                .long("sma")
+                                .help("Draw the simple moving average of the given window over the prices (e.g: '20')")
+                                .value_parser(clap::value_parser!(usize))
+                        )
+                        .arg(
+                            Arg::new("width")
+                                .long("width")
+                                .help("Width of the chart in characters (defaults to the terminal width)")
+                                .value_parser(clap::value_parser!(usize))
+                        )
+                        .arg(
+                            Arg::new("height")
+                                .long("height")
+                                .help("Height of the chart in lines")
+                                .default_value("20")
+                                .value_parser(clap::value_parser!(usize))
+                        )
+                )
+                .subcommand(
+                    Command::new("indicators")
+                        .about("Compute technical indicators over the quotes. Defaults to RSI 14, SMA 20, MACD, Bollinger bands 20, ATR 14, max drawdown and volatility")
+                        .arg(
+                            Arg::new("sma")
+                                .long("sma")
+                                .help("Simple moving averages windows (e.g: '50,200')")
+                                .value_parser(clap::value_parser!(usize))
+                                .value_delimiter(',')
+                                .num_args(1..)
+                        )
+                        .arg(
+                            Arg::new("ema")
+                                .long("ema")
+                                .help("Exponential moving averages windows (e.g: '12,26')")
+                                .value_parser(clap::value_parser!(usize))
+                                .value_delimiter(',')
+                                .num_args(1..)
+                        )
+                        .arg(
+                            Arg::new("rsi")
+                                .long("rsi")
+                                .help("Relative Strength Index window (e.g: '14')")
+                                .value_parser(clap::value_parser!(usize))
+                        )
+                        .arg(
+                            Arg::new("macd")
+                                .long("macd")
+                                .help("Moving Average Convergence Divergence (12, 26, 9)")
+                                .action(ArgAction::SetTrue)
+                        )
+                        .arg(
+                            Arg::new("bollinger")
+                                .long("bollinger")
+                                .help("Bollinger bands window, with 2 standard deviations (e.g: '20')")
+                                .value_parser(clap::value_parser!(usize))
+                        )
+                        .arg(
+                            Arg::new("atr")
+                                .long("atr")
+                                .help("Average True Range window (e.g: '14')")
+                                .value_parser(clap::value_parser!(usize))
+                        )
+                        .arg(
+                            Arg::new("drawdown")
+                                .long("drawdown")
+                                .help("Maximum drawdown over the quotes")
+                                .action(ArgAction::SetTrue)
+                        )
+                        .arg(
+                            Arg::new("volatility")
+                                .long("volatility")
+                                .help("Annualized volatility over the quotes")
+                                .action(ArgAction::SetTrue)
+                        )
+                        .arg(
+                            Arg::new("returns")
+                                .long("returns")
+                                .help("Returns over the last given numbers of quotes (e.g: '5,20')")
+                                .value_parser(clap::value_parser!(usize))
+                                .value_delimiter(',')
+                                .num_args(1..)
+                        )
+                )
         )
         .subcommand(
             Command::new("export")
@@ -205,10 +578,49 @@ async fn main() -> Result<()> {
                         )
                 )
         )
+        .subcommand(
+            Command::new("beneficiaries")
+                .about("Manage the external accounts you can transfer money to")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List your saved beneficiaries")
+                        .arg(account_arg.clone().help("The account to transfer from by its 'id'"))
+                )
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a beneficiary by its IBAN. You will be asked to validate it in the BoursoBank app")
+                        .arg(account_arg.clone().help("The account to transfer from by its 'id'"))
+                        .arg(
+                            Arg::new("name")
+                                .long("name")
+                                .help("The beneficiary name")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("iban")
+                                .long("iban")
+                                .help("The beneficiary IBAN")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("bic")
+                                .long("bic")
+                                .help("The beneficiary BIC. Defaults to the one of the IBAN bank")
+                        )
+                )
+        )
         .subcommand(
             Command::new("transfer")
                 .about("Make a transfer between your accounts")
-                .arg(account_arg.clone())
+                .subcommand_negates_reqs(true)
+                .arg(
+                    account_arg
+                        .clone()
+                        .required(false)
+                        .required_unless_present("batch"),
+                )
                 .arg(
                     Arg::new("to_account")
                         .long("to")
@@ -216,13 +628,19 @@ async fn main() -> Result<()> {
                             r#"The destination account id as an hexadecimal string (32 characters).
     You can get this info with the command `bourso accounts`"#
                         )
-                        .required(true)
+                        .required_unless_present_any(["to_iban", "batch"])
+                        .conflicts_with("to_iban")
+                )
+                .arg(
+                    Arg::new("to_iban")
+                        .long("to-iban")
+                        .help("The IBAN of the destination, an external beneficiary saved with `bourso beneficiaries add`")
                 )
                 .arg(
                     Arg::new("amount")
                         .long("amount")
                         .help("The amount to transfer")
-                        .required(true)
+                        .required_unless_present("batch")
                 )
                 .arg(
                     Arg::new("reason")
@@ -230,6 +648,118 @@ async fn main() -> Result<()> {
                         .help("The reason for the transfer (max 50 characters)")
                         .required(false)
                 )
+                .arg(
+                    Arg::new("on")
+                        .long("on")
+                        .help("Execute the transfer at this date (YYYY-MM-DD) instead of right away. With --every, the date of the first transfer")
+                        .value_parser(clap::value_parser!(NaiveDate))
+                )
+                .arg(
+                    Arg::new("every")
+                        .long("every")
+                        .help("Create a standing order repeating the transfer every week, month or quarter, from tomorrow or the --on date")
+                        .value_parser(["week", "month", "quarter"])
+                )
+                .arg(
+                    Arg::new("until")
+                        .long("until")
+                        .help("Date of the last transfer of the standing order (YYYY-MM-DD). Defaults to until cancelled")
+                        .value_parser(clap::value_parser!(NaiveDate))
+                        .requires("every")
+                )
+                .arg(
+                    Arg::new("batch")
+                        .long("batch")
+                        .help("The path to a CSV file of transfers between your accounts, with a 'from,to,amount,reason' header. Every transfer is checked before the first one is executed")
+                        .conflicts_with_all(["account", "to_account", "to_iban", "amount", "reason", "on", "every", "key", "check-limits"])
+                )
+                .arg(
+                    Arg::new("results")
+                        .long("results")
+                        .help("The path to the results file of the batch, used to resume it without repeating the completed transfers. Defaults to the batch path followed by the run and '.results.jsonl'")
+                        .requires("batch")
+                )
+                .arg(
+                    Arg::new("run")
+                        .long("run")
+                        .help("The run of the batch: its transfers are executed once per run. Defaults to the current month (YYYY-MM), so that a batch of monthly transfers can be run every month")
+                        .requires("batch")
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Check the transfer with Bourso and show its summary without confirming it")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("check-limits")
+                        .long("check-limits")
+                        .help("Check the amount against the daily and monthly ceilings of the source account before starting the transfer. Only immediate transfers are checked")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(key_arg.clone())
+                .arg(force_arg.clone())
+                .subcommand(
+                    Command::new("list")
+                        .about("List the transfers made from an account, with their status (pending, executed, rejected, cancelled)")
+                        .arg(account_arg.clone().help("The debited account id"))
+                        .arg(
+                            Arg::new("since")
+                                .long("since")
+                                .help("List the transfers from this date (YYYY-MM-DD). Defaults to 30 days ago")
+                                .value_parser(clap::value_parser!(NaiveDate))
+                        )
+                        .arg(
+                            Arg::new("until")
+                                .long("until")
+                                .help("List the transfers until this date (YYYY-MM-DD). Defaults to today")
+                                .value_parser(clap::value_parser!(NaiveDate))
+                        )
+                )
+                .subcommand(
+                    Command::new("limits")
+                        .about("Show the outgoing transfers ceilings of an account, and how much is still available")
+                        .arg(account_arg.clone().help("The debited account id"))
+                )
+                .subcommand(
+                    Command::new("standing-orders")
+                        .about("Manage your deferred transfers and standing orders")
+                        .subcommand_required(true)
+                        .arg_required_else_help(true)
+                        .subcommand(
+                            Command::new("list")
+                                .about("List the transfers of an account that are not executed yet")
+                                .arg(account_arg.clone().help("The debited account id"))
+                        )
+                        .subcommand(
+                            Command::new("cancel")
+                                .about("Cancel a deferred transfer or a standing order")
+                                .arg(account_arg.clone().help("The debited account id"))
+                                .arg(
+                                    Arg::new("id")
+                                        .help("The id of the standing order, as listed by `bourso transfer standing-orders list`")
+                                        .required(true)
+                                )
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("sweep")
+                .about("Move money between your accounts to apply your sweep rules, e.g. keep 1500 EUR on your current account and send the excess to your Livret A")
+                .arg(
+                    Arg::new("rules")
+                        .long("rules")
+                        .help("The path to the sweep rules file. Defaults to ~/.bourso/sweep.toml")
+                        .value_parser(clap::value_parser!(String))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Only show the transfers needed by the rules, without executing them")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(force_arg.clone())
         )
         .arg(
             Arg::new("credentials")
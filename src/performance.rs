@@ -0,0 +1,448 @@
+use anyhow::{Context, Result};
+use bourso_api::{
+    account::Account,
+    client::trade::{
+        tick::{Candle, TickLength},
+        AccountSummary,
+    },
+};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::settings::get_bourso_dir;
+
+const SNAPSHOTS_FILE: &str = "snapshots.jsonl";
+
+/// Value and contributions of a trading account at a point in time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub timestamp: DateTime<Local>,
+    pub account_id: String,
+    pub account_name: String,
+    /// Cash and positions value
+    pub total: f64,
+    /// Cash deposited since the account opening, its changes are the cash flows
+    pub contribution: f64,
+}
+
+impl Snapshot {
+    pub fn from_summary(account: &Account, summary: &AccountSummary) -> Snapshot {
+        Snapshot {
+            timestamp: Local::now(),
+            account_id: account.id.clone(),
+            account_name: account.name.clone(),
+            total: summary.total.value,
+            contribution: summary.contribution,
+        }
+    }
+}
+
+/// Snapshots of the trading accounts, stored as JSON lines
+pub struct SnapshotStore {
+    path: PathBuf,
+    snapshots: Vec<Snapshot>,
+}
+
+impl SnapshotStore {
+    /// Open the snapshots located in `~/.bourso/snapshots.jsonl`
+    #[cfg(not(tarpaulin_include))]
+    pub fn open_default() -> Result<SnapshotStore> {
+        SnapshotStore::open(get_bourso_dir()?.join(SNAPSHOTS_FILE))
+    }
+
+    /// Open the snapshots at the given path, it will be created on the first append
+    pub fn open(path: impl AsRef<Path>) -> Result<SnapshotStore> {
+        let path = path.as_ref().to_path_buf();
+        let mut snapshots = Vec::new();
+
+        if path.exists() {
+            let file = fs::File::open(&path)
+                .with_context(|| format!("Failed to open snapshots {}", path.display()))?;
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                snapshots.push(serde_json::from_str(&line).with_context(|| {
+                    format!(
+                        "Failed to parse line {} of snapshots {}",
+                        i + 1,
+                        path.display()
+                    )
+                })?);
+            }
+        }
+
+        Ok(SnapshotStore { path, snapshots })
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Append snapshots to the file
+    pub fn append(&mut self, snapshots: &[Snapshot]) -> Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open snapshots {}", self.path.display()))?;
+        for snapshot in snapshots {
+            writeln!(file, "{}", serde_json::to_string(snapshot)?)
+                .context("Failed to write snapshot")?;
+            self.snapshots.push(snapshot.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Value and contributions at the end of a day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub date: NaiveDate,
+    pub total: f64,
+    pub contribution: f64,
+}
+
+/// Daily points of the given accounts, summed across accounts
+///
+/// The last snapshot of each day is kept. An account without a snapshot on a day keeps the value
+/// of its previous snapshot, so that accounts snapshotted at different times can be summed.
+///
+/// # Arguments
+///
+/// * `snapshots` - Snapshots of any accounts, in any order
+/// * `account_ids` - Accounts to include
+pub fn points(snapshots: &[Snapshot], account_ids: &[&str]) -> Vec<Point> {
+    let mut by_account: BTreeMap<&str, BTreeMap<NaiveDate, &Snapshot>> = BTreeMap::new();
+    for snapshot in snapshots
+        .iter()
+        .filter(|s| account_ids.contains(&s.account_id.as_str()))
+    {
+        let days = by_account.entry(&snapshot.account_id).or_default();
+        let date = snapshot.timestamp.date_naive();
+        match days.get(&date) {
+            Some(existing) if existing.timestamp > snapshot.timestamp => {}
+            _ => {
+                days.insert(date, snapshot);
+            }
+        }
+    }
+
+    let mut dates = by_account
+        .values()
+        .flat_map(|days| days.keys().copied())
+        .collect::<Vec<_>>();
+    dates.sort();
+    dates.dedup();
+
+    dates
+        .into_iter()
+        .map(|date| {
+            let mut point = Point {
+                date,
+                total: 0.0,
+                contribution: 0.0,
+            };
+            for days in by_account.values() {
+                if let Some((_, snapshot)) = days.range(..=date).next_back() {
+                    point.total += snapshot.total;
+                    point.contribution += snapshot.contribution;
+                }
+            }
+            point
+        })
+        .collect()
+}
+
+/// Returns of an account, or of several accounts together, over a period
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Performance {
+    pub name: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub start_value: f64,
+    pub end_value: f64,
+    /// Deposits minus withdrawals over the period
+    pub net_flows: f64,
+    /// Time-weighted return, independent of the cash flows timing
+    pub twr: f64,
+    /// Money-weighted annual return (XIRR), None when it does not converge
+    pub xirr: Option<f64>,
+}
+
+impl Performance {
+    /// Compute the returns since a date
+    ///
+    /// The period starts at the last point on or before `since`, or at the first point after it.
+    /// Cash flows are assumed to happen at the start of the period between two points.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The account name, or e.g. "All accounts"
+    /// * `points` - Daily points sorted by date, see [`points`]
+    /// * `since` - Start of the period
+    ///
+    /// # Returns
+    ///
+    /// None if there are less than two points in the period
+    pub fn compute(name: &str, points: &[Point], since: NaiveDate) -> Option<Performance> {
+        let start = points.iter().rposition(|p| p.date <= since).unwrap_or(0);
+        let points = &points[start..];
+        let (first, last) = (points.first()?, points.last()?);
+        if points.len() < 2 {
+            return None;
+        }
+
+        let mut growth = 1.0;
+        let mut flows = vec![(first.date, -first.total)];
+        for pair in points.windows(2) {
+            let flow = pair[1].contribution - pair[0].contribution;
+            let invested = pair[0].total + flow;
+            if invested > 0.0 {
+                growth *= pair[1].total / invested;
+            }
+            if flow != 0.0 {
+                flows.push((pair[0].date, -flow));
+            }
+        }
+        flows.push((last.date, last.total));
+
+        Some(Performance {
+            name: name.to_string(),
+            start: first.date,
+            end: last.date,
+            start_value: first.total,
+            end_value: last.total,
+            net_flows: last.contribution - first.contribution,
+            twr: growth - 1.0,
+            xirr: xirr(&flows),
+        })
+    }
+}
+
+/// Annual rate for which the net present value of the cash flows is zero
+///
+/// # Arguments
+///
+/// * `flows` - Dated cash flows: negative when invested, positive when received
+pub fn xirr(flows: &[(NaiveDate, f64)]) -> Option<f64> {
+    let first = flows.iter().map(|(date, _)| *date).min()?;
+    let npv = |rate: f64| {
+        flows
+            .iter()
+            .map(|(date, amount)| {
+                let years = (*date - first).num_days() as f64 / 365.0;
+                amount / (1.0 + rate).powf(years)
+            })
+            .sum::<f64>()
+    };
+
+    // Bisection, the net present value decreasing with the rate when money is invested first
+    let (mut low, mut high) = (-0.9999, 100.0);
+    let (npv_low, npv_high) = (npv(low), npv(high));
+    if !npv_low.is_finite() || !npv_high.is_finite() || npv_low.signum() == npv_high.signum() {
+        return None;
+    }
+    for _ in 0..200 {
+        let middle = (low + high) / 2.0;
+        if npv(middle).signum() == npv_low.signum() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+/// Return of a benchmark over the period of a performance, from its daily candles
+pub fn benchmark_return(candles: &[Candle], start: NaiveDate, end: NaiveDate) -> Option<f64> {
+    let first = candles
+        .iter()
+        .rev()
+        .find(|c| c.time.date_naive() <= start)
+        .or_else(|| candles.first())?;
+    let last = candles.iter().rev().find(|c| c.time.date_naive() <= end)?;
+    if first.close == 0.0 {
+        return None;
+    }
+    Some(last.close / first.close - 1.0)
+}
+
+/// Shortest time frame of daily ticks covering the period, at most ten years
+pub fn benchmark_length(start: NaiveDate, end: NaiveDate) -> TickLength {
+    let days = (end - start).num_days();
+    [
+        TickLength::Month,
+        TickLength::Quarter,
+        TickLength::HalfYear,
+        TickLength::Year,
+        TickLength::FiveYears,
+    ]
+    .into_iter()
+    .find(|length| length.days() >= days)
+    .unwrap_or(TickLength::TenYears)
+}
+
+/// Render the performances as a table, with the return of the benchmark if any
+pub fn render_table(performances: &[Performance], benchmark: Option<(&str, f64)>) -> String {
+    let headers = [
+        "account",
+        "start",
+        "end",
+        "start value",
+        "end value",
+        "net flows",
+        "TWR",
+        "XIRR",
+    ];
+    let mut rows: Vec<Vec<String>> = vec![headers.iter().map(|h| h.to_string()).collect()];
+    for performance in performances {
+        rows.push(vec![
+            performance.name.clone(),
+            performance.start.to_string(),
+            performance.end.to_string(),
+            format!("{:.2}", performance.start_value),
+            format!("{:.2}", performance.end_value),
+            format!("{:.2}", performance.net_flows),
+            format_percent(performance.twr),
+            performance
+                .xirr
+                .map(format_percent)
+                .unwrap_or_else(|| "n/a".to_string()),
+        ]);
+    }
+
+    let widths = (0..headers.len())
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    let mut lines = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| match i {
+                    0..=2 => format!("{:<width$}", cell, width = widths[i]),
+                    _ => format!("{:>width$}", cell, width = widths[i]),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    if let Some((symbol, benchmark_return)) = benchmark {
+        lines.push(String::new());
+        lines.push(format!(
+            "Benchmark {}: {}",
+            symbol,
+            format_percent(benchmark_return)
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// e.g. "+12.34%"
+fn format_percent(rate: f64) -> String {
+    format!("{:+.2}%", rate * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn snapshot(account_id: &str, date: &str, total: f64, contribution: f64) -> Snapshot {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Snapshot {
+            timestamp: Local
+                .from_local_datetime(&date.and_hms_opt(18, 0, 0).unwrap())
+                .unwrap(),
+            account_id: account_id.to_string(),
+            account_name: account_id.to_uppercase(),
+            total,
+            contribution,
+        }
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_points() {
+        let snapshots = vec![
+            snapshot("pea", "2024-01-01", 1000.0, 1000.0),
+            snapshot("cto", "2024-01-02", 500.0, 500.0),
+            snapshot("pea", "2024-01-03", 1100.0, 1000.0),
+            snapshot("other", "2024-01-03", 1.0, 1.0),
+        ];
+        let points = points(&snapshots, &["pea", "cto"]);
+        assert_eq!(
+            points.iter().map(|p| p.total).collect::<Vec<_>>(),
+            vec![1000.0, 1500.0, 1600.0]
+        );
+        assert_eq!(points[2].contribution, 1500.0);
+    }
+
+    #[test]
+    fn test_performance() {
+        let snapshots = vec![
+            snapshot("pea", "2023-06-01", 800.0, 800.0),
+            snapshot("pea", "2024-01-01", 1000.0, 1000.0),
+            // +10%
+            snapshot("pea", "2024-07-01", 1100.0, 1000.0),
+            // 1000€ deposited, then -10%
+            snapshot("pea", "2025-01-01", 1890.0, 2000.0),
+        ];
+        let points = points(&snapshots, &["pea"]);
+        let performance = Performance::compute("PEA", &points, date("2024-01-01")).unwrap();
+
+        assert_eq!(performance.start, date("2024-01-01"));
+        assert_eq!(performance.net_flows, 1000.0);
+        assert!((performance.twr - -0.01).abs() < 1e-9);
+        // Less than the TWR: more money was invested during the losing half
+        let rate = performance.xirr.unwrap();
+        assert!(rate > -0.1 && rate < -0.05, "{}", rate);
+
+        assert_eq!(
+            Performance::compute("PEA", &points, date("2025-01-01")),
+            None
+        );
+
+        let flows = vec![(date("2024-01-01"), -1000.0), (date("2025-01-01"), 1100.0)];
+        let rate = xirr(&flows).unwrap();
+        // 2024 is a leap year, 366 days
+        assert!((rate - (1.1f64.powf(365.0 / 366.0) - 1.0)).abs() < 1e-6);
+
+        assert_eq!(xirr(&[(date("2024-01-01"), 1000.0)]), None);
+    }
+
+    #[test]
+    fn test_benchmark_return() {
+        let candle = |day: &str, close: f64| Candle {
+            time: Utc.from_utc_datetime(&date(day).and_hms_opt(0, 0, 0).unwrap()),
+            close,
+            ..Default::default()
+        };
+        let candles = vec![
+            candle("2023-12-29", 100.0),
+            candle("2024-01-02", 101.0),
+            candle("2024-12-31", 125.0),
+        ];
+        assert_eq!(
+            benchmark_return(&candles, date("2024-01-01"), date("2025-01-01")),
+            Some(0.25)
+        );
+    }
+}
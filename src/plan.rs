@@ -0,0 +1,427 @@
+use anyhow::{Context, Result};
+use bourso_api::{
+    account::AccountKind,
+    client::{
+        trade::{error::OrderError, order::OrderSide},
+        BoursoWebClient,
+    },
+};
+use chrono::{Datelike, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tracing::{error, info, warn};
+
+use crate::{
+    carry_over,
+    journal::{Journal, JournalEntry, JournalStatus},
+    settings::get_bourso_dir,
+};
+
+const PLAN_FILE: &str = "plan.toml";
+
+/// How often a planned buy is executed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Cadence {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+/// A recurring buy, e.g. "buy 300 EUR of 1rTCW8 every month"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlannedBuy {
+    /// Name identifying the buy in the journal, defaults to the symbol
+    pub name: Option<String>,
+    /// The trading account id
+    pub account: String,
+    /// The symbol id (e.g: '1rTCW8')
+    pub symbol: String,
+    /// Amount to invest, the quantity is computed from the last price and fees
+    pub amount: Option<f64>,
+    /// Quantity to buy
+    pub quantity: Option<usize>,
+    pub cadence: Cadence,
+    /// First day of the period the buy is due: the ISO weekday (1 = Monday) for a weekly
+    /// cadence, the day of the month for a monthly or quarterly cadence. Defaults to the
+    /// first day of the period
+    pub day: Option<u32>,
+    /// Keep what is left of the amount for the next period
+    #[serde(default)]
+    pub carry_over: bool,
+}
+
+/// A DCA (Dollar Cost Averaging) plan, read from a TOML file:
+///
+/// ```toml
+/// [[buy]]
+/// account = "a583f3c5842c34fb00b408486ef493e0"
+/// symbol = "1rTCW8"
+/// amount = 300.0
+/// cadence = "monthly"
+/// day = 5
+/// carry_over = true
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    #[serde(rename = "buy", default)]
+    pub buys: Vec<PlannedBuy>,
+}
+
+impl Cadence {
+    /// Identifier of the period containing the given date, e.g. `2026-W42` for a weekly cadence
+    pub fn period(&self, date: NaiveDate) -> String {
+        match self {
+            Cadence::Daily => date.format("%Y-%m-%d").to_string(),
+            Cadence::Weekly => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Cadence::Monthly => date.format("%Y-%m").to_string(),
+            Cadence::Quarterly => format!("{}-Q{}", date.year(), date.month0() / 3 + 1),
+        }
+    }
+
+    /// First day of the period containing the given date
+    pub fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Cadence::Daily => date,
+            Cadence::Weekly => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Cadence::Monthly => date.with_day(1).unwrap(),
+            Cadence::Quarterly => {
+                NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1).unwrap()
+            }
+        }
+    }
+}
+
+impl PlannedBuy {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.symbol)
+    }
+
+    /// Key of the buy in the journal for the period containing the given date
+    pub fn journal_key(&self, date: NaiveDate) -> String {
+        format!("plan:{}:{}", self.name(), self.cadence.period(date))
+    }
+
+    /// Date from which the buy is due in the period containing the given date
+    pub fn due_date(&self, date: NaiveDate) -> NaiveDate {
+        let start = self.cadence.period_start(date);
+        let offset = match (self.cadence, self.day) {
+            (Cadence::Daily, _) | (_, None) => 0,
+            (Cadence::Weekly, Some(day)) => day.clamp(1, 7) - 1,
+            // Clamp to the last day of the month, e.g. 31 is the 30th in April
+            (_, Some(day)) => day.clamp(1, days_in_month(start)) - 1,
+        };
+        start + Duration::days(offset as i64)
+    }
+
+    fn validate(&self) -> Result<()> {
+        match (self.amount, self.quantity) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "Planned buy '{}' must set either an amount or a quantity",
+                self.name()
+            )),
+        }
+    }
+}
+
+impl Plan {
+    /// Load the plan from the given path, or from `~/.bourso/plan.toml`
+    #[cfg(not(tarpaulin_include))]
+    pub fn load(path: Option<&str>) -> Result<Plan> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => get_bourso_dir()?.join(PLAN_FILE),
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read plan file {}", path.display()))?;
+        Plan::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Plan> {
+        let plan: Plan = toml::from_str(content).context("Failed to parse plan file")?;
+        for buy in plan.buys.iter() {
+            buy.validate()?;
+        }
+        Ok(plan)
+    }
+
+    /// Buys due on the given date that have not been completed yet for their current period
+    ///
+    /// Buys whose order was started but whose outcome is unknown are skipped too, unless forced,
+    /// see [`Journal::guard`].
+    pub fn due<'a>(
+        &'a self,
+        journal: &Journal,
+        date: NaiveDate,
+        force: bool,
+    ) -> Vec<&'a PlannedBuy> {
+        self.buys
+            .iter()
+            .filter(|buy| date >= buy.due_date(date))
+            .filter(|buy| journal.guard(&buy.journal_key(date), force).is_ok())
+            .collect()
+    }
+}
+
+/// Place the orders of the buys due on the given date and record them in the journal
+///
+/// Buys are skipped, and retried on the next run, while the market of their symbol is closed.
+/// A buy whose order may have been placed, e.g. when its confirmation failed, stays started in
+/// the journal and is not retried until forced.
+#[cfg(not(tarpaulin_include))]
+pub async fn run(
+    web_client: &BoursoWebClient,
+    plan: &Plan,
+    journal: &mut Journal,
+    date: NaiveDate,
+    force: bool,
+) -> Result<()> {
+    let due: Vec<PlannedBuy> = plan
+        .due(journal, date, force)
+        .into_iter()
+        .cloned()
+        .collect();
+    if due.is_empty() {
+        info!("No buy is due, nothing to do");
+        return Ok(());
+    }
+
+    let accounts = web_client.get_accounts(Some(AccountKind::Trading)).await?;
+
+    for buy in due {
+        let key = buy.journal_key(date);
+
+        let account = match accounts.iter().find(|a| a.id == buy.account) {
+            Some(account) => account,
+            None => {
+                error!("Account {} of buy '{}' not found", buy.account, buy.name());
+                journal.append(JournalEntry::new(
+                    &key,
+                    JournalStatus::Failed,
+                    serde_json::json!({ "error": "Account not found" }),
+                ))?;
+                continue;
+            }
+        };
+
+        let calendar = match web_client.exchange_calendar(&buy.symbol).await {
+            Ok(calendar) => calendar,
+            Err(e) => {
+                warn!("Skipping buy '{}': {}", buy.name(), e);
+                continue;
+            }
+        };
+        let now = Utc::now();
+        if !calendar.is_open(&now) {
+            info!(
+                "Market is closed for {} (next open: {}), buy '{}' will be retried on the next run",
+                buy.symbol,
+                calendar
+                    .next_open(&now)
+                    .map(|open| open.with_timezone(&Local).to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                buy.name()
+            );
+            continue;
+        }
+
+        if let Err(e) = journal.guard(&key, force) {
+            warn!("Skipping buy '{}': {}", buy.name(), e);
+            continue;
+        }
+
+        info!("Executing buy '{}' ({})", buy.name(), key);
+        journal.append(JournalEntry::new(
+            &key,
+            JournalStatus::Started,
+            serde_json::json!({ "account": account.id, "symbol": buy.symbol }),
+        ))?;
+
+        let result = match (buy.amount, buy.quantity) {
+            (Some(amount), _) => carry_over::order_by_amount(
+                web_client,
+                OrderSide::Buy,
+                account,
+                &buy.symbol,
+                amount,
+                None,
+                buy.carry_over,
+            )
+            .await
+            .map(|order| match order {
+                Some(order) => serde_json::json!({
+                    "orderId": order.order_id,
+                    "quantity": order.quantity,
+                    "cost": order.cost,
+                }),
+                None => serde_json::json!({ "carriedOver": amount }),
+            }),
+            (None, Some(quantity)) => web_client
+                .order(OrderSide::Buy, account, &buy.symbol, quantity, None)
+                .await
+                .map(|(order_id, price)| {
+                    serde_json::json!({
+                        "orderId": order_id,
+                        "quantity": quantity,
+                        "price": price,
+                    })
+                }),
+            (None, None) => unreachable!(),
+        };
+
+        match result {
+            Ok(details) => {
+                journal.append(JournalEntry::new(&key, JournalStatus::Completed, details))?
+            }
+            // The order may have been placed, keep it started so that it is not placed again
+            // without --force
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<OrderError>(),
+                    Some(OrderError::ConfirmationFailed)
+                ) =>
+            {
+                error!("Buy '{}' failed: {}", buy.name(), e);
+                warn!("The outcome of the order is unknown, check your orders before running the plan with --force");
+            }
+            Err(e) => {
+                error!("Buy '{}' failed: {}", buy.name(), e);
+                journal.append(JournalEntry::new(
+                    &key,
+                    JournalStatus::Failed,
+                    serde_json::json!({ "error": e.to_string() }),
+                ))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let next_month = if date.month() == 12 {
+        NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+    };
+    next_month
+        .unwrap()
+        .signed_duration_since(date.with_day(1).unwrap())
+        .num_days() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{JournalEntry, JournalStatus};
+
+    const PLAN: &str = r#"
+[[buy]]
+name = "world"
+account = "a583f3c5842c34fb00b408486ef493e0"
+symbol = "1rTCW8"
+amount = 300.0
+cadence = "monthly"
+day = 5
+carry_over = true
+
+[[buy]]
+account = "a583f3c5842c34fb00b408486ef493e0"
+symbol = "1rTPE500"
+quantity = 2
+cadence = "weekly"
+"#;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        let plan = Plan::parse(PLAN).unwrap();
+        assert_eq!(plan.buys.len(), 2);
+        assert_eq!(plan.buys[0].name(), "world");
+        assert_eq!(plan.buys[0].amount, Some(300.0));
+        assert!(plan.buys[0].carry_over);
+        assert_eq!(plan.buys[1].name(), "1rTPE500");
+        assert_eq!(plan.buys[1].cadence, Cadence::Weekly);
+        assert!(!plan.buys[1].carry_over);
+
+        let invalid = "[[buy]]\naccount = \"a\"\nsymbol = \"b\"\ncadence = \"daily\"\n";
+        assert!(Plan::parse(invalid).is_err());
+    }
+
+    #[test]
+    fn test_periods() {
+        let day = date(2026, 10, 18);
+        assert_eq!(Cadence::Daily.period(day), "2026-10-18");
+        assert_eq!(Cadence::Weekly.period(day), "2026-W42");
+        assert_eq!(Cadence::Monthly.period(day), "2026-10");
+        assert_eq!(Cadence::Quarterly.period(day), "2026-Q4");
+        // ISO weeks can belong to the previous year
+        assert_eq!(Cadence::Weekly.period(date(2027, 1, 1)), "2026-W53");
+
+        assert_eq!(Cadence::Weekly.period_start(day), date(2026, 10, 12));
+        assert_eq!(Cadence::Monthly.period_start(day), date(2026, 10, 1));
+        assert_eq!(
+            Cadence::Quarterly.period_start(date(2026, 8, 20)),
+            date(2026, 7, 1)
+        );
+    }
+
+    #[test]
+    fn test_due_date() {
+        let mut buy = Plan::parse(PLAN).unwrap().buys[0].clone();
+        assert_eq!(buy.due_date(date(2026, 10, 18)), date(2026, 10, 5));
+        buy.day = Some(31);
+        assert_eq!(buy.due_date(date(2026, 2, 10)), date(2026, 2, 28));
+        buy.cadence = Cadence::Weekly;
+        buy.day = Some(3);
+        assert_eq!(buy.due_date(date(2026, 10, 18)), date(2026, 10, 14));
+    }
+
+    #[test]
+    fn test_due_buys() {
+        let plan = Plan::parse(PLAN).unwrap();
+        let path = std::env::temp_dir().join(format!("bourso-plan-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut journal = Journal::open(&path).unwrap();
+
+        // The monthly buy is not due before the 5th
+        let due = plan.due(&journal, date(2026, 10, 2), false);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].symbol, "1rTPE500");
+
+        assert_eq!(plan.due(&journal, date(2026, 10, 18), false).len(), 2);
+
+        journal
+            .append(JournalEntry::new(
+                "plan:world:2026-10",
+                JournalStatus::Completed,
+                serde_json::Value::Null,
+            ))
+            .unwrap();
+        let due = plan.due(&journal, date(2026, 10, 18), false);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].symbol, "1rTPE500");
+        assert_eq!(plan.due(&journal, date(2026, 11, 5), false).len(), 2);
+
+        // A buy whose outcome is unknown is not due again, unless forced
+        journal
+            .append(JournalEntry::new(
+                "plan:1rTPE500:2026-W42",
+                JournalStatus::Started,
+                serde_json::Value::Null,
+            ))
+            .unwrap();
+        assert!(plan.due(&journal, date(2026, 10, 18), false).is_empty());
+        assert_eq!(plan.due(&journal, date(2026, 10, 18), true).len(), 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
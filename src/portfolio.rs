@@ -0,0 +1,185 @@
+use anyhow::Result;
+use bourso_api::client::trade::{
+    portfolio::{Portfolio, PortfolioTotals},
+    SummaryValue,
+};
+
+const HEADERS: [&str; 10] = [
+    "account",
+    "symbol",
+    "label",
+    "quantity",
+    "buyingPrice",
+    "last",
+    "amount",
+    "gainLoss",
+    "gainLossPercent",
+    "weight",
+];
+
+/// Render the portfolio positions as a table, followed by the totals of each account
+/// and the totals across all accounts
+pub fn render_table(portfolio: &Portfolio) -> String {
+    let mut rows: Vec<Vec<String>> = vec![HEADERS.iter().map(|h| h.to_string()).collect()];
+    for (account, position) in portfolio.positions() {
+        rows.push(vec![
+            account.account.name.clone(),
+            position.symbol.clone(),
+            position.label.clone(),
+            position.quantity.to_string(),
+            position.buying_price.to_string(),
+            position.last.to_string(),
+            position.amount.to_string(),
+            position.gain_loss.to_string(),
+            format_percent(&position.gain_loss_percent),
+            format!("{:.2}%", portfolio.weight(position) * 100.0),
+        ]);
+    }
+
+    let mut lines = format_rows(&rows);
+
+    lines.push(String::new());
+    for account in portfolio.accounts.iter() {
+        lines.push(format_totals(&account.account.name, &account.totals()));
+    }
+    if portfolio.accounts.len() > 1 {
+        lines.push(format_totals("Total", &portfolio.totals()));
+    }
+
+    lines.join("\n")
+}
+
+/// Render the portfolio positions as CSV, using the same separator as the transactions export.
+/// Fields containing the separator, such as some instrument labels, are quoted
+pub fn render_csv(portfolio: &Portfolio) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_writer(Vec::new());
+    writer.write_record(HEADERS)?;
+    for (account, position) in portfolio.positions() {
+        writer.write_record([
+            account.account.id.clone(),
+            position.symbol.clone(),
+            position.label.clone(),
+            format_value(&position.quantity),
+            format_value(&position.buying_price),
+            format_value(&position.last),
+            format_value(&position.amount),
+            format_value(&position.gain_loss),
+            format_value(&position.gain_loss_percent),
+            format!("{:.4}", portfolio.weight(position)),
+        ])?;
+    }
+    let content = String::from_utf8(writer.into_inner()?)?;
+    Ok(content.trim_end().to_string())
+}
+
+/// Render the portfolio, weights and totals as JSON
+pub fn render_json(portfolio: &Portfolio) -> serde_json::Result<String> {
+    let weights = portfolio
+        .positions()
+        .map(|(account, position)| {
+            serde_json::json!({
+                "account": account.account.id,
+                "symbol": position.symbol,
+                "weight": portfolio.weight(position),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "accounts": portfolio.accounts,
+        "weights": weights,
+        "totals": portfolio.totals(),
+    }))
+}
+
+/// Format a value with its decimals but without currency, for machine readable outputs
+fn format_value(value: &SummaryValue) -> String {
+    format!("{:.*}", value.decimals as usize, value.value)
+}
+
+fn format_percent(value: &SummaryValue) -> String {
+    format!("{}%", format_value(value))
+}
+
+fn format_totals(name: &str, totals: &PortfolioTotals) -> String {
+    format!(
+        "{}: valuation {:.2}, cash {:.2}, contribution {:.2}, gain/loss {:.2} ({:.2}%)",
+        name,
+        totals.valuation,
+        totals.cash,
+        totals.contribution,
+        totals.gain_loss,
+        totals.gain_loss_percent
+    )
+}
+
+/// Align the columns of the given rows, text is left aligned and numbers right aligned
+fn format_rows(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.first().map(|r| r.len()).unwrap_or(0);
+    let widths = (0..columns)
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i < 3 {
+                        format!("{:<width$}", cell, width = widths[i])
+                    } else {
+                        format!("{:>width$}", cell, width = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bourso_api::{
+        account::Account,
+        client::trade::{portfolio::PortfolioAccount, PositionSummary},
+    };
+
+    #[test]
+    fn test_render_csv_quotes_labels() {
+        let value = |value: f64| SummaryValue {
+            value,
+            decimals: 2,
+            currency: None,
+        };
+        let portfolio = Portfolio {
+            accounts: vec![PortfolioAccount {
+                account: Account {
+                    id: "a583f3c5842c34fb00b408486ef493e0".to_string(),
+                    ..Default::default()
+                },
+                summary: None,
+                positions: vec![PositionSummary {
+                    symbol: "1rTCW8".to_string(),
+                    label: "AMUNDI MSCI WORLD; UCITS ETF".to_string(),
+                    quantity: value(4.0),
+                    amount: value(100.0),
+                    ..Default::default()
+                }],
+            }],
+        };
+
+        let csv = render_csv(&portfolio).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], HEADERS.join(";"));
+        assert_eq!(
+            lines[1],
+            "a583f3c5842c34fb00b408486ef493e0;1rTCW8;\"AMUNDI MSCI WORLD; UCITS ETF\";4.00;0;0;100.00;0;0;1.0000"
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use anyhow::{Context, Result};
+use bourso_api::client::trade::tick::Candle;
+use std::collections::BTreeMap;
+
+/// Columns of the long layout, one row per date and symbol
+const LONG_COLUMNS: [&str; 7] = ["date", "symbol", "open", "high", "low", "close", "volume"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// `;` separated values, like the transactions export
+    Csv,
+    /// An array of objects
+    Json,
+    /// One object per line
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportLayout {
+    /// One row per date and symbol with the OHLCV values
+    Long,
+    /// One row per date with the closing price of each symbol in its own column
+    Wide,
+}
+
+/// Quotes of one or several symbols, keyed by date (and symbol for the long layout)
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteTable {
+    layout: ExportLayout,
+    columns: Vec<String>,
+    rows: BTreeMap<Vec<String>, BTreeMap<String, String>>,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => Err(anyhow::anyhow!("Invalid export format: {}", s)),
+        }
+    }
+}
+
+impl std::str::FromStr for ExportLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "long" => Ok(ExportLayout::Long),
+            "wide" => Ok(ExportLayout::Wide),
+            _ => Err(anyhow::anyhow!("Invalid export layout: {}", s)),
+        }
+    }
+}
+
+impl ExportLayout {
+    fn key_columns(&self) -> &'static [&'static str] {
+        match self {
+            ExportLayout::Long => &["date", "symbol"],
+            ExportLayout::Wide => &["date"],
+        }
+    }
+}
+
+impl QuoteTable {
+    pub fn new(layout: ExportLayout) -> QuoteTable {
+        let columns = match layout {
+            ExportLayout::Long => LONG_COLUMNS.iter().map(|c| c.to_string()).collect(),
+            ExportLayout::Wide => vec!["date".to_string()],
+        };
+        QuoteTable {
+            layout,
+            columns,
+            rows: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Add the candles of a symbol, replacing the rows already present for the same dates
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol id of the candles
+    /// * `candles` - The candles to add
+    /// * `intraday` - Whether the candles are dated with a time, or only with a day
+    pub fn add_candles(&mut self, symbol: &str, candles: &[Candle], intraday: bool) {
+        for candle in candles {
+            let date = if intraday {
+                candle.time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+            } else {
+                candle.time.format("%Y-%m-%d").to_string()
+            };
+
+            let mut values = BTreeMap::new();
+            values.insert("date".to_string(), date);
+            match self.layout {
+                ExportLayout::Long => {
+                    values.insert("symbol".to_string(), symbol.to_string());
+                    values.insert("open".to_string(), candle.open.to_string());
+                    values.insert("high".to_string(), candle.high.to_string());
+                    values.insert("low".to_string(), candle.low.to_string());
+                    values.insert("close".to_string(), candle.close.to_string());
+                    values.insert("volume".to_string(), candle.volume.to_string());
+                }
+                ExportLayout::Wide => {
+                    values.insert(symbol.to_string(), candle.close.to_string());
+                }
+            }
+            self.insert(values);
+        }
+    }
+
+    /// Add the rows of another table, replacing the rows already present with the same key
+    pub fn merge(&mut self, other: QuoteTable) {
+        for values in other.rows.into_values() {
+            self.insert(values);
+        }
+    }
+
+    /// Parse a table previously rendered with the given format and layout
+    pub fn parse(content: &str, format: ExportFormat, layout: ExportLayout) -> Result<QuoteTable> {
+        let mut table = QuoteTable::new(layout);
+
+        match format {
+            ExportFormat::Csv => {
+                let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+                let header = match lines.next() {
+                    Some(header) => header.split(';').map(|c| c.to_string()).collect::<Vec<_>>(),
+                    None => return Ok(table),
+                };
+                for column in header.iter() {
+                    if !table.columns.contains(column) {
+                        table.columns.push(column.clone());
+                    }
+                }
+                for line in lines {
+                    let values = header
+                        .iter()
+                        .cloned()
+                        .zip(line.split(';').map(|v| v.to_string()))
+                        .filter(|(_, v)| !v.is_empty())
+                        .collect::<BTreeMap<_, _>>();
+                    table.insert_checked(values)?;
+                }
+            }
+            ExportFormat::Json | ExportFormat::Ndjson => {
+                let objects: Vec<serde_json::Map<String, serde_json::Value>> = match format {
+                    ExportFormat::Json if content.trim().is_empty() => Vec::new(),
+                    ExportFormat::Json => {
+                        serde_json::from_str(content).context("Failed to parse the JSON file")?
+                    }
+                    _ => content
+                        .lines()
+                        .filter(|l| !l.trim().is_empty())
+                        .map(serde_json::from_str)
+                        .collect::<serde_json::Result<_>>()
+                        .context("Failed to parse the NDJSON file")?,
+                };
+                for object in objects {
+                    let values = object
+                        .into_iter()
+                        .filter(|(_, v)| !v.is_null())
+                        .map(|(k, v)| match v {
+                            serde_json::Value::String(s) => (k, s),
+                            v => (k, v.to_string()),
+                        })
+                        .collect::<BTreeMap<_, _>>();
+                    table.insert_checked(values)?;
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => {
+                let mut lines = vec![self.columns.join(";")];
+                for values in self.rows.values() {
+                    lines.push(
+                        self.columns
+                            .iter()
+                            .map(|c| values.get(c).cloned().unwrap_or_default())
+                            .collect::<Vec<_>>()
+                            .join(";"),
+                    );
+                }
+                lines.join("\n")
+            }
+            ExportFormat::Json => {
+                let objects = self.rows.values().map(|v| self.to_json(v)).collect();
+                serde_json::to_string_pretty(&serde_json::Value::Array(objects)).unwrap()
+            }
+            ExportFormat::Ndjson => self
+                .rows
+                .values()
+                .map(|v| self.to_json(v).to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    fn insert_checked(&mut self, values: BTreeMap<String, String>) -> Result<()> {
+        for column in self.layout.key_columns() {
+            if !values.contains_key(*column) {
+                return Err(anyhow::anyhow!(
+                    "Missing column '{}', is the file using the same layout?",
+                    column
+                ));
+            }
+        }
+        self.insert(values);
+        Ok(())
+    }
+
+    fn insert(&mut self, values: BTreeMap<String, String>) {
+        for column in values.keys() {
+            if !self.columns.contains(column) {
+                self.columns.push(column.clone());
+            }
+        }
+
+        let key = self
+            .layout
+            .key_columns()
+            .iter()
+            .map(|c| values.get(*c).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+        // Wide rows hold one column per symbol, update them one symbol at a time
+        self.rows.entry(key).or_default().extend(values);
+    }
+
+    fn to_json(&self, values: &BTreeMap<String, String>) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        for column in self.columns.iter() {
+            let value = match values.get(column) {
+                None => serde_json::Value::Null,
+                Some(value) if self.layout.key_columns().contains(&column.as_str()) => {
+                    serde_json::Value::String(value.clone())
+                }
+                Some(value) => serde_json::from_str::<serde_json::Number>(value)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.clone())),
+            };
+            object.insert(column.clone(), value);
+        }
+        serde_json::Value::Object(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn candle(day: u32, close: f64) -> Candle {
+        Candle {
+            time: Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+        }
+    }
+
+    #[test]
+    fn test_long_layout() {
+        let mut table = QuoteTable::new(ExportLayout::Long);
+        table.add_candles("1rTCW8", &[candle(4, 29.5), candle(5, 30.0)], false);
+        table.add_candles("1rPAI", &[candle(4, 180.0)], false);
+
+        assert_eq!(
+            table.render(ExportFormat::Csv),
+            "date;symbol;open;high;low;close;volume\n\
+             2024-03-04;1rPAI;180;180;180;180;100\n\
+             2024-03-04;1rTCW8;29.5;29.5;29.5;29.5;100\n\
+             2024-03-05;1rTCW8;30;30;30;30;100"
+        );
+
+        let ndjson = table.render(ExportFormat::Ndjson);
+        assert_eq!(
+            ndjson.lines().next().unwrap(),
+            r#"{"date":"2024-03-04","symbol":"1rPAI","open":180,"high":180,"low":180,"close":180,"volume":100}"#
+        );
+    }
+
+    #[test]
+    fn test_wide_layout() {
+        let mut table = QuoteTable::new(ExportLayout::Wide);
+        table.add_candles("1rTCW8", &[candle(4, 29.5), candle(5, 30.0)], false);
+        table.add_candles("1rPAI", &[candle(5, 180.0)], false);
+
+        assert_eq!(
+            table.render(ExportFormat::Csv),
+            "date;1rTCW8;1rPAI\n2024-03-04;29.5;\n2024-03-05;30;180"
+        );
+    }
+
+    #[test]
+    fn test_append_without_duplicates() {
+        for format in [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Ndjson] {
+            for layout in [ExportLayout::Long, ExportLayout::Wide] {
+                let mut existing = QuoteTable::new(layout);
+                existing.add_candles("1rTCW8", &[candle(4, 29.5), candle(5, 29.9)], false);
+                let content = existing.render(format);
+
+                let mut table = QuoteTable::parse(&content, format, layout).unwrap();
+                assert_eq!(table, existing);
+
+                // The quote of the 5th has been updated since the last export
+                let mut new = QuoteTable::new(layout);
+                new.add_candles("1rTCW8", &[candle(5, 30.0), candle(6, 30.5)], false);
+                table.merge(new);
+
+                assert_eq!(table.len(), 3);
+                let rendered = table.render(format);
+                assert!(!rendered.contains("29.9"));
+                assert!(rendered.contains("30.5"));
+            }
+        }
+
+        assert!(QuoteTable::parse(
+            "date;1rTCW8\n2024-03-04;29.5",
+            ExportFormat::Csv,
+            ExportLayout::Long
+        )
+        .is_err());
+        assert!(
+            QuoteTable::parse("", ExportFormat::Json, ExportLayout::Long)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
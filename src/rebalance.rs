@@ -0,0 +1,397 @@
+use anyhow::{Context, Result};
+use bourso_api::client::trade::order::OrderSide;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs};
+
+/// How the orders are computed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RebalanceMode {
+    /// Only buy with the available cash, positions are never sold (e.g: for a PEA)
+    BuyOnly,
+    /// Sell the overweight positions and buy the underweight ones
+    Full,
+}
+
+/// Target allocations, read from a TOML file:
+///
+/// ```toml
+/// [[target]]
+/// symbol = "1rTCW8"
+/// weight = 70
+///
+/// [[target]]
+/// symbol = "1rTPAEEM"
+/// weight = 30
+/// min_lot = 1
+/// ```
+///
+/// Weights are relative to each other, they don't have to sum to 100.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Targets {
+    #[serde(rename = "target", default)]
+    pub targets: Vec<Target>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Target {
+    /// The symbol id (e.g: '1rTCW8')
+    pub symbol: String,
+    pub weight: f64,
+    /// Orders are placed by multiples of this quantity
+    #[serde(default = "default_min_lot")]
+    pub min_lot: usize,
+}
+
+/// A position of the account, or a target symbol not held yet with a quantity of 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holding {
+    pub symbol: String,
+    pub quantity: f64,
+    /// Last price of the symbol
+    pub price: f64,
+}
+
+/// An order needed to reach the target allocations
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebalanceOrder {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: usize,
+    pub price: f64,
+    /// Fees estimated by Bourso, 0 until the order has been checked
+    pub fees: f64,
+    pub min_lot: usize,
+}
+
+fn default_min_lot() -> usize {
+    1
+}
+
+impl Targets {
+    /// Load the targets from the given TOML file
+    #[cfg(not(tarpaulin_include))]
+    pub fn load(path: &str) -> Result<Targets> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read targets file {}", path))?;
+        Targets::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Targets> {
+        let targets: Targets = toml::from_str(content).context("Failed to parse targets file")?;
+
+        if targets.targets.is_empty() {
+            return Err(anyhow::anyhow!("The targets file has no target"));
+        }
+        let mut symbols = HashSet::new();
+        for target in targets.targets.iter() {
+            if !symbols.insert(&target.symbol) {
+                return Err(anyhow::anyhow!("Duplicate target {}", target.symbol));
+            }
+            if target.weight.is_nan() || target.weight < 0.0 {
+                return Err(anyhow::anyhow!(
+                    "Invalid weight for target {}",
+                    target.symbol
+                ));
+            }
+            if target.min_lot == 0 {
+                return Err(anyhow::anyhow!(
+                    "Invalid min lot for target {}",
+                    target.symbol
+                ));
+            }
+        }
+        if targets.total_weight() <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "The sum of the target weights must be positive"
+            ));
+        }
+
+        Ok(targets)
+    }
+
+    fn total_weight(&self) -> f64 {
+        self.targets.iter().map(|t| t.weight).sum()
+    }
+
+    /// Weight of the given target, between 0 and 1
+    pub fn weight(&self, target: &Target) -> f64 {
+        target.weight / self.total_weight()
+    }
+}
+
+impl RebalanceOrder {
+    /// Gross value of the order
+    pub fn amount(&self) -> f64 {
+        self.quantity as f64 * self.price
+    }
+
+    /// Cash needed by a buy order (fees included), or cash released by a sell order (fees deducted)
+    pub fn cash_flow(&self) -> f64 {
+        match self.side {
+            OrderSide::Buy => -(self.amount() + self.fees),
+            OrderSide::Sell => self.amount() - self.fees,
+        }
+    }
+}
+
+/// Compute the orders needed to reach the target allocations
+///
+/// Positions that are not part of the targets are left untouched and ignored in the allocations.
+///
+/// # Arguments
+///
+/// * `targets` - The target allocations
+/// * `holdings` - The positions of the account, with a price for every target symbol
+/// * `cash` - The cash available to buy
+/// * `mode` - Whether positions can be sold
+///
+/// # Returns
+/// The sell orders followed by the buy orders. Buys never cost more than the available cash
+/// and the proceeds of the sells, fees excluded
+pub fn compute(
+    targets: &Targets,
+    holdings: &[Holding],
+    cash: f64,
+    mode: RebalanceMode,
+) -> Result<Vec<RebalanceOrder>> {
+    let mut positions = Vec::new();
+    for target in targets.targets.iter() {
+        let holding = holdings
+            .iter()
+            .find(|h| h.symbol == target.symbol)
+            .with_context(|| format!("No price for target {}", target.symbol))?;
+        if holding.price.is_nan() || holding.price <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Invalid price for target {}",
+                target.symbol
+            ));
+        }
+        positions.push((target, holding));
+    }
+
+    let invested: f64 = positions.iter().map(|(_, h)| h.quantity * h.price).sum();
+    let total = invested + cash;
+
+    let mut sells = Vec::new();
+    let mut buys = Vec::new();
+
+    match mode {
+        RebalanceMode::Full => {
+            for (target, holding) in positions.iter() {
+                let delta = targets.weight(target) * total - holding.quantity * holding.price;
+                let quantity = floor_to_lot(delta.abs() / holding.price, target.min_lot);
+                if delta < 0.0 {
+                    // Never sell more than held
+                    let held = floor_to_lot(holding.quantity, 1);
+                    sells.push(order(target, holding, OrderSide::Sell, quantity.min(held)));
+                } else {
+                    buys.push(order(target, holding, OrderSide::Buy, quantity));
+                }
+            }
+        }
+        RebalanceMode::BuyOnly => {
+            let deficits = positions
+                .iter()
+                .map(|(target, holding)| {
+                    (targets.weight(target) * total - holding.quantity * holding.price).max(0.0)
+                })
+                .collect::<Vec<_>>();
+            let total_deficit: f64 = deficits.iter().sum();
+            // Share the cash between the underweight positions when it can't fill all of them
+            let ratio = if total_deficit > cash {
+                cash / total_deficit
+            } else {
+                1.0
+            };
+
+            for ((target, holding), deficit) in positions.iter().zip(deficits) {
+                let quantity = floor_to_lot(deficit * ratio / holding.price, target.min_lot);
+                buys.push(order(target, holding, OrderSide::Buy, quantity));
+            }
+        }
+    }
+
+    let mut orders = sells;
+    orders.extend(buys);
+    fit_to_cash(&mut orders, cash);
+
+    Ok(orders)
+}
+
+/// Lower the buy orders, by their min lot and starting with the largest, until their cost
+/// fits in the available cash and the proceeds of the sell orders. Empty orders are removed.
+pub fn fit_to_cash(orders: &mut Vec<RebalanceOrder>, cash: f64) {
+    orders.retain(|o| o.quantity > 0);
+
+    while cash + orders.iter().map(|o| o.cash_flow()).sum::<f64>() < -1e-9 {
+        let largest = orders
+            .iter_mut()
+            .filter(|o| o.side == OrderSide::Buy)
+            .max_by(|a, b| a.amount().total_cmp(&b.amount()));
+        match largest {
+            Some(order) => {
+                // Fees are kept as is, the caller estimates them again for the new quantity
+                order.quantity = order.quantity.saturating_sub(order.min_lot);
+            }
+            None => break,
+        }
+        orders.retain(|o| o.quantity > 0);
+    }
+}
+
+/// Render the orders as a table
+pub fn render_table(orders: &[RebalanceOrder]) -> String {
+    let mut lines = vec![format!(
+        "{:<4}  {:<12}  {:>8}  {:>10}  {:>10}  {:>8}",
+        "side", "symbol", "quantity", "price", "amount", "fees"
+    )];
+    for order in orders {
+        lines.push(format!(
+            "{:<4}  {:<12}  {:>8}  {:>10.3}  {:>10.2}  {:>8.2}",
+            match order.side {
+                OrderSide::Buy => "buy",
+                OrderSide::Sell => "sell",
+            },
+            order.symbol,
+            order.quantity,
+            order.price,
+            order.amount(),
+            order.fees
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "Cash flow: {:.2}",
+        orders.iter().map(|o| o.cash_flow()).sum::<f64>()
+    ));
+    lines.join("\n")
+}
+
+fn order(target: &Target, holding: &Holding, side: OrderSide, quantity: usize) -> RebalanceOrder {
+    RebalanceOrder {
+        symbol: target.symbol.clone(),
+        side,
+        quantity,
+        price: holding.price,
+        fees: 0.0,
+        min_lot: target.min_lot,
+    }
+}
+
+/// Largest multiple of `lot` lower than or equal to `quantity`
+fn floor_to_lot(quantity: f64, lot: usize) -> usize {
+    // Round before flooring to avoid losing a lot to floating point errors (e.g: 300.0 / 30.0)
+    let lots = ((quantity / lot as f64 * 1e6).round() / 1e6).floor();
+    if lots < 1.0 {
+        return 0;
+    }
+    lots as usize * lot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(symbol: &str, quantity: f64, price: f64) -> Holding {
+        Holding {
+            symbol: symbol.to_string(),
+            quantity,
+            price,
+        }
+    }
+
+    const TARGETS: &str = r#"
+        [[target]]
+        symbol = "1rTCW8"
+        weight = 60
+
+        [[target]]
+        symbol = "1rTPAEEM"
+        weight = 40
+        min_lot = 5
+    "#;
+
+    #[test]
+    fn test_parse_targets() {
+        let targets = Targets::parse(TARGETS).unwrap();
+        assert_eq!(targets.targets.len(), 2);
+        assert_eq!(targets.targets[0].min_lot, 1);
+        assert_eq!(targets.targets[1].min_lot, 5);
+        assert!((targets.weight(&targets.targets[0]) - 0.6).abs() < 1e-9);
+
+        assert!(Targets::parse("").is_err());
+        assert!(Targets::parse(
+            "[[target]]\nsymbol = \"A\"\nweight = 1\n[[target]]\nsymbol = \"A\"\nweight = 1"
+        )
+        .is_err());
+        assert!(Targets::parse("[[target]]\nsymbol = \"A\"\nweight = 0").is_err());
+    }
+
+    #[test]
+    fn test_compute_buy_only() {
+        let targets = Targets::parse(TARGETS).unwrap();
+        // 1000 invested in the world ETF, 1000 of cash: targets are 1200 / 800
+        let holdings = [
+            holding("1rTCW8", 2.0, 500.0),
+            holding("1rTPAEEM", 0.0, 20.0),
+        ];
+
+        let orders = compute(&targets, &holdings, 1000.0, RebalanceMode::BuyOnly).unwrap();
+        assert_eq!(orders.len(), 1);
+        // 200 of world is less than a share, 800 / 20 = 40 shares of emerging
+        assert_eq!(orders[0].symbol, "1rTPAEEM");
+        assert_eq!(orders[0].side, OrderSide::Buy);
+        assert_eq!(orders[0].quantity, 40);
+
+        // Less cash: targets are 900 / 600, world is overweight and can't be sold
+        let orders = compute(&targets, &holdings, 500.0, RebalanceMode::BuyOnly).unwrap();
+        assert_eq!(orders.len(), 1);
+        // The 600 deficit only gets 500, i.e. 25 shares which is a multiple of the min lot
+        assert_eq!(orders[0].quantity, 25);
+    }
+
+    #[test]
+    fn test_compute_full() {
+        let targets = Targets::parse(TARGETS).unwrap();
+        // 3000 invested in world, 0 in emerging: targets are 1800 / 1200
+        let holdings = [
+            holding("1rTCW8", 6.0, 500.0),
+            holding("1rTPAEEM", 0.0, 20.0),
+        ];
+
+        let orders = compute(&targets, &holdings, 0.0, RebalanceMode::Full).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].side, OrderSide::Sell);
+        assert_eq!(orders[0].quantity, 2);
+        assert_eq!(orders[1].side, OrderSide::Buy);
+        // 60 shares are needed but the sell only brings 1000, lowered by lots of 5
+        assert_eq!(orders[1].quantity, 50);
+    }
+
+    #[test]
+    fn test_fit_to_cash() {
+        let targets = Targets::parse(TARGETS).unwrap();
+        let holdings = [
+            holding("1rTCW8", 0.0, 500.0),
+            holding("1rTPAEEM", 0.0, 20.0),
+        ];
+        let mut orders = compute(&targets, &holdings, 1000.0, RebalanceMode::BuyOnly).unwrap();
+        assert_eq!(orders[0].quantity, 1);
+        assert_eq!(orders[1].quantity, 20);
+
+        // Fees don't fit anymore: the largest order is lowered first
+        orders[1].fees = 101.0;
+        fit_to_cash(&mut orders, 1000.0);
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].symbol, "1rTPAEEM");
+        assert_eq!(orders[0].quantity, 20);
+    }
+
+    #[test]
+    fn test_floor_to_lot() {
+        assert_eq!(floor_to_lot(10.0, 1), 10);
+        assert_eq!(floor_to_lot(300.0 / 30.0, 5), 10);
+        assert_eq!(floor_to_lot(14.9, 5), 10);
+        assert_eq!(floor_to_lot(4.9, 5), 0);
+    }
+}
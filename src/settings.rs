@@ -3,6 +3,7 @@ use directories::UserDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::prelude::*;
+use std::path::PathBuf;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Settings {
@@ -78,6 +79,15 @@ pub fn save_settings(settings: &Settings) -> Result<()> {
     Ok(())
 }
 
+/// Get the path to the `~/.bourso` directory holding the CLI files, create it if it doesn't exist
+#[cfg(not(tarpaulin_include))]
+pub fn get_bourso_dir() -> Result<PathBuf> {
+    let user_dirs = UserDirs::new().context("Failed to get user directories")?;
+    let path = user_dirs.home_dir().join(".bourso");
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
 pub fn init_logger() -> Result<()> {
     use std::io::IsTerminal;
     use std::{fs, io};
@@ -0,0 +1,424 @@
+use anyhow::{Context, Result};
+use bourso_api::{
+    account::Account,
+    client::{
+        transfer::{TransferOptions, TransferProgress},
+        BoursoWebClient,
+    },
+};
+use futures_util::{pin_mut, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::PathBuf};
+use tracing::{error, info, warn};
+
+use crate::{
+    journal::{Journal, JournalEntry, JournalStatus},
+    settings::get_bourso_dir,
+};
+
+const SWEEP_FILE: &str = "sweep.toml";
+
+/// Bourso refuses transfers below 10 EUR
+const MIN_TRANSFER_CENTS: isize = 1000;
+
+/// A rule keeping the balance of an account between two thresholds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SweepRule {
+    /// Name identifying the rule in the logs and the journal, defaults to the account id
+    pub name: Option<String>,
+    /// The account id
+    pub account: String,
+    /// Balance to keep on the account, the excess is sent to `excess_to`
+    pub keep: Option<f64>,
+    /// The account receiving the excess
+    pub excess_to: Option<String>,
+    /// Balance under which the account is topped up from `top_up_from`, back to `keep` (or to
+    /// `minimum` if `keep` is not set)
+    pub minimum: Option<f64>,
+    /// The account the top up is taken from
+    pub top_up_from: Option<String>,
+    /// The reason of the transfers (max 50 characters)
+    pub reason: Option<String>,
+}
+
+/// Sweep rules, read from a TOML file:
+///
+/// ```toml
+/// [[rule]]
+/// name = "current"
+/// account = "a583f3c5842c34fb00b408486ef493e0"
+/// keep = 1500.0
+/// excess_to = "b419f2d28010bafb331badef11bba51e"
+/// minimum = 500.0
+/// top_up_from = "c3d1e5f28010bafb331badef11bba51e"
+/// ```
+///
+/// Rules are applied in order, each one seeing the balances left by the previous ones.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SweepRules {
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<SweepRule>,
+}
+
+/// A transfer needed to apply a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movement {
+    /// Name of the rule
+    pub rule: String,
+    pub from: String,
+    pub to: String,
+    pub amount: f64,
+    pub reason: Option<String>,
+}
+
+impl SweepRule {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.account)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| Err(anyhow::anyhow!("Rule '{}' {}", self.name(), reason));
+        if self.excess_to.is_none() && self.top_up_from.is_none() {
+            return invalid("must set excess_to or top_up_from");
+        }
+        if self.excess_to.is_some() && self.keep.is_none() {
+            return invalid("must set the balance to keep with excess_to");
+        }
+        if self.top_up_from.is_some() && self.minimum.is_none() {
+            return invalid("must set the minimum balance with top_up_from");
+        }
+        if let (Some(keep), Some(minimum)) = (self.keep, self.minimum) {
+            if minimum > keep {
+                return invalid("must keep a balance above its minimum");
+            }
+        }
+        if self.excess_to.as_ref() == Some(&self.account)
+            || self.top_up_from.as_ref() == Some(&self.account)
+        {
+            return invalid("cannot transfer to its own account");
+        }
+        if self.reason.as_ref().is_some_and(|r| r.len() > 50) {
+            return invalid("has a reason longer than 50 characters");
+        }
+        Ok(())
+    }
+}
+
+impl SweepRules {
+    /// Load the rules from the given path, or from `~/.bourso/sweep.toml`
+    #[cfg(not(tarpaulin_include))]
+    pub fn load(path: Option<&str>) -> Result<SweepRules> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => get_bourso_dir()?.join(SWEEP_FILE),
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read sweep rules {}", path.display()))?;
+        SweepRules::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<SweepRules> {
+        let rules: SweepRules = toml::from_str(content).context("Failed to parse sweep rules")?;
+        for rule in rules.rules.iter() {
+            rule.validate()?;
+        }
+        Ok(rules)
+    }
+
+    /// Compute the transfers applying the rules to the balances of the accounts
+    ///
+    /// Transfers below the minimum amount accepted by Bourso are skipped, and a top up never
+    /// takes more than the balance of its source account.
+    ///
+    /// # Arguments
+    ///
+    /// * `accounts` - Your accounts, including every account used by the rules
+    ///
+    /// # Returns
+    ///
+    /// The transfers to execute, in order
+    pub fn compute(&self, accounts: &[Account]) -> Result<Vec<Movement>> {
+        let mut balances: HashMap<&str, isize> = accounts
+            .iter()
+            .map(|a| (a.id.as_str(), a.balance))
+            .collect();
+
+        let mut movements = Vec::new();
+        for rule in self.rules.iter() {
+            for id in [
+                Some(&rule.account),
+                rule.excess_to.as_ref(),
+                rule.top_up_from.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                if !balances.contains_key(id.as_str()) {
+                    anyhow::bail!(
+                        "Account {} of rule '{}' not found. Run `bourso accounts` to list your accounts",
+                        id,
+                        rule.name()
+                    );
+                }
+            }
+
+            let balance = balances[rule.account.as_str()];
+            let movement = match (&rule.excess_to, &rule.top_up_from) {
+                (Some(to), _) if balance > cents(rule.keep.unwrap()) => {
+                    Some((&rule.account, to, balance - cents(rule.keep.unwrap())))
+                }
+                (_, Some(from)) if balance < cents(rule.minimum.unwrap()) => {
+                    let target = cents(rule.keep.or(rule.minimum).unwrap());
+                    let available = balances[from.as_str()].max(0);
+                    Some((from, &rule.account, (target - balance).min(available)))
+                }
+                _ => None,
+            };
+
+            match movement {
+                Some((from, to, amount)) if amount >= MIN_TRANSFER_CENTS => {
+                    *balances.get_mut(from.as_str()).unwrap() -= amount;
+                    *balances.get_mut(to.as_str()).unwrap() += amount;
+                    movements.push(Movement {
+                        rule: rule.name().to_string(),
+                        from: from.clone(),
+                        to: to.clone(),
+                        amount: amount as f64 / 100.0,
+                        reason: rule.reason.clone(),
+                    });
+                }
+                Some((from, to, amount)) => info!(
+                    "Rule '{}': skipping the transfer of {:.2} from {} to {}, below the minimum of {:.2}",
+                    rule.name(),
+                    amount as f64 / 100.0,
+                    from,
+                    to,
+                    MIN_TRANSFER_CENTS as f64 / 100.0
+                ),
+                None => {}
+            }
+        }
+
+        Ok(movements)
+    }
+}
+
+fn cents(amount: f64) -> isize {
+    (amount * 100.0).round() as isize
+}
+
+pub fn render_table(movements: &[Movement], accounts: &[Account]) -> String {
+    let name = |id: &str| {
+        accounts
+            .iter()
+            .find(|a| a.id == id)
+            .map(|a| a.name.clone())
+            .unwrap_or_else(|| id.to_string())
+    };
+    let mut lines = vec![format!(
+        "{:<16}  {:<24}  {:<24}  {:>10}",
+        "rule", "from", "to", "amount"
+    )];
+    for movement in movements {
+        lines.push(format!(
+            "{:<16}  {:<24}  {:<24}  {:>10.2}",
+            movement.rule,
+            name(&movement.from),
+            name(&movement.to),
+            movement.amount
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Execute the transfers of a sweep and record each of them in the journal
+///
+/// The sweep stops at the first failed transfer, since the following ones were computed from
+/// the balances it would have left. A transfer that failed while being confirmed stays started
+/// in the journal, and the rule is not applied again until forced.
+#[cfg(not(tarpaulin_include))]
+pub async fn run(
+    web_client: &BoursoWebClient,
+    movements: &[Movement],
+    accounts: &[Account],
+    journal: &mut Journal,
+    force: bool,
+) -> Result<()> {
+    let today = chrono::Local::now().date_naive();
+
+    for movement in movements {
+        let account = |id: &str| accounts.iter().find(|a| a.id == id).unwrap().clone();
+        let key = format!("sweep:{}:{}", movement.rule, today);
+        let details = serde_json::json!({
+            "from": movement.from,
+            "to": movement.to,
+            "amount": movement.amount,
+        });
+
+        journal.guard(&key, force)?;
+        journal.append(JournalEntry::new(
+            &key,
+            JournalStatus::Started,
+            details.clone(),
+        ))?;
+
+        info!(
+            "Rule '{}': transferring {:.2} from {} to {}",
+            movement.rule, movement.amount, movement.from, movement.to
+        );
+        let stream = web_client.transfer_funds(
+            movement.amount,
+            account(&movement.from),
+            account(&movement.to),
+            TransferOptions {
+                reason: movement.reason.clone(),
+                ..Default::default()
+            },
+        );
+        pin_mut!(stream);
+
+        let mut result = Ok(None);
+        let mut step = TransferProgress::Validating;
+        while let Some(progress) = stream.next().await {
+            match progress {
+                Ok(TransferProgress::Completed(receipt)) => result = Ok(Some(receipt)),
+                Ok(progress) => step = progress,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        match result {
+            Ok(receipt) => {
+                info!(
+                    "Rule '{}': transfer of {:.2} from {} to {} successful ✅",
+                    movement.rule, movement.amount, movement.from, movement.to
+                );
+                let mut details = details;
+                details["receipt"] = serde_json::json!(receipt);
+                journal.append(JournalEntry::new(&key, JournalStatus::Completed, details))?;
+            }
+            // The transfer may have been confirmed, keep it started so that it is not executed
+            // again without --force
+            Err(e) if matches!(step, TransferProgress::ConfirmingTransfer) => {
+                error!("Rule '{}': transfer failed: {}", movement.rule, e);
+                warn!("The outcome of the transfer is unknown, check your accounts before running the sweep with --force");
+                return Err(e);
+            }
+            Err(e) => {
+                error!("Rule '{}': transfer failed: {}", movement.rule, e);
+                let mut details = details;
+                details["error"] = serde_json::json!(e.to_string());
+                journal.append(JournalEntry::new(&key, JournalStatus::Failed, details))?;
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+[[rule]]
+name = "current"
+account = "a583f3c5842c34fb00b408486ef493e0"
+keep = 1500.0
+excess_to = "b419f2d28010bafb331badef11bba51e"
+minimum = 500.0
+top_up_from = "c3d1e5f28010bafb331badef11bba51e"
+
+[[rule]]
+name = "joint"
+account = "d5e6f7a8842c34fb00b408486ef493e0"
+minimum = 200.0
+top_up_from = "b419f2d28010bafb331badef11bba51e"
+"#;
+
+    fn accounts(balances: &[(&str, isize)]) -> Vec<Account> {
+        balances
+            .iter()
+            .map(|(id, balance)| Account {
+                id: id.to_string(),
+                balance: *balance,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = SweepRules::parse(RULES).unwrap();
+        assert_eq!(rules.rules.len(), 2);
+        assert_eq!(rules.rules[0].keep, Some(1500.0));
+        assert_eq!(rules.rules[1].name(), "joint");
+
+        let invalid = "[[rule]]\naccount = \"a\"\nexcess_to = \"b\"\n";
+        assert!(SweepRules::parse(invalid).is_err());
+        let invalid =
+            "[[rule]]\naccount = \"a\"\nkeep = 100.0\nminimum = 200.0\ntop_up_from = \"b\"\n";
+        assert!(SweepRules::parse(invalid).is_err());
+    }
+
+    #[test]
+    fn test_compute_movements() {
+        let rules = SweepRules::parse(RULES).unwrap();
+        let current = "a583f3c5842c34fb00b408486ef493e0";
+        let livret = "b419f2d28010bafb331badef11bba51e";
+        let savings = "c3d1e5f28010bafb331badef11bba51e";
+        let joint = "d5e6f7a8842c34fb00b408486ef493e0";
+
+        // The excess of the current account funds the top up of the joint account
+        let movements = rules
+            .compute(&accounts(&[
+                (current, 182_050),
+                (livret, 0),
+                (savings, 1_000_000),
+                (joint, 5_000),
+            ]))
+            .unwrap();
+        assert_eq!(movements.len(), 2);
+        assert_eq!(
+            (
+                movements[0].from.as_str(),
+                movements[0].to.as_str(),
+                movements[0].amount
+            ),
+            (current, livret, 320.5)
+        );
+        assert_eq!(
+            (
+                movements[1].from.as_str(),
+                movements[1].to.as_str(),
+                movements[1].amount
+            ),
+            (livret, joint, 150.0)
+        );
+
+        // Topped up back to the balance to keep, and nothing below 10 EUR
+        let movements = rules
+            .compute(&accounts(&[
+                (current, 40_000),
+                (livret, 500),
+                (savings, 1_000_000),
+                (joint, 19_500),
+            ]))
+            .unwrap();
+        assert_eq!(movements.len(), 1);
+        assert_eq!(
+            (
+                movements[0].from.as_str(),
+                movements[0].to.as_str(),
+                movements[0].amount
+            ),
+            (savings, current, 1100.0)
+        );
+
+        assert!(rules.compute(&accounts(&[(current, 0)])).is_err());
+    }
+}
@@ -0,0 +1,555 @@
+use anyhow::Result;
+use bourso_api::{
+    account::Transaction,
+    client::trade::{order::AccountFiscality, PositionSummary},
+};
+use chrono::{Datelike, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+lazy_static! {
+    /// e.g. "ACHAT COMPTANT 10 AMUNDI MSCI WORLD" or "VTE 5 AIR LIQUIDE"
+    static ref TRADE_REGEX: Regex = Regex::new(
+        r"(?i)^(?P<side>ACHAT|ACH|VENTE|VTE)\.?(?:\s+(?:COMPTANT|CPT|ETRANGER|ETR)\.?)?\s+(?P<quantity>\d+(?:[.,]\d+)?)\s+(?P<label>.+?)\s*$"
+    )
+    .unwrap();
+    /// e.g. "COUPON AIR LIQUIDE" or "DIVIDENDE 10 AIR LIQUIDE"
+    static ref DIVIDEND_REGEX: Regex = Regex::new(
+        r"(?i)^(?:COUPONS?|DIVIDENDES?|DIV|CPN)\.?\s+(?:\d+(?:[.,]\d+)?\s+)?(?P<label>.+?)\s*$"
+    )
+    .unwrap();
+    static ref TRANSFER_REGEX: Regex =
+        Regex::new(r"(?i)^(?:VIR|VIREMENT|VERSEMENT|RETRAIT)\b").unwrap();
+}
+
+/// Relative difference above which a computed PRU is reported as different from Bourso's
+const PRU_TOLERANCE: f64 = 0.01;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Deposit,
+    Buy,
+    Sell,
+    Dividend,
+    Withdrawal,
+}
+
+/// An operation of a trading account, read from its transactions
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub date: NaiveDate,
+    pub kind: OperationKind,
+    /// Label of the instrument, empty for deposits and withdrawals
+    pub label: String,
+    pub quantity: f64,
+    /// Cash amount, fees included: negative for buys and withdrawals
+    pub amount: f64,
+}
+
+/// A sale and its gain computed with the weighted-average cost (PRU, prix de revient unitaire)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RealizedGain {
+    pub date: NaiveDate,
+    pub label: String,
+    pub quantity: f64,
+    /// Sale price net of fees (prix de cession)
+    pub proceeds: f64,
+    /// PRU times the quantity sold (prix d'acquisition)
+    pub cost_basis: f64,
+    pub gain: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Dividend {
+    pub date: NaiveDate,
+    pub label: String,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Withdrawal {
+    pub date: NaiveDate,
+    pub amount: f64,
+}
+
+/// A position left at the end of the year, with its PRU
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Holding {
+    pub label: String,
+    pub quantity: f64,
+    pub pru: f64,
+}
+
+/// Capital gains, dividends and withdrawals of a trading account over a year
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxReport {
+    pub year: i32,
+    pub account: String,
+    /// Gains realized in a PEA are only taxed on withdrawal
+    pub pea: bool,
+    pub sales: Vec<RealizedGain>,
+    pub dividends: Vec<Dividend>,
+    pub withdrawals: Vec<Withdrawal>,
+    /// Cash deposited up to the end of the year
+    pub contributions: f64,
+    pub holdings: Vec<Holding>,
+    /// Inconsistencies found in the history, e.g. a sale of more shares than bought
+    pub warnings: Vec<String>,
+}
+
+impl Operation {
+    /// Read the operation of a trading account transaction, `None` if it is not a trade,
+    /// a dividend or a cash transfer
+    pub fn from_transaction(transaction: &Transaction) -> Option<Operation> {
+        let date = NaiveDate::parse_from_str(&transaction.date_op, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&transaction.date_op, "%d/%m/%Y"))
+            .ok()?;
+        let label = transaction.label.trim();
+        let amount = transaction.amount;
+
+        if let Some(captures) = TRADE_REGEX.captures(label) {
+            let kind = match captures["side"].to_uppercase().as_str() {
+                "ACHAT" | "ACH" => OperationKind::Buy,
+                _ => OperationKind::Sell,
+            };
+            return Some(Operation {
+                date,
+                kind,
+                label: normalize_label(&captures["label"]),
+                quantity: captures["quantity"].replace(',', ".").parse().ok()?,
+                amount,
+            });
+        }
+        if let Some(captures) = DIVIDEND_REGEX.captures(label) {
+            return Some(Operation {
+                date,
+                kind: OperationKind::Dividend,
+                label: normalize_label(&captures["label"]),
+                quantity: 0.0,
+                amount,
+            });
+        }
+        if TRANSFER_REGEX.is_match(label) && amount != 0.0 {
+            return Some(Operation {
+                date,
+                kind: if amount > 0.0 {
+                    OperationKind::Deposit
+                } else {
+                    OperationKind::Withdrawal
+                },
+                label: String::new(),
+                quantity: 0.0,
+                amount,
+            });
+        }
+        None
+    }
+}
+
+impl TaxReport {
+    /// Compute the report of a year from the whole history of the account
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account name
+    /// * `operations` - Every operation since the account opening, in any order. Operations
+    ///   before the year are needed to compute the PRU of the positions sold during the year
+    /// * `year` - The tax year
+    /// * `pea` - Whether the account is a PEA
+    pub fn compute(account: &str, operations: &[Operation], year: i32, pea: bool) -> TaxReport {
+        let mut operations = operations.to_vec();
+        // Buys before sales on the same day
+        operations.sort_by_key(|o| (o.date, o.kind));
+
+        let mut report = TaxReport {
+            year,
+            account: account.to_string(),
+            pea,
+            ..Default::default()
+        };
+        // Quantity and total cost of each position
+        let mut positions: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+
+        for operation in operations.iter().filter(|o| o.date.year() <= year) {
+            let in_year = operation.date.year() == year;
+            match operation.kind {
+                OperationKind::Deposit => report.contributions += operation.amount,
+                OperationKind::Withdrawal if in_year => report.withdrawals.push(Withdrawal {
+                    date: operation.date,
+                    amount: -operation.amount,
+                }),
+                OperationKind::Withdrawal => {}
+                OperationKind::Buy => {
+                    let position = positions.entry(operation.label.clone()).or_default();
+                    position.0 += operation.quantity;
+                    position.1 += -operation.amount;
+                }
+                OperationKind::Sell => {
+                    let position = positions.entry(operation.label.clone()).or_default();
+                    if operation.quantity > position.0 + f64::EPSILON {
+                        report.warnings.push(format!(
+                            "{}: sold {} {} but only {} were bought in the history, the gain is overestimated",
+                            operation.date, operation.quantity, operation.label, position.0
+                        ));
+                    }
+                    let pru = if position.0 > 0.0 {
+                        position.1 / position.0
+                    } else {
+                        0.0
+                    };
+                    let sold = operation.quantity.min(position.0);
+                    let cost_basis = pru * sold;
+                    position.0 -= sold;
+                    position.1 -= cost_basis;
+
+                    if in_year {
+                        report.sales.push(RealizedGain {
+                            date: operation.date,
+                            label: operation.label.clone(),
+                            quantity: operation.quantity,
+                            proceeds: operation.amount,
+                            cost_basis,
+                            gain: operation.amount - cost_basis,
+                        });
+                    }
+                }
+                OperationKind::Dividend if in_year => report.dividends.push(Dividend {
+                    date: operation.date,
+                    label: operation.label.clone(),
+                    amount: operation.amount,
+                }),
+                OperationKind::Dividend => {}
+            }
+        }
+
+        report.holdings = positions
+            .into_iter()
+            .filter(|(_, (quantity, _))| *quantity > f64::EPSILON)
+            .map(|(label, (quantity, cost))| Holding {
+                label,
+                quantity,
+                pru: cost / quantity,
+            })
+            .collect();
+
+        report
+    }
+
+    /// Compare the computed positions with the positions held according to Bourso, a
+    /// difference usually meaning that the history is incomplete
+    pub fn check_positions(&mut self, positions: &[PositionSummary]) {
+        for holding in self.holdings.iter() {
+            let position = positions.iter().find(|p| {
+                let label = normalize_label(&p.label);
+                label.starts_with(&holding.label) || holding.label.starts_with(&label)
+            });
+            let position = match position {
+                Some(position) => position,
+                None => continue,
+            };
+            if (position.quantity.value - holding.quantity).abs() > f64::EPSILON {
+                self.warnings.push(format!(
+                    "{}: {} held according to the history, {} according to Bourso",
+                    holding.label, holding.quantity, position.quantity.value
+                ));
+            } else if position.buying_price.value > 0.0
+                && (holding.pru / position.buying_price.value - 1.0).abs() > PRU_TOLERANCE
+            {
+                self.warnings.push(format!(
+                    "{}: PRU of {:.4} computed from the history, {:.4} according to Bourso",
+                    holding.label, holding.pru, position.buying_price.value
+                ));
+            }
+        }
+    }
+
+    /// Sum of the sale prices, net of fees (prix de cession)
+    pub fn proceeds(&self) -> f64 {
+        self.sales.iter().map(|s| s.proceeds).sum()
+    }
+
+    /// Sum of the acquisition prices of the shares sold (prix d'acquisition)
+    pub fn cost_basis(&self) -> f64 {
+        self.sales.iter().map(|s| s.cost_basis).sum()
+    }
+
+    pub fn realized_gain(&self) -> f64 {
+        self.sales.iter().map(|s| s.gain).sum()
+    }
+
+    pub fn dividends_total(&self) -> f64 {
+        self.dividends.iter().map(|d| d.amount).sum()
+    }
+
+    pub fn withdrawals_total(&self) -> f64 {
+        self.withdrawals.iter().map(|w| w.amount).sum()
+    }
+}
+
+/// Render the report as text: the operations of the year, then a summary for the tax return
+///
+/// # Arguments
+///
+/// * `report` - The report to render
+/// * `fiscality` - Latent and realized gains according to Bourso, if known
+pub fn render_table(report: &TaxReport, fiscality: Option<&AccountFiscality>) -> String {
+    let mut lines = vec![format!(
+        "{} ({}) - {}",
+        report.account,
+        if report.pea { "PEA" } else { "CTO" },
+        report.year
+    )];
+
+    lines.push(String::new());
+    lines.push(format!(
+        "{:<10}  {:<32}  {:>10}  {:>12}  {:>12}  {:>12}",
+        "date", "sale", "quantity", "proceeds", "cost basis", "gain"
+    ));
+    for sale in report.sales.iter() {
+        lines.push(format!(
+            "{:<10}  {:<32}  {:>10}  {:>12.2}  {:>12.2}  {:>12.2}",
+            sale.date,
+            truncate(&sale.label, 32),
+            sale.quantity,
+            sale.proceeds,
+            sale.cost_basis,
+            sale.gain
+        ));
+    }
+    if !report.dividends.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "{:<10}  {:<32}  {:>12}",
+            "date", "dividend", "amount"
+        ));
+        for dividend in report.dividends.iter() {
+            lines.push(format!(
+                "{:<10}  {:<32}  {:>12.2}",
+                dividend.date,
+                truncate(&dividend.label, 32),
+                dividend.amount
+            ));
+        }
+    }
+
+    lines.push(String::new());
+    if report.pea {
+        lines.push(format!(
+            "Realized gains (not taxed while kept in the PEA): {:.2}",
+            report.realized_gain()
+        ));
+        lines.push(format!("Dividends: {:.2}", report.dividends_total()));
+        lines.push(format!(
+            "Withdrawals: {:.2} (contributions: {:.2})",
+            report.withdrawals_total(),
+            report.contributions
+        ));
+    } else {
+        lines.push("Form 2074 summary:".to_string());
+        lines.push(format!(
+            "  Sale prices, net of fees (prix de cession): {:.2}",
+            report.proceeds()
+        ));
+        lines.push(format!(
+            "  Acquisition prices, PRU method (prix d'acquisition): {:.2}",
+            report.cost_basis()
+        ));
+        lines.push(format!(
+            "  Net capital gain or loss: {:.2}",
+            report.realized_gain()
+        ));
+        lines.push(format!(
+            "Dividends (form 2042, box 2DC): {:.2}",
+            report.dividends_total()
+        ));
+    }
+    if let Some(fiscality) = fiscality {
+        lines.push(format!(
+            "According to Bourso: realized gains {:.2}, latent gains {:.2}",
+            fiscality.real_gl, fiscality.lat_gl
+        ));
+    }
+
+    for warning in report.warnings.iter() {
+        lines.push(format!("⚠️ {}", warning));
+    }
+
+    lines.join("\n")
+}
+
+/// Render the operations of the year as CSV, one line per sale, dividend or withdrawal
+pub fn render_csv(reports: &[TaxReport]) -> String {
+    let mut lines = vec!["account;kind;date;label;quantity;amount;costBasis;gain".to_string()];
+    for report in reports {
+        for sale in report.sales.iter() {
+            lines.push(format!(
+                "{};sale;{};{};{};{:.2};{:.2};{:.2}",
+                report.account,
+                sale.date,
+                sale.label,
+                sale.quantity,
+                sale.proceeds,
+                sale.cost_basis,
+                sale.gain
+            ));
+        }
+        for dividend in report.dividends.iter() {
+            lines.push(format!(
+                "{};dividend;{};{};;{:.2};;",
+                report.account, dividend.date, dividend.label, dividend.amount
+            ));
+        }
+        for withdrawal in report.withdrawals.iter() {
+            lines.push(format!(
+                "{};withdrawal;{};;;{:.2};;",
+                report.account, withdrawal.date, withdrawal.amount
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+pub fn render_json(reports: &[TaxReport]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(reports)?)
+}
+
+/// Uppercase label with collapsed whitespaces, to match the labels of the different sources
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+fn truncate(label: &str, width: usize) -> String {
+    label.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bourso_api::client::trade::SummaryValue;
+
+    fn transaction(date: &str, label: &str, amount: f64) -> Transaction {
+        Transaction {
+            date_op: date.to_string(),
+            label: label.to_string(),
+            amount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_operation_from_transaction() {
+        let buy = Operation::from_transaction(&transaction(
+            "2024-03-04",
+            "ACHAT COMPTANT 10 AMUNDI MSCI WORLD",
+            -5012.5,
+        ))
+        .unwrap();
+        assert_eq!(buy.kind, OperationKind::Buy);
+        assert_eq!(buy.label, "AMUNDI MSCI WORLD");
+        assert_eq!(buy.quantity, 10.0);
+        assert_eq!(buy.date, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+
+        let sell =
+            Operation::from_transaction(&transaction("04/03/2024", "VTE 2,5 Air Liquide", 450.0))
+                .unwrap();
+        assert_eq!(sell.kind, OperationKind::Sell);
+        assert_eq!(sell.label, "AIR LIQUIDE");
+        assert_eq!(sell.quantity, 2.5);
+
+        let dividend =
+            Operation::from_transaction(&transaction("2024-05-20", "COUPON AIR LIQUIDE", 32.1))
+                .unwrap();
+        assert_eq!(dividend.kind, OperationKind::Dividend);
+
+        let withdrawal =
+            Operation::from_transaction(&transaction("2024-06-01", "VIR SEPA M DOE", -1000.0))
+                .unwrap();
+        assert_eq!(withdrawal.kind, OperationKind::Withdrawal);
+
+        assert_eq!(
+            Operation::from_transaction(&transaction("2024-06-01", "FRAIS DE GARDE", -5.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_weighted_average_cost() {
+        let operations = [
+            transaction("2023-01-10", "VIR SEPA M DOE", 5000.0),
+            transaction("2023-01-11", "ACHAT COMPTANT 10 AIR LIQUIDE", -1010.0),
+            transaction("2023-06-11", "ACHAT COMPTANT 10 AIR LIQUIDE", -1210.0),
+            // PRU: 2220 / 20 = 111
+            transaction("2024-02-01", "VENTE COMPTANT 5 AIR LIQUIDE", 745.0),
+            transaction("2024-05-20", "COUPON AIR LIQUIDE", 42.5),
+            transaction("2025-02-01", "VENTE COMPTANT 5 AIR LIQUIDE", 800.0),
+        ]
+        .iter()
+        .filter_map(Operation::from_transaction)
+        .collect::<Vec<_>>();
+
+        let mut report = TaxReport::compute("CTO", &operations, 2024, false);
+        assert_eq!(report.sales.len(), 1);
+        assert_eq!(report.cost_basis(), 555.0);
+        assert_eq!(report.realized_gain(), 190.0);
+        assert_eq!(report.dividends_total(), 42.5);
+        assert_eq!(report.contributions, 5000.0);
+        assert_eq!(
+            report.holdings,
+            vec![Holding {
+                label: "AIR LIQUIDE".to_string(),
+                quantity: 15.0,
+                pru: 111.0
+            }]
+        );
+        assert!(report.warnings.is_empty());
+
+        let position = |quantity: f64, buying_price: f64| PositionSummary {
+            label: "Air Liquide".to_string(),
+            quantity: SummaryValue {
+                value: quantity,
+                ..Default::default()
+            },
+            buying_price: SummaryValue {
+                value: buying_price,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        report.check_positions(&[position(15.0, 111.2)]);
+        assert!(report.warnings.is_empty());
+        report.check_positions(&[position(15.0, 120.0)]);
+        assert_eq!(report.warnings.len(), 1);
+
+        let csv = render_csv(&[report]);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("CTO;sale;2024-02-01;AIR LIQUIDE;5;745.00;555.00;190.00")
+        );
+    }
+
+    #[test]
+    fn test_sale_without_history() {
+        let operations = [transaction(
+            "2024-02-01",
+            "VENTE COMPTANT 5 AIR LIQUIDE",
+            745.0,
+        )]
+        .iter()
+        .filter_map(Operation::from_transaction)
+        .collect::<Vec<_>>();
+
+        let report = TaxReport::compute("PEA", &operations, 2024, true);
+        assert_eq!(report.realized_gain(), 745.0);
+        assert_eq!(report.warnings.len(), 1);
+    }
+}
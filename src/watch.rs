@@ -0,0 +1,529 @@
+use anyhow::{Context, Result};
+use bourso_api::client::trade::{
+    calendar::ExchangeCalendar,
+    indicators,
+    tick::{TickLength, TickPeriod},
+};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{info, warn};
+
+use crate::{cache::MarketData, settings::get_bourso_dir};
+
+const WATCHLIST_FILE: &str = "watchlist.toml";
+const WATCH_STATE_FILE: &str = "watch-state.json";
+
+fn default_interval() -> u64 {
+    300
+}
+
+/// Conditions watched on a symbol, an alert is raised each time one of them becomes true
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Watch {
+    /// The symbol id (e.g: '1rTCW8')
+    pub symbol: String,
+    /// Alert when the price goes above this value
+    pub above: Option<f64>,
+    /// Alert when the price goes below this value
+    pub below: Option<f64>,
+    /// Alert when the price moves by this percentage since the previous close, up or down
+    pub change: Option<f64>,
+    /// Alert when the price crosses the simple moving average of this many days
+    pub sma: Option<usize>,
+}
+
+/// Where alerts are sent, in addition to the standard output
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Delivery {
+    /// Shell command run for each alert, with the `BOURSO_ALERT_SYMBOL`, `BOURSO_ALERT_PRICE`
+    /// and `BOURSO_ALERT_MESSAGE` environment variables set
+    pub command: Option<String>,
+    /// URL to which each alert is posted as JSON
+    pub webhook: Option<String>,
+}
+
+/// A watchlist, read from a TOML file:
+///
+/// ```toml
+/// # Seconds between two polls while the market is open
+/// interval = 300
+///
+/// [[watch]]
+/// symbol = "1rTCW8"
+/// above = 520.0
+/// below = 480.0
+/// change = 2.5
+/// sma = 50
+///
+/// [delivery]
+/// command = "notify-send Bourso \"$BOURSO_ALERT_MESSAGE\""
+/// webhook = "https://example.com/hooks/bourso"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Watchlist {
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(rename = "watch", default)]
+    pub watches: Vec<Watch>,
+    #[serde(default)]
+    pub delivery: Delivery,
+}
+
+/// An alert raised by a watch
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub symbol: String,
+    pub price: f64,
+    pub message: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Prices of a symbol at a poll
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Observation {
+    pub price: f64,
+    pub previous_close: f64,
+    /// Value of the moving average of the watch, if any
+    pub sma: Option<f64>,
+}
+
+/// Whether each condition was met at the previous poll, so that an alert is only raised
+/// when its condition becomes true instead of at every poll
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WatchState {
+    /// Keyed by condition, e.g. `1rTCW8:above:520`
+    conditions: BTreeMap<String, bool>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Watch {
+    fn validate(&self) -> Result<()> {
+        if self.above.is_none()
+            && self.below.is_none()
+            && self.change.is_none()
+            && self.sma.is_none()
+        {
+            return Err(anyhow::anyhow!(
+                "Watch of {} must set at least one of above, below, change or sma",
+                self.symbol
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the conditions of the watch and record them in the state
+    ///
+    /// # Returns
+    ///
+    /// The alerts of the conditions that became true since the previous check
+    pub fn check(&self, observation: &Observation, state: &mut WatchState) -> Vec<Alert> {
+        let price = observation.price;
+        let mut messages = Vec::new();
+
+        if let Some(above) = self.above {
+            let key = format!("{}:above:{}", self.symbol, above);
+            if state.becomes_true(&key, price >= above) {
+                messages.push(format!(
+                    "{} is above {:.2} at {:.2}",
+                    self.symbol, above, price
+                ));
+            }
+        }
+        if let Some(below) = self.below {
+            let key = format!("{}:below:{}", self.symbol, below);
+            if state.becomes_true(&key, price <= below) {
+                messages.push(format!(
+                    "{} is below {:.2} at {:.2}",
+                    self.symbol, below, price
+                ));
+            }
+        }
+        if let (Some(change), true) = (self.change, observation.previous_close > 0.0) {
+            let variation = (price / observation.previous_close - 1.0) * 100.0;
+            let key = format!("{}:change:{}", self.symbol, change);
+            if state.becomes_true(&key, variation.abs() >= change) {
+                messages.push(format!(
+                    "{} moved by {:+.2}% since the previous close, at {:.2}",
+                    self.symbol, variation, price
+                ));
+            }
+        }
+        if let (Some(window), Some(sma)) = (self.sma, observation.sma) {
+            // Only a change of side is a crossing, the first check only records the side
+            let key = format!("{}:sma:{}", self.symbol, window);
+            let above = price > sma;
+            if state
+                .update(&key, above)
+                .is_some_and(|previous| previous != above)
+            {
+                messages.push(format!(
+                    "{} crossed {} its SMA {} ({:.2}) at {:.2}",
+                    self.symbol,
+                    if above { "above" } else { "below" },
+                    window,
+                    sma,
+                    price
+                ));
+            }
+        }
+
+        messages
+            .into_iter()
+            .map(|message| Alert {
+                symbol: self.symbol.clone(),
+                price,
+                message,
+                time: Utc::now(),
+            })
+            .collect()
+    }
+}
+
+impl Watchlist {
+    /// Load the watchlist from the given path, or from `~/.bourso/watchlist.toml`
+    #[cfg(not(tarpaulin_include))]
+    pub fn load(path: Option<&str>) -> Result<Watchlist> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => get_bourso_dir()?.join(WATCHLIST_FILE),
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read watchlist file {}", path.display()))?;
+        Watchlist::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Watchlist> {
+        let watchlist: Watchlist =
+            toml::from_str(content).context("Failed to parse watchlist file")?;
+        for watch in watchlist.watches.iter() {
+            watch.validate()?;
+        }
+        Ok(watchlist)
+    }
+}
+
+impl WatchState {
+    /// Open the state located in `~/.bourso/watch-state.json`
+    #[cfg(not(tarpaulin_include))]
+    pub fn open_default() -> Result<WatchState> {
+        WatchState::open(get_bourso_dir()?.join(WATCH_STATE_FILE))
+    }
+
+    /// Open the state at the given path, it will be created on the first save
+    pub fn open(path: impl AsRef<Path>) -> Result<WatchState> {
+        let path = path.as_ref().to_path_buf();
+        let mut state = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read watch state {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse watch state {}", path.display()))?
+        } else {
+            WatchState::default()
+        };
+        state.path = Some(path);
+        Ok(state)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = self.path.as_ref() {
+            fs::write(path, serde_json::to_string_pretty(self)?)
+                .with_context(|| format!("Failed to write watch state {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Record whether a condition is met, returns whether it was met at the previous check
+    fn update(&mut self, key: &str, met: bool) -> Option<bool> {
+        self.conditions.insert(key.to_string(), met)
+    }
+
+    /// Record whether a condition is met, returns whether it is met now but was not at the previous check
+    fn becomes_true(&mut self, key: &str, met: bool) -> bool {
+        let previous = self.update(key, met);
+        met && previous != Some(true)
+    }
+}
+
+/// Poll the quotes of the watchlist and deliver the alerts
+///
+/// Symbols are only checked while their market is open. When every market is closed, the next
+/// poll waits for the next opening.
+///
+/// # Arguments
+///
+/// * `market_data` - The market data fetched with the client, no login is required
+/// * `watchlist` - The symbols and conditions to watch
+/// * `state` - The conditions met at the previous poll, saved after each poll
+/// * `once` - Poll only once, e.g. when run by cron
+#[cfg(not(tarpaulin_include))]
+pub async fn run(
+    market_data: &MarketData<'_>,
+    watchlist: &Watchlist,
+    state: &mut WatchState,
+    once: bool,
+) -> Result<()> {
+    let interval = std::time::Duration::from_secs(watchlist.interval.max(1));
+    // Moving averages are computed on daily quotes, once a day
+    let mut averages: HashMap<(String, usize), (NaiveDate, Option<f64>)> = HashMap::new();
+
+    loop {
+        let now = Utc::now();
+        let mut any_open = false;
+        let mut next_open: Option<DateTime<Utc>> = None;
+
+        let symbols = watchlist
+            .watches
+            .iter()
+            .map(|w| w.symbol.as_str())
+            .collect::<Vec<_>>();
+        let quotes = market_data.instrument_quotes(&symbols).await;
+
+        for (watch, (_, quote)) in watchlist.watches.iter().zip(quotes) {
+            let quote = match quote {
+                Ok(quote) => quote,
+                Err(e) => {
+                    warn!("Failed to get the quote of {}: {}", watch.symbol, e);
+                    continue;
+                }
+            };
+
+            let calendar = ExchangeCalendar::from_quote(&quote);
+            if !calendar.is_open(&now) {
+                if let Some(open) = calendar.next_open(&now) {
+                    let open = open.with_timezone(&Utc);
+                    next_open = Some(next_open.map_or(open, |next| next.min(open)));
+                }
+                continue;
+            }
+            any_open = true;
+
+            let sma = match watch.sma {
+                Some(window) => {
+                    let today = Local::now().date_naive();
+                    let key = (watch.symbol.clone(), window);
+                    match averages.get(&key) {
+                        Some((date, sma)) if *date == today => *sma,
+                        _ => {
+                            let sma = match moving_average(market_data, &watch.symbol, window).await
+                            {
+                                Ok(sma) => sma,
+                                Err(e) => {
+                                    warn!("Failed to get the SMA of {}: {}", watch.symbol, e);
+                                    None
+                                }
+                            };
+                            averages.insert(key, (today, sma));
+                            sma
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            let observation = Observation {
+                price: quote.last,
+                previous_close: quote.previous_close,
+                sma,
+            };
+            for alert in watch.check(&observation, state) {
+                deliver(&watchlist.delivery, &alert).await;
+            }
+        }
+
+        state.save()?;
+
+        if once {
+            if !any_open {
+                info!("Every market of the watchlist is closed");
+            }
+            return Ok(());
+        }
+
+        let wait = match (any_open, next_open) {
+            (false, Some(next_open)) => {
+                info!(
+                    "Every market of the watchlist is closed, waiting for the next open at {}",
+                    next_open.with_timezone(&Local)
+                );
+                (next_open - Utc::now()).to_std().unwrap_or(interval)
+            }
+            _ => interval,
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Last value of the simple moving average of the daily closing prices
+#[cfg(not(tarpaulin_include))]
+async fn moving_average(
+    market_data: &MarketData<'_>,
+    symbol: &str,
+    window: usize,
+) -> Result<Option<f64>> {
+    // About 5 trading days per week
+    let length = [
+        TickLength::Month,
+        TickLength::Quarter,
+        TickLength::HalfYear,
+        TickLength::Year,
+        TickLength::FiveYears,
+    ]
+    .into_iter()
+    .find(|length| length.days() * 5 / 7 > window as i64)
+    .unwrap_or(TickLength::TenYears);
+
+    let quotes = market_data
+        .get_ticks(symbol, length, TickPeriod::Daily)
+        .await?;
+    let closes = indicators::closes(&quotes.d.quote_tab);
+    Ok(indicators::last(&indicators::sma(&closes, window)))
+}
+
+/// Log the alert and send it with the configured deliveries
+#[cfg(not(tarpaulin_include))]
+async fn deliver(delivery: &Delivery, alert: &Alert) {
+    info!(
+        symbol = alert.symbol,
+        price = alert.price,
+        "🔔 {}",
+        alert.message
+    );
+
+    if let Some(command) = delivery.command.as_ref() {
+        let mut shell = if cfg!(windows) {
+            let mut shell = tokio::process::Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = tokio::process::Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let status = shell
+            .arg(command)
+            .env("BOURSO_ALERT_SYMBOL", &alert.symbol)
+            .env("BOURSO_ALERT_PRICE", alert.price.to_string())
+            .env("BOURSO_ALERT_MESSAGE", &alert.message)
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!("Alert command exited with {}", status),
+            Err(e) => warn!("Failed to run the alert command: {}", e),
+        }
+    }
+
+    if let Some(webhook) = delivery.webhook.as_ref() {
+        let response = reqwest::Client::new()
+            .post(webhook)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(alert).unwrap())
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => warn!("Alert webhook answered {}", response.status()),
+            Err(e) => warn!("Failed to call the alert webhook: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_watchlist() {
+        let watchlist = Watchlist::parse(
+            r#"
+            [[watch]]
+            symbol = "1rTCW8"
+            above = 520.0
+            sma = 50
+
+            [delivery]
+            webhook = "https://example.com/hooks/bourso"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(watchlist.interval, 300);
+        assert_eq!(watchlist.watches[0].above, Some(520.0));
+        assert_eq!(watchlist.watches[0].sma, Some(50));
+        assert_eq!(watchlist.delivery.command, None);
+
+        assert!(Watchlist::parse("[[watch]]\nsymbol = \"1rTCW8\"").is_err());
+    }
+
+    #[test]
+    fn test_alerts_are_edge_triggered() {
+        let watch = Watch {
+            symbol: "1rTCW8".to_string(),
+            above: Some(520.0),
+            change: Some(2.0),
+            ..Default::default()
+        };
+        let mut state = WatchState::default();
+        let mut check = |price: f64| -> Vec<String> {
+            let observation = Observation {
+                price,
+                previous_close: 512.0,
+                sma: None,
+            };
+            watch
+                .check(&observation, &mut state)
+                .into_iter()
+                .map(|alert| alert.message)
+                .collect()
+        };
+
+        assert!(check(515.0).is_empty());
+        assert_eq!(
+            check(521.0),
+            vec!["1rTCW8 is above 520.00 at 521.00".to_string()]
+        );
+        // Still above: no new alert
+        assert!(check(522.0).is_empty());
+        assert_eq!(
+            check(525.0),
+            vec!["1rTCW8 moved by +2.54% since the previous close, at 525.00".to_string()]
+        );
+        // Back under the threshold, then above again
+        assert!(check(519.0).is_empty());
+        assert_eq!(check(521.0).len(), 1);
+    }
+
+    #[test]
+    fn test_sma_crossing() {
+        let watch = Watch {
+            symbol: "1rTCW8".to_string(),
+            sma: Some(50),
+            ..Default::default()
+        };
+        let mut state = WatchState::default();
+        let mut check = |price: f64| {
+            let observation = Observation {
+                price,
+                previous_close: 0.0,
+                sma: Some(500.0),
+            };
+            watch.check(&observation, &mut state)
+        };
+
+        // The first check only records the side of the price
+        assert!(check(510.0).is_empty());
+        assert!(check(505.0).is_empty());
+        let alerts = check(495.0);
+        assert_eq!(
+            alerts[0].message,
+            "1rTCW8 crossed below its SMA 50 (500.00) at 495.00"
+        );
+        assert_eq!(check(501.0).len(), 1);
+    }
+}